@@ -0,0 +1,75 @@
+// Throughput of StateManager under concurrent Background-style load: every
+// job is started, receives a few worker results, is polled and finished.
+//
+//     cargo bench --bench state_manager
+
+use std::{
+    hint::black_box,
+    thread,
+    time::{Duration, Instant},
+};
+
+use basic_grpc_service_rust::{
+    sdk::basic::service::v1::{SomeServiceResponse, State},
+    state::StateManager,
+};
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+
+const JOBS_PER_THREAD: usize = 1_000;
+const RESULTS_PER_JOB: usize = 3;
+
+fn run_jobs(state: &StateManager, worker: usize, round: u64) {
+    for job in 0..JOBS_PER_THREAD {
+        let hash = format!("{}-{}-{}", round, worker, job);
+        state.start(&hash, State::Process);
+        for i in 0..RESULTS_PER_JOB {
+            state.add_result(
+                &hash,
+                SomeServiceResponse {
+                    id: i.to_string(),
+                    name: format!("service-{}", i),
+                    version: "1.1.2".to_string(),
+                    data: None,
+                },
+            );
+            black_box(state.get_state(&hash));
+        }
+        state.finish(&hash, State::Complete);
+    }
+}
+
+fn concurrent_background(c: &mut Criterion) {
+    let mut group = c.benchmark_group("state_manager");
+
+    for threads in [1, 4, 16] {
+        group.throughput(Throughput::Elements((threads * JOBS_PER_THREAD) as u64));
+        group.bench_with_input(
+            BenchmarkId::new("background_jobs", threads),
+            &threads,
+            |b, &threads| {
+                let state = StateManager::new();
+                let mut round = 0;
+                b.iter_custom(|iters| {
+                    let mut elapsed = Duration::ZERO;
+                    for _ in 0..iters {
+                        round += 1;
+                        let start = Instant::now();
+                        thread::scope(|scope| {
+                            for worker in 0..threads {
+                                let state = &state;
+                                scope.spawn(move || run_jobs(state, worker, round));
+                            }
+                        });
+                        elapsed += start.elapsed();
+                    }
+                    elapsed
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, concurrent_background);
+criterion_main!(benches);
@@ -1,9 +1,18 @@
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Generates `prost::Name` impls, so payloads can be matched against the
+    // `type_url` of the `Any` they arrive in.
+    let mut config = tonic_prost_build::Config::new();
+    config
+        .enable_type_names()
+        .type_name_domain(["."], "type.googleapis.com");
+
     tonic_prost_build::configure()
         .build_server(true)
+        .build_client(true)
+        .codec_path("crate::validation::ValidatingCodec")
         .out_dir("src/sdk")
         .file_descriptor_set_path("src/sdk/descriptor.bin")
-        .compile_protos(&["proto/basic/v1/basic.proto"], &["proto"])?;
+        .compile_with_config(config, &["proto/basic/v1/basic.proto"], &["proto"])?;
 
     Ok(())
 }
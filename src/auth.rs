@@ -0,0 +1,410 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    future::Future,
+    path::Path,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use http::HeaderMap;
+use jsonwebtoken::{
+    Algorithm, DecodingKey, Validation, decode, decode_header,
+    jwk::{Jwk, JwkSet},
+};
+use serde::Deserialize;
+use tonic::{Status, body::Body};
+use tower::{Layer, Service};
+
+use crate::{
+    error::ServiceError,
+    sdk::io::cloudevents::v1::cloud_event::{
+        CloudEventAttributeValue, cloud_event_attribute_value::Attr,
+    },
+};
+
+pub const AUTH_CONFIG_ENV: &str = "AUTH_CONFIG";
+
+// CloudEvent extension attribute naming the caller behind an event.
+pub const PRINCIPAL_ATTRIBUTE: &str = "principal";
+
+// Subscribe streams events caused by every caller, so unless `methods` says
+// otherwise it needs `SUBSCRIBE_SCOPE`. Without `SUBSCRIBE_ALL_SCOPE` a
+// subscriber only sees the events it caused itself.
+pub const SUBSCRIBE_METHOD: &str = "/basic.v1.BasicService/Subscribe";
+pub const SUBSCRIBE_SCOPE: &str = "subscribe";
+pub const SUBSCRIBE_ALL_SCOPE: &str = "subscribe:all";
+
+// Failure to load the auth config or the JWKS file it points at.
+#[derive(Debug)]
+pub enum AuthError {
+    Read {
+        path: String,
+        source: std::io::Error,
+    },
+    Parse {
+        path: String,
+        source: serde_json::Error,
+    },
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthError::Read { path, source } => write!(f, "cannot read {}: {}", path, source),
+            AuthError::Parse { path, source } => write!(f, "invalid JSON in {}: {}", path, source),
+        }
+    }
+}
+
+impl std::error::Error for AuthError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AuthError::Read { source, .. } => Some(source),
+            AuthError::Parse { source, .. } => Some(source),
+        }
+    }
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T, AuthError> {
+    let display = path.display().to_string();
+    let bytes = std::fs::read(path).map_err(|source| AuthError::Read {
+        path: display.clone(),
+        source,
+    })?;
+    serde_json::from_slice(&bytes).map_err(|source| AuthError::Parse {
+        path: display,
+        source,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthMethod {
+    ApiKey,
+    Jwt,
+}
+
+impl AuthMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuthMethod::ApiKey => "apikey",
+            AuthMethod::Jwt => "jwt",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    pub subject: String,
+    pub scopes: HashSet<String>,
+    pub method: AuthMethod,
+}
+
+impl Principal {
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.contains(scope)
+    }
+
+    // CloudEvent extension attributes identifying who caused an event.
+    pub fn attributes(&self) -> HashMap<String, CloudEventAttributeValue> {
+        HashMap::from([
+            (
+                PRINCIPAL_ATTRIBUTE.to_string(),
+                CloudEventAttributeValue {
+                    attr: Some(Attr::CeString(self.subject.clone())),
+                },
+            ),
+            (
+                "authmethod".to_string(),
+                CloudEventAttributeValue {
+                    attr: Some(Attr::CeString(self.method.as_str().to_string())),
+                },
+            ),
+        ])
+    }
+}
+
+pub fn principal_attributes<T>(
+    request: &tonic::Request<T>,
+) -> HashMap<String, CloudEventAttributeValue> {
+    request
+        .extensions()
+        .get::<Principal>()
+        .map(Principal::attributes)
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiKey {
+    pub key: String,
+    pub subject: String,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuthConfig {
+    #[serde(default)]
+    pub api_keys: Vec<ApiKey>,
+    pub jwks_file: Option<String>,
+    pub issuer: Option<String>,
+    pub audience: Option<String>,
+    // Signing algorithms accepted for JWTs. Empty accepts whatever `alg` each
+    // JWKS key declares; keys without one are then unusable.
+    #[serde(default)]
+    pub algorithms: Vec<Algorithm>,
+    // Full gRPC method paths mapped to the scopes a caller must hold.
+    #[serde(default)]
+    pub methods: HashMap<String, Vec<String>>,
+    // Path prefixes that are reachable without credentials.
+    #[serde(default = "default_public")]
+    pub public: Vec<String>,
+}
+
+fn default_public() -> Vec<String> {
+    vec!["/grpc.reflection.".to_string()]
+}
+
+#[derive(Debug, Deserialize)]
+struct Claims {
+    sub: String,
+    #[serde(default)]
+    scope: Option<String>,
+    #[serde(default)]
+    scp: Option<Vec<String>>,
+}
+
+#[derive(Clone)]
+pub struct Authenticator {
+    config: AuthConfig,
+    jwks: Option<JwkSet>,
+}
+
+impl Authenticator {
+    pub fn new(config: AuthConfig) -> Result<Self, AuthError> {
+        let jwks = match &config.jwks_file {
+            Some(path) => Some(read_json(Path::new(path))?),
+            None => None,
+        };
+        Ok(Self { config, jwks })
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, AuthError> {
+        Self::new(read_json(path.as_ref())?)
+    }
+
+    // Authentication is enabled by pointing AUTH_CONFIG at a JSON config file.
+    pub fn from_env() -> Result<Option<Self>, AuthError> {
+        match std::env::var(AUTH_CONFIG_ENV) {
+            Ok(path) => Ok(Some(Self::from_file(path)?)),
+            Err(_) => Ok(None),
+        }
+    }
+
+    pub fn authorize(
+        &self,
+        path: &str,
+        headers: &HeaderMap,
+    ) -> Result<Option<Principal>, ServiceError> {
+        if self.config.public.iter().any(|p| path.starts_with(p)) {
+            return Ok(None);
+        }
+
+        let principal = self.authenticate(headers)?;
+
+        let required = match self.config.methods.get(path) {
+            Some(required) => required.as_slice(),
+            None if path == SUBSCRIBE_METHOD => &[SUBSCRIBE_SCOPE.to_string()],
+            None => &[],
+        };
+        if let Some(missing) = required.iter().find(|s| !principal.has_scope(s)) {
+            return Err(ServiceError::PermissionDenied {
+                scope: missing.clone(),
+                method: path.to_string(),
+            });
+        }
+
+        Ok(Some(principal))
+    }
+
+    pub fn authenticate(&self, headers: &HeaderMap) -> Result<Principal, ServiceError> {
+        let value = headers
+            .get(http::header::AUTHORIZATION)
+            .ok_or_else(|| {
+                ServiceError::Unauthenticated("missing authorization metadata".to_string())
+            })?
+            .to_str()
+            .map_err(|_| {
+                ServiceError::Unauthenticated("malformed authorization metadata".to_string())
+            })?;
+
+        // Auth schemes are case-insensitive (RFC 9110).
+        let token = value
+            .split_once(' ')
+            .filter(|(scheme, _)| {
+                scheme.eq_ignore_ascii_case("Bearer") || scheme.eq_ignore_ascii_case("ApiKey")
+            })
+            .ok_or_else(|| {
+                ServiceError::Unauthenticated("unsupported authorization scheme".to_string())
+            })?
+            .1
+            .trim();
+
+        if let Some(key) = self
+            .config
+            .api_keys
+            .iter()
+            .find(|k| constant_time_eq(k.key.as_bytes(), token.as_bytes()))
+        {
+            return Ok(Principal {
+                subject: key.subject.clone(),
+                scopes: key.scopes.iter().cloned().collect(),
+                method: AuthMethod::ApiKey,
+            });
+        }
+
+        self.verify_jwt(token)
+    }
+
+    fn verify_jwt(&self, token: &str) -> Result<Principal, ServiceError> {
+        let jwks = self
+            .jwks
+            .as_ref()
+            .ok_or_else(|| ServiceError::Unauthenticated("invalid credentials".to_string()))?;
+
+        let header = decode_header(token)
+            .map_err(|_| ServiceError::Unauthenticated("invalid credentials".to_string()))?;
+        // Without a kid the key is only unambiguous when there is just one.
+        let jwk = match &header.kid {
+            Some(kid) => jwks.find(kid),
+            None if jwks.keys.len() == 1 => jwks.keys.first(),
+            None => {
+                return Err(ServiceError::Unauthenticated(
+                    "token has no key id".to_string(),
+                ));
+            }
+        }
+        .ok_or_else(|| ServiceError::Unauthenticated("unknown signing key".to_string()))?;
+        let key = DecodingKey::from_jwk(jwk)
+            .map_err(|_| ServiceError::Unauthenticated("unusable signing key".to_string()))?;
+
+        // The token header only picks among the algorithms we accept for this
+        // key, it never decides on its own.
+        let algorithms = self.algorithms(jwk);
+        if !algorithms.contains(&header.alg) {
+            return Err(ServiceError::Unauthenticated(
+                "unexpected signing algorithm".to_string(),
+            ));
+        }
+        let mut validation = Validation::new(header.alg);
+        validation.algorithms = algorithms;
+        match &self.config.audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+        if let Some(issuer) = &self.config.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+
+        let claims = decode::<Claims>(token, &key, &validation)
+            .map_err(|e| ServiceError::Unauthenticated(format!("invalid token: {}", e)))?
+            .claims;
+
+        let mut scopes: HashSet<String> = claims
+            .scope
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(str::to_string)
+            .collect();
+        scopes.extend(claims.scp.unwrap_or_default());
+
+        Ok(Principal {
+            subject: claims.sub,
+            scopes,
+            method: AuthMethod::Jwt,
+        })
+    }
+
+    fn algorithms(&self, jwk: &Jwk) -> Vec<Algorithm> {
+        let declared = jwk
+            .common
+            .key_algorithm
+            .and_then(|alg| alg.to_string().parse::<Algorithm>().ok());
+        match declared {
+            Some(alg)
+                if self.config.algorithms.is_empty() || self.config.algorithms.contains(&alg) =>
+            {
+                vec![alg]
+            }
+            Some(_) => Vec::new(),
+            None => self.config.algorithms.clone(),
+        }
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+// Tower layer that authenticates every request before it reaches a service
+// and stores the resulting `Principal` in the request extensions.
+#[derive(Clone, Default)]
+pub struct AuthLayer {
+    authenticator: Option<Arc<Authenticator>>,
+}
+
+impl AuthLayer {
+    pub fn new(authenticator: Option<Authenticator>) -> Self {
+        Self {
+            authenticator: authenticator.map(Arc::new),
+        }
+    }
+}
+
+impl<S> Layer<S> for AuthLayer {
+    type Service = AuthService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuthService {
+            inner,
+            authenticator: self.authenticator.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AuthService<S> {
+    inner: S,
+    authenticator: Option<Arc<Authenticator>>,
+}
+
+impl<S, B> Service<http::Request<B>> for AuthService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<Body>> + Send,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: http::Request<B>) -> Self::Future {
+        let Some(authenticator) = &self.authenticator else {
+            return Box::pin(self.inner.call(req));
+        };
+
+        match authenticator.authorize(req.uri().path(), req.headers()) {
+            Ok(principal) => {
+                if let Some(principal) = principal {
+                    req.extensions_mut().insert(principal);
+                }
+                Box::pin(self.inner.call(req))
+            }
+            Err(e) => Box::pin(async move { Ok(Status::from(e).into_http()) }),
+        }
+    }
+}
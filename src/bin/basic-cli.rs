@@ -0,0 +1,418 @@
+use std::{io::Write, path::PathBuf, time::Duration};
+
+use basic_grpc_service_rust::{
+    client::{BasicClient, ClientConfig, ClientError},
+    error, info,
+    sdk::{
+        basic::service::v1::{
+            BackgroundResponseEvent, HelloBatchRequest, HelloRequest, HelloResponseEvent, State,
+            SubscribeLagEvent, SubscribeRequest, TalkResponseEvent,
+        },
+        io::cloudevents::v1::{
+            CloudEvent,
+            cloud_event::{Data::ProtoData, cloud_event_attribute_value::Attr},
+        },
+    },
+    success, utils, warning,
+};
+use clap::{Parser, Subcommand, ValueEnum};
+use colored::Colorize;
+use prost_types::Timestamp;
+use serde_json::{Map, Value, json};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio_stream::StreamExt;
+use tonic::codec::CompressionEncoding;
+
+#[derive(Parser)]
+#[command(name = "basic-cli", about = "Command-line client for BasicService")]
+struct Cli {
+    #[arg(long, default_value = "https://127.0.0.1:50443")]
+    endpoint: String,
+    #[arg(long, default_value = "certs/local.crt")]
+    ca_cert: PathBuf,
+    #[arg(long, default_value = "localhost")]
+    domain: String,
+    #[arg(long, env = "BASIC_TOKEN")]
+    token: Option<String>,
+    #[arg(long, default_value_t = 30)]
+    timeout_secs: u64,
+    /// Compress requests and accept compressed responses.
+    #[arg(long, value_enum)]
+    compression: Option<Compression>,
+    /// Print machine-readable JSON instead of formatted output.
+    #[arg(long, global = true)]
+    json: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Compression {
+    Gzip,
+    Zstd,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Send a single greeting
+    Hello {
+        message: String,
+        /// Greet this name instead of the message
+        #[arg(long)]
+        name: Option<String>,
+        /// Preferred greeting language, e.g. de-CH
+        #[arg(long)]
+        locale: Option<String>,
+    },
+    /// Greet several names in one call
+    HelloBatch {
+        #[arg(required = true)]
+        names: Vec<String>,
+        /// Preferred greeting language, e.g. de-CH
+        #[arg(long)]
+        locale: Option<String>,
+    },
+    /// Start an interactive conversation
+    Talk,
+    /// Start background processes and follow their progress
+    Background {
+        #[arg(default_value_t = 3)]
+        processes: i64,
+        /// Attach to the job started with this key instead of starting a new one
+        #[arg(long)]
+        idempotency_key: Option<String>,
+    },
+    /// Follow every event the server emits
+    Subscribe {
+        /// Only show events of this type (repeatable)
+        #[arg(long = "type")]
+        types: Vec<String>,
+        /// Only show events from this source (repeatable)
+        #[arg(long = "source")]
+        sources: Vec<String>,
+        /// Only show events with this attribute value, as KEY=VALUE (repeatable)
+        #[arg(long = "attr", value_parser = parse_attribute)]
+        attributes: Vec<(String, String)>,
+    },
+}
+
+fn parse_attribute(value: &str) -> Result<(String, String), String> {
+    value
+        .split_once('=')
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .ok_or_else(|| format!("expected KEY=VALUE, got {}", value))
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    let config = ClientConfig {
+        endpoint: cli.endpoint.clone(),
+        ca_cert: Some(cli.ca_cert.clone()),
+        domain: cli.domain.clone(),
+        token: cli.token.clone(),
+        timeout: Duration::from_secs(cli.timeout_secs),
+        compression: cli.compression.map(|compression| match compression {
+            Compression::Gzip => CompressionEncoding::Gzip,
+            Compression::Zstd => CompressionEncoding::Zstd,
+        }),
+        ..Default::default()
+    };
+
+    let mut client = match BasicClient::connect(config).await {
+        Ok(client) => client,
+        Err(e) => error!("Failed to connect to {}: {}", cli.endpoint, e),
+    };
+
+    let result = match cli.command {
+        Command::Hello {
+            message,
+            name,
+            locale,
+        } => {
+            let request = HelloRequest {
+                message,
+                name,
+                locale,
+            };
+            hello(&mut client, request, cli.json).await
+        }
+        Command::Talk => talk(&mut client, cli.json).await,
+        Command::Background {
+            processes,
+            idempotency_key,
+        } => background(&mut client, processes, idempotency_key.as_deref(), cli.json).await,
+        Command::HelloBatch { names, locale } => {
+            hello_batch(&mut client, HelloBatchRequest { names, locale }, cli.json).await
+        }
+        Command::Subscribe {
+            types,
+            sources,
+            attributes,
+        } => {
+            let filter = SubscribeRequest {
+                types,
+                sources,
+                attributes: attributes.into_iter().collect(),
+            };
+            subscribe(&mut client, filter, cli.json).await
+        }
+    };
+
+    if let Err(e) = result {
+        error!("{}", e);
+    }
+}
+
+async fn hello(
+    client: &mut BasicClient,
+    request: HelloRequest,
+    json: bool,
+) -> Result<(), ClientError> {
+    let hello = client.hello(request).await?;
+    if json {
+        println!(
+            "{}",
+            cloud_event_json(&hello.cloud_event, hello_json(&hello.event))
+        );
+    } else {
+        success!("{}", hello.event.greeting);
+        info!(
+            "event {} ({})",
+            hello.cloud_event.id, hello.cloud_event.r#type
+        );
+    }
+    Ok(())
+}
+
+async fn hello_batch(
+    client: &mut BasicClient,
+    request: HelloBatchRequest,
+    json: bool,
+) -> Result<(), ClientError> {
+    for hello in client.hello_batch(request).await? {
+        if json {
+            println!(
+                "{}",
+                cloud_event_json(&hello.cloud_event, hello_json(&hello.event))
+            );
+        } else {
+            success!("{}", hello.event.greeting);
+        }
+    }
+    Ok(())
+}
+
+async fn talk(client: &mut BasicClient, json: bool) -> Result<(), ClientError> {
+    let mut talk = client.talk().await?;
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+
+    if !json {
+        info!("Talk session started. Say \"bye\" to end it.");
+    }
+
+    loop {
+        if !json {
+            print!("{} ", ">".blue().bold());
+            std::io::stdout().flush()?;
+        }
+
+        let Some(line) = lines.next_line().await? else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = talk.ask(line).await?;
+        if json {
+            println!(
+                "{}",
+                json!({
+                    "sequence": response.sequence,
+                    "answer": response.answer,
+                    "rule": response.rule,
+                    "fragment": response.fragment,
+                    "ended": response.ended,
+                })
+            );
+        } else {
+            println!("{} {}", "<".green().bold(), response.answer);
+        }
+
+        if response.ended {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+async fn background(
+    client: &mut BasicClient,
+    processes: i64,
+    idempotency_key: Option<&str>,
+    json: bool,
+) -> Result<(), ClientError> {
+    let mut updates = match idempotency_key {
+        Some(key) => client.background_with_key(processes, key).await?,
+        None => client.background(processes).await?,
+    };
+    let mut last = None;
+
+    while let Some(update) = updates.next().await {
+        let update = update?;
+        if json {
+            println!(
+                "{}",
+                cloud_event_json(&update.cloud_event, background_json(&update.event))
+            );
+        } else {
+            print!(
+                "\r[{}] {}/{} processes done ({})",
+                "o".blue().bold(),
+                update.event.responses.len(),
+                processes.max(0),
+                state_name(update.event.state)
+            );
+            std::io::stdout().flush()?;
+        }
+        last = Some(update.event);
+    }
+
+    if json {
+        return Ok(());
+    }
+    println!();
+
+    let Some(event) = last else {
+        warning!("Background stream ended without any update.");
+        return Ok(());
+    };
+    for response in &event.responses {
+        let protocol = response
+            .data
+            .as_ref()
+            .map(|d| d.value.as_str())
+            .unwrap_or("-");
+        info!("{} v{} via {}", response.name, response.version, protocol);
+    }
+    match State::try_from(event.state) {
+        Ok(State::Complete) => success!("Background processing complete."),
+        _ => warning!("Background finished in {}", state_name(event.state)),
+    }
+    Ok(())
+}
+
+async fn subscribe(
+    client: &mut BasicClient,
+    filter: SubscribeRequest,
+    json: bool,
+) -> Result<(), ClientError> {
+    let mut events = client.subscribe(filter).await?;
+    if !json {
+        info!("Listening for events. Press Ctrl+C to stop.");
+    }
+
+    while let Some(event) = events.next().await {
+        let event = event?;
+        if json {
+            println!("{}", cloud_event_json(&event, event_data_json(&event)));
+        } else if event.r#type == "io.basic.subscribe.lagged" {
+            let missed = utils::decode_cloud_event::<SubscribeLagEvent>(&event)
+                .map(|lag| lag.missed)
+                .unwrap_or_default();
+            warning!("Fell behind, {} events were dropped", missed);
+        } else {
+            println!(
+                "[{}] {} {} {}",
+                "*".blue().bold(),
+                event.r#type,
+                event.source,
+                event_data_json(&event)
+            );
+        }
+    }
+    Ok(())
+}
+
+// Decodes the payloads the server is known to emit; anything else is shown
+// by its type URL.
+fn event_data_json(event: &CloudEvent) -> Value {
+    let Some(ProtoData(any)) = &event.data else {
+        return Value::Null;
+    };
+    let type_url = any.type_url.as_str();
+    let decoded = if type_url.ends_with("HelloResponseEvent") {
+        utils::decode_cloud_event::<HelloResponseEvent>(event).map(|e| hello_json(&e))
+    } else if type_url.ends_with("BackgroundResponseEvent") {
+        utils::decode_cloud_event::<BackgroundResponseEvent>(event).map(|e| background_json(&e))
+    } else if type_url.ends_with("TalkResponseEvent") {
+        utils::decode_cloud_event::<TalkResponseEvent>(event).map(|e| {
+            json!({
+                "sequence": e.sequence,
+                "answer": e.answer,
+                "rule": e.rule,
+                "fragment": e.fragment,
+                "ended": e.ended,
+            })
+        })
+    } else if type_url.ends_with("SubscribeLagEvent") {
+        utils::decode_cloud_event::<SubscribeLagEvent>(event).map(|e| json!({ "missed": e.missed }))
+    } else {
+        return json!({ "typeUrl": type_url });
+    };
+    decoded.unwrap_or_else(|_| json!({ "typeUrl": type_url }))
+}
+
+fn state_name(state: i32) -> &'static str {
+    State::try_from(state)
+        .map(|s| s.as_str_name())
+        .unwrap_or("STATE_UNSPECIFIED")
+}
+
+fn timestamp_json(ts: &Option<Timestamp>) -> Value {
+    ts.as_ref()
+        .map(|ts| Value::String(ts.to_string()))
+        .unwrap_or(Value::Null)
+}
+
+fn hello_json(event: &HelloResponseEvent) -> Value {
+    json!({ "greeting": event.greeting })
+}
+
+fn background_json(event: &BackgroundResponseEvent) -> Value {
+    json!({
+        "state": state_name(event.state),
+        "startedAt": timestamp_json(&event.started_at),
+        "completedAt": timestamp_json(&event.completed_at),
+        "responses": event.responses.iter().map(|r| json!({
+            "id": r.id,
+            "name": r.name,
+            "version": r.version,
+            "data": r.data.as_ref().map(|d| json!({ "type": d.r#type, "value": d.value })),
+        })).collect::<Vec<_>>(),
+    })
+}
+
+// Renders a CloudEvent in the structured JSON format with the decoded payload.
+fn cloud_event_json(event: &CloudEvent, data: Value) -> Value {
+    let mut out = Map::new();
+    out.insert("specversion".into(), json!(event.spec_version));
+    out.insert("id".into(), json!(event.id));
+    out.insert("source".into(), json!(event.source));
+    out.insert("type".into(), json!(event.r#type));
+    for (key, value) in &event.attributes {
+        let value = match &value.attr {
+            Some(Attr::CeBoolean(b)) => json!(b),
+            Some(Attr::CeInteger(i)) => json!(i),
+            Some(Attr::CeString(s)) | Some(Attr::CeUri(s)) | Some(Attr::CeUriRef(s)) => json!(s),
+            Some(Attr::CeBytes(b)) => json!(b),
+            Some(Attr::CeTimestamp(ts)) => json!(ts.to_string()),
+            None => Value::Null,
+        };
+        out.insert(key.clone(), value);
+    }
+    out.insert("data".into(), data);
+    Value::Object(out)
+}
@@ -0,0 +1,27 @@
+use std::path::PathBuf;
+
+use basic_grpc_service_rust::{error, info, success, talk::transcript, warning};
+
+fn main() {
+    let Some(path) = std::env::args().nth(1).map(PathBuf::from) else {
+        error!("Usage: talk-replay <transcript.jsonl|transcript.pb>");
+    };
+
+    let entries = match transcript::load(&path) {
+        Ok(entries) => entries,
+        Err(e) => error!("Failed to read transcript {}: {}", path.display(), e),
+    };
+
+    info!("Replaying {} turns from {}", entries.len(), path.display());
+
+    let divergences = transcript::replay(&entries);
+    if divergences.is_empty() {
+        success!("Transcript replayed without divergence.");
+        return;
+    }
+
+    for divergence in &divergences {
+        warning!("{}", divergence);
+    }
+    error!("{} of {} turns diverged.", divergences.len(), entries.len());
+}
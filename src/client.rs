@@ -0,0 +1,388 @@
+use std::{fmt, path::PathBuf, pin::Pin, time::Duration};
+
+use futures_core::Stream;
+use prost::{Message, Name};
+use rustls::crypto::{CryptoProvider, ring};
+use tokio::sync::mpsc;
+use tokio_stream::{StreamExt, wrappers::ReceiverStream};
+use tonic::{
+    Code, Request, Status, Streaming,
+    codec::CompressionEncoding,
+    metadata::{MetadataValue, errors::InvalidMetadataValue},
+    transport::{Certificate, Channel, ClientTlsConfig, Endpoint},
+};
+use uuid::Uuid;
+
+use crate::idempotency::IDEMPOTENCY_KEY_HEADER;
+use crate::sdk::{
+    basic::{
+        service::v1::{
+            BackgroundRequest, BackgroundResponseEvent, HelloBatchRequest, HelloRequest,
+            HelloResponseEvent, SubscribeRequest, TalkRequest, TalkResponse,
+        },
+        v1::basic_service_client::BasicServiceClient,
+    },
+    io::cloudevents::v1::{CloudEvent, CloudEventBatch},
+};
+use crate::utils::{self, EventDecodeError};
+
+#[derive(Debug)]
+pub enum ClientError {
+    Io(std::io::Error),
+    Transport(tonic::transport::Error),
+    Status(Status),
+    // The token or idempotency key cannot be sent as metadata.
+    Metadata(InvalidMetadataValue),
+    Decode(EventDecodeError),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Io(e) => write!(f, "io error: {}", e),
+            ClientError::Transport(e) => write!(f, "transport error: {}", e),
+            ClientError::Status(s) => write!(f, "{}: {}", s.code(), s.message()),
+            ClientError::Metadata(e) => write!(f, "invalid metadata: {}", e),
+            ClientError::Decode(e) => write!(f, "decode error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ClientError::Io(e) => Some(e),
+            ClientError::Transport(e) => Some(e),
+            ClientError::Status(e) => Some(e),
+            ClientError::Metadata(e) => Some(e),
+            ClientError::Decode(e) => Some(e),
+        }
+    }
+}
+
+impl From<std::io::Error> for ClientError {
+    fn from(e: std::io::Error) -> Self {
+        ClientError::Io(e)
+    }
+}
+
+impl From<tonic::transport::Error> for ClientError {
+    fn from(e: tonic::transport::Error) -> Self {
+        ClientError::Transport(e)
+    }
+}
+
+impl From<Status> for ClientError {
+    fn from(s: Status) -> Self {
+        ClientError::Status(s)
+    }
+}
+
+impl From<InvalidMetadataValue> for ClientError {
+    fn from(e: InvalidMetadataValue) -> Self {
+        ClientError::Metadata(e)
+    }
+}
+
+impl From<EventDecodeError> for ClientError {
+    fn from(e: EventDecodeError) -> Self {
+        ClientError::Decode(e)
+    }
+}
+
+// `hello("Ada")` greets with the server's default locale.
+impl From<&str> for HelloRequest {
+    fn from(message: &str) -> Self {
+        message.to_string().into()
+    }
+}
+
+impl From<String> for HelloRequest {
+    fn from(message: String) -> Self {
+        HelloRequest {
+            message,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    // `https://` endpoints use TLS, `http://` endpoints connect in plaintext.
+    pub endpoint: String,
+    // PEM file with the certificate(s) to trust. For mkcert certificates this
+    // is usually `$(mkcert -CAROOT)/rootCA.pem`.
+    pub ca_cert: Option<PathBuf>,
+    pub domain: String,
+    pub token: Option<String>,
+    pub connect_timeout: Duration,
+    // Deadline applied to unary calls.
+    pub timeout: Duration,
+    // Deadline applied to whole Talk and Background streams, if any.
+    pub stream_timeout: Option<Duration>,
+    pub hello_retries: u32,
+    pub retry_backoff: Duration,
+    // Compresses requests with this encoding and offers it for responses.
+    pub compression: Option<CompressionEncoding>,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: "https://127.0.0.1:50443".to_string(),
+            ca_cert: Some(PathBuf::from("certs/local.crt")),
+            domain: "localhost".to_string(),
+            token: None,
+            connect_timeout: Duration::from_secs(5),
+            timeout: Duration::from_secs(30),
+            stream_timeout: None,
+            hello_retries: 3,
+            retry_backoff: Duration::from_millis(200),
+            compression: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Decoded<T> {
+    pub cloud_event: CloudEvent,
+    pub event: T,
+}
+
+impl<T: Message + Name + Default> Decoded<T> {
+    pub fn from_cloud_event(cloud_event: Option<CloudEvent>) -> Result<Self, ClientError> {
+        let cloud_event = cloud_event.ok_or(EventDecodeError::MissingEvent)?;
+        let event = utils::decode_cloud_event(&cloud_event)?;
+        Ok(Self { cloud_event, event })
+    }
+}
+
+pub type BackgroundEvents =
+    Pin<Box<dyn Stream<Item = Result<Decoded<BackgroundResponseEvent>, ClientError>> + Send>>;
+
+pub type CloudEvents = Pin<Box<dyn Stream<Item = Result<CloudEvent, ClientError>> + Send>>;
+
+pub type HelloBatches =
+    Pin<Box<dyn Stream<Item = Result<Vec<Decoded<HelloResponseEvent>>, ClientError>> + Send>>;
+
+#[derive(Debug, Clone)]
+pub struct BasicClient {
+    inner: BasicServiceClient<Channel>,
+    config: ClientConfig,
+}
+
+impl BasicClient {
+    pub async fn connect(config: ClientConfig) -> Result<Self, ClientError> {
+        let mut endpoint =
+            Endpoint::from_shared(config.endpoint.clone())?.connect_timeout(config.connect_timeout);
+
+        if config.endpoint.starts_with("https://") {
+            // rustls needs a process-wide provider; ignore the error if the
+            // embedding application already installed one.
+            let _ = CryptoProvider::install_default(ring::default_provider());
+
+            let mut tls = ClientTlsConfig::new().domain_name(config.domain.clone());
+            tls = match &config.ca_cert {
+                Some(path) => {
+                    tls.ca_certificate(Certificate::from_pem(tokio::fs::read(path).await?))
+                }
+                None => tls.with_webpki_roots(),
+            };
+            endpoint = endpoint.tls_config(tls)?;
+        }
+
+        let channel = endpoint.connect().await?;
+        Ok(Self::from_channel(channel, config))
+    }
+
+    pub fn from_channel(channel: Channel, config: ClientConfig) -> Self {
+        let mut inner = BasicServiceClient::new(channel);
+        if let Some(encoding) = config.compression {
+            inner = inner.send_compressed(encoding).accept_compressed(encoding);
+        }
+        Self { inner, config }
+    }
+
+    pub fn config(&self) -> &ClientConfig {
+        &self.config
+    }
+
+    fn request<T>(&self, message: T, timeout: Option<Duration>) -> Result<Request<T>, ClientError> {
+        let mut request = Request::new(message);
+        if let Some(timeout) = timeout {
+            request.set_timeout(timeout);
+        }
+        if let Some(token) = &self.config.token {
+            let value = MetadataValue::try_from(format!("Bearer {}", token))?;
+            request.metadata_mut().insert("authorization", value);
+        }
+        Ok(request)
+    }
+
+    fn with_idempotency_key<T>(
+        mut request: Request<T>,
+        key: &str,
+    ) -> Result<Request<T>, ClientError> {
+        let value = MetadataValue::try_from(key)?;
+        request.metadata_mut().insert(IDEMPOTENCY_KEY_HEADER, value);
+        Ok(request)
+    }
+
+    // Retries transient failures with exponential backoff. All attempts share
+    // one idempotency key, so a retry returns the event the server already
+    // produced.
+    pub async fn hello(
+        &mut self,
+        hello: impl Into<HelloRequest>,
+    ) -> Result<Decoded<HelloResponseEvent>, ClientError> {
+        let hello = hello.into();
+        let key = Uuid::new_v4().to_string();
+        let mut backoff = self.config.retry_backoff;
+        let mut attempt = 0;
+
+        loop {
+            let request = self.request(hello.clone(), Some(self.config.timeout))?;
+            let request = Self::with_idempotency_key(request, &key)?;
+            match self.inner.hello(request).await {
+                Ok(response) => {
+                    return Decoded::from_cloud_event(response.into_inner().cloud_event);
+                }
+                Err(status) if attempt < self.config.hello_retries && is_retryable(&status) => {
+                    attempt += 1;
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                Err(status) => return Err(status.into()),
+            }
+        }
+    }
+
+    // One greeting per name, in order. Not retried: a batch has no
+    // idempotency key.
+    pub async fn hello_batch(
+        &mut self,
+        batch: HelloBatchRequest,
+    ) -> Result<Vec<Decoded<HelloResponseEvent>>, ClientError> {
+        let request = self.request(batch, Some(self.config.timeout))?;
+        decode_batch(self.inner.hello_batch(request).await?.into_inner())
+    }
+
+    // Sends every batch `batches` yields and streams back one result per
+    // batch, in order.
+    pub async fn hello_batch_stream(
+        &mut self,
+        batches: impl Stream<Item = HelloBatchRequest> + Send + 'static,
+    ) -> Result<HelloBatches, ClientError> {
+        let request = self.request(batches, self.config.stream_timeout)?;
+        let stream = self.inner.hello_batch_stream(request).await?.into_inner();
+        Ok(Box::pin(stream.map(|batch| decode_batch(batch?))))
+    }
+
+    pub async fn talk(&mut self) -> Result<TalkHandle, ClientError> {
+        let (tx, rx) = mpsc::channel(16);
+        let request = self.request(ReceiverStream::new(rx), self.config.stream_timeout)?;
+        let inbound = self.inner.talk(request).await?.into_inner();
+        Ok(TalkHandle {
+            tx,
+            inbound,
+            cloud_events: false,
+        })
+    }
+
+    pub async fn background(&mut self, processes: i64) -> Result<BackgroundEvents, ClientError> {
+        let request = self.request(BackgroundRequest { processes }, self.config.stream_timeout)?;
+        self.background_request(request).await
+    }
+
+    // Starts the job once per key; calling again with the same key, e.g.
+    // after the stream broke, follows the job that is already running.
+    pub async fn background_with_key(
+        &mut self,
+        processes: i64,
+        key: &str,
+    ) -> Result<BackgroundEvents, ClientError> {
+        let request = self.request(BackgroundRequest { processes }, self.config.stream_timeout)?;
+        self.background_request(Self::with_idempotency_key(request, key)?)
+            .await
+    }
+
+    async fn background_request(
+        &mut self,
+        request: Request<BackgroundRequest>,
+    ) -> Result<BackgroundEvents, ClientError> {
+        let stream = self.inner.background(request).await?.into_inner();
+        Ok(Box::pin(stream.map(|response| {
+            Decoded::from_cloud_event(response?.cloud_event)
+        })))
+    }
+
+    // Follows the server-wide event stream. No deadline is applied; the
+    // stream runs until it is dropped.
+    pub async fn subscribe(
+        &mut self,
+        filter: SubscribeRequest,
+    ) -> Result<CloudEvents, ClientError> {
+        let request = self.request(filter, None)?;
+        let stream = self.inner.subscribe(request).await?.into_inner();
+        Ok(Box::pin(stream.map(|response| {
+            Ok(response?
+                .cloud_event
+                .ok_or(EventDecodeError::MissingEvent)?)
+        })))
+    }
+}
+
+fn decode_batch(batch: CloudEventBatch) -> Result<Vec<Decoded<HelloResponseEvent>>, ClientError> {
+    batch
+        .events
+        .into_iter()
+        .map(|event| Decoded::from_cloud_event(Some(event)))
+        .collect()
+}
+
+fn is_retryable(status: &Status) -> bool {
+    matches!(
+        status.code(),
+        Code::Unavailable | Code::DeadlineExceeded | Code::ResourceExhausted | Code::Aborted
+    )
+}
+
+pub struct TalkHandle {
+    tx: mpsc::Sender<TalkRequest>,
+    inbound: Streaming<TalkResponse>,
+    cloud_events: bool,
+}
+
+impl TalkHandle {
+    // Asks for every answer to be wrapped in `TalkResponse.cloud_event`.
+    pub fn with_cloud_events(mut self, cloud_events: bool) -> Self {
+        self.cloud_events = cloud_events;
+        self
+    }
+
+    pub async fn send(&self, message: impl Into<String>) -> Result<(), ClientError> {
+        self.tx
+            .send(TalkRequest {
+                message: message.into(),
+                include_cloud_event: self.cloud_events,
+            })
+            .await
+            .map_err(|_| ClientError::Status(Status::cancelled("talk stream closed")))
+    }
+
+    pub async fn recv(&mut self) -> Result<Option<TalkResponse>, ClientError> {
+        Ok(self.inbound.message().await?)
+    }
+
+    pub async fn ask(&mut self, message: impl Into<String>) -> Result<TalkResponse, ClientError> {
+        self.send(message).await?;
+        self.recv()
+            .await?
+            .ok_or_else(|| ClientError::Status(Status::aborted("talk stream ended")))
+    }
+
+    // Closes the outbound half; remaining answers can still be received.
+    pub fn close(self) -> Streaming<TalkResponse> {
+        self.inbound
+    }
+}
@@ -0,0 +1,154 @@
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use prost_types::Timestamp;
+use tokio::sync::{Notify, oneshot};
+
+// Source of wall-clock time and delays. Everything that stamps or waits goes
+// through a `Clock` so tests can drive time by hand with `ManualClock`.
+#[tonic::async_trait]
+pub trait Clock: Debug + Send + Sync + 'static {
+    fn now(&self) -> SystemTime;
+
+    fn timestamp(&self) -> Timestamp {
+        Timestamp::from(self.now())
+    }
+
+    async fn sleep(&self, duration: Duration);
+}
+
+pub type SharedClock = Arc<dyn Clock>;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+#[tonic::async_trait]
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+}
+
+// Virtual time that only moves when `advance` is called. Sleepers wake once
+// the clock has been advanced past their deadline.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    state: Arc<Mutex<ManualState>>,
+    registered: Arc<Notify>,
+}
+
+#[derive(Debug)]
+struct ManualState {
+    now: SystemTime,
+    next_id: u64,
+    sleepers: Vec<Sleeper>,
+}
+
+#[derive(Debug)]
+struct Sleeper {
+    id: u64,
+    deadline: SystemTime,
+    wake: oneshot::Sender<()>,
+}
+
+// Unregisters a sleep that is dropped before its deadline, e.g. because it
+// lost a `select!`.
+struct Registration<'a> {
+    state: &'a Mutex<ManualState>,
+    id: u64,
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        state.sleepers.retain(|sleeper| sleeper.id != self.id);
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new(SystemTime::UNIX_EPOCH)
+    }
+}
+
+impl ManualClock {
+    pub fn new(start: SystemTime) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(ManualState {
+                now: start,
+                next_id: 0,
+                sleepers: Vec::new(),
+            })),
+            registered: Arc::new(Notify::new()),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.now += duration;
+
+        let now = state.now;
+        let (due, pending) = std::mem::take(&mut state.sleepers)
+            .into_iter()
+            .partition(|sleeper: &Sleeper| sleeper.deadline <= now);
+        state.sleepers = pending;
+
+        for sleeper in due {
+            let _ = sleeper.wake.send(());
+        }
+    }
+
+    // Number of tasks currently blocked in `sleep`.
+    pub fn sleepers(&self) -> usize {
+        self.state.lock().unwrap().sleepers.len()
+    }
+
+    // Waits until at least `count` tasks are blocked in `sleep`, so a test can
+    // advance the clock without racing the code under test.
+    pub async fn wait_for_sleepers(&self, count: usize) {
+        loop {
+            let registered = self.registered.notified();
+            if self.sleepers() >= count {
+                return;
+            }
+            registered.await;
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl Clock for ManualClock {
+    fn now(&self) -> SystemTime {
+        self.state.lock().unwrap().now
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        if duration.is_zero() {
+            return;
+        }
+
+        let (wake, woken) = oneshot::channel();
+        let id = {
+            let mut state = self.state.lock().unwrap();
+            let id = state.next_id;
+            state.next_id += 1;
+            let deadline = state.now + duration;
+            state.sleepers.push(Sleeper { id, deadline, wake });
+            id
+        };
+        let _registration = Registration {
+            state: &self.state,
+            id,
+        };
+        self.registered.notify_waiters();
+
+        let _ = woken.await;
+    }
+}
@@ -0,0 +1,627 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD_NO_PAD};
+use bytes::{Bytes, BytesMut};
+use http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode, header};
+use http_body::Frame;
+use http_body_util::{BodyExt, Full, LengthLimitError, Limited, StreamBody};
+use prost::Message;
+use serde_json::{Map, Value, json};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Code, Status, body::Body, codec::CompressionEncoding};
+use tower::{Layer, Service};
+
+use crate::{
+    error::ServiceError,
+    gateway::{MethodCodec, descriptor_pool, http_status},
+    payload::{self, PayloadConfig},
+    server::BoxError,
+};
+
+pub const CONNECT_ENV: &str = "CONNECT_PROTOCOL";
+
+pub const PROTOCOL_VERSION_HEADER: &str = "connect-protocol-version";
+pub const TIMEOUT_HEADER: &str = "connect-timeout-ms";
+const STREAM_ENCODING_HEADER: &str = "connect-content-encoding";
+const STREAM_ACCEPT_ENCODING_HEADER: &str = "connect-accept-encoding";
+
+// Envelope flags of streaming requests and responses.
+const COMPRESSED: u8 = 0x01;
+const END_STREAM: u8 = 0x02;
+
+// Connect, and with it HTTP/1.1, is off unless CONNECT_PROTOCOL=true.
+pub fn enabled_from_env() -> Result<bool, String> {
+    match std::env::var(CONNECT_ENV) {
+        Ok(enabled) => enabled
+            .parse()
+            .map_err(|_| format!("invalid value for {}: {}", CONNECT_ENV, enabled)),
+        Err(_) => Ok(false),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Codec {
+    Proto,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Protocol {
+    streaming: bool,
+    codec: Codec,
+}
+
+impl Protocol {
+    fn detect(headers: &HeaderMap) -> Option<Self> {
+        let content_type = headers.get(header::CONTENT_TYPE)?.to_str().ok()?;
+        let (streaming, codec) = match content_type.split(';').next()?.trim() {
+            "application/proto" => (false, Codec::Proto),
+            "application/json" => (false, Codec::Json),
+            "application/connect+proto" => (true, Codec::Proto),
+            "application/connect+json" => (true, Codec::Json),
+            _ => return None,
+        };
+        Some(Self { streaming, codec })
+    }
+
+    fn content_type(&self) -> &'static str {
+        match (self.streaming, self.codec) {
+            (false, Codec::Proto) => "application/proto",
+            (false, Codec::Json) => "application/json",
+            (true, Codec::Proto) => "application/connect+proto",
+            (true, Codec::Json) => "application/connect+json",
+        }
+    }
+}
+
+// Serves the Connect protocol next to gRPC and gRPC-Web. Connect calls are
+// rewritten into gRPC calls for the inner service, transcoding JSON bodies
+// with FILE_DESCRIPTOR_SET, and the gRPC responses are rewritten back:
+//
+//   application/proto, application/json                  unary
+//   application/connect+proto, application/connect+json  streaming
+//
+// Anything else passes through untouched. Requests may be compressed with
+// any encoding the `PayloadConfig` accepts, and are held to its
+// `max_recv_message_bytes`. Responses are sent uncompressed.
+#[derive(Debug, Clone)]
+pub struct ConnectLayer {
+    methods: Arc<HashMap<String, MethodCodec>>,
+    payload: Arc<PayloadConfig>,
+}
+
+impl ConnectLayer {
+    pub fn new() -> Result<Self, prost_reflect::DescriptorError> {
+        let methods = MethodCodec::all(&descriptor_pool()?)
+            .into_iter()
+            .map(|codec| (codec.path().to_string(), codec))
+            .collect();
+        Ok(Self {
+            methods: Arc::new(methods),
+            payload: Arc::new(PayloadConfig::default()),
+        })
+    }
+
+    pub fn with_payload(mut self, payload: PayloadConfig) -> Self {
+        self.payload = Arc::new(payload);
+        self
+    }
+}
+
+impl<S> Layer<S> for ConnectLayer {
+    type Service = ConnectService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ConnectService {
+            inner,
+            methods: self.methods.clone(),
+            payload: self.payload.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConnectService<S> {
+    inner: S,
+    methods: Arc<HashMap<String, MethodCodec>>,
+    payload: Arc<PayloadConfig>,
+}
+
+impl<S, B> Service<http::Request<B>> for ConnectService<S>
+where
+    S: Service<http::Request<Body>, Response = http::Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Send,
+    B: http_body::Body<Data = Bytes> + Send + 'static,
+    B::Error: Into<BoxError>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let req = req.map(Body::new);
+        let protocol = Protocol::detect(req.headers()).filter(|_| req.method() == Method::POST);
+        let codec = self.methods.get(req.uri().path()).cloned();
+        let (Some(protocol), Some(codec)) = (protocol, codec) else {
+            return Box::pin(self.inner.call(req));
+        };
+
+        // The clone may not be ready; keep the one that is.
+        let clone = self.inner.clone();
+        let inner = std::mem::replace(&mut self.inner, clone);
+        let payload = self.payload.clone();
+        if protocol.streaming {
+            Box::pin(streaming(inner, req, protocol, codec, payload))
+        } else {
+            Box::pin(unary(inner, req, protocol, codec, payload))
+        }
+    }
+}
+
+async fn unary<S>(
+    mut inner: S,
+    req: http::Request<Body>,
+    protocol: Protocol,
+    codec: MethodCodec,
+    payload: Arc<PayloadConfig>,
+) -> Result<http::Response<Body>, S::Error>
+where
+    S: Service<http::Request<Body>, Response = http::Response<Body>>,
+{
+    let (parts, body) = req.into_parts();
+    let message = async {
+        let encoding =
+            request_encoding(&parts.headers, header::CONTENT_ENCODING.as_str(), &payload)?;
+        let limit = payload.max_recv_message_bytes;
+        let message = Limited::new(body, limit)
+            .collect()
+            .await
+            .map_err(|e| match e.downcast::<Status>() {
+                Ok(status) => *status,
+                Err(e) if e.is::<LengthLimitError>() => {
+                    ServiceError::MessageTooLarge { size: None, limit }.into()
+                }
+                Err(e) => Status::internal(e.to_string()),
+            })?
+            .to_bytes();
+        let message = match encoding {
+            Some(encoding) => payload::decompress(encoding, &message, limit)?,
+            None => message,
+        };
+        match protocol.codec {
+            Codec::Proto => Ok(message),
+            Codec::Json => codec.request_to_binary(&message).map(Bytes::from),
+        }
+    }
+    .await;
+    let req = match message
+        .and_then(|message| grpc_request(parts, Body::new(Full::new(envelope(0, &message)))))
+    {
+        Ok(req) => req,
+        Err(status) => return Ok(unary_error(&status, &HeaderMap::new(), &HeaderMap::new())),
+    };
+
+    let (parts, body) = inner.call(req).await?.into_parts();
+    let (mut data, trailers) = match body.collect().await {
+        Ok(collected) => {
+            let trailers = collected.trailers().cloned().unwrap_or_default();
+            (BytesMut::from(collected.to_bytes()), trailers)
+        }
+        Err(status) => return Ok(unary_error(&status, &parts.headers, &HeaderMap::new())),
+    };
+    // Trailers-only responses carry the status in the headers.
+    let status = grpc_status(if trailers.contains_key("grpc-status") {
+        &trailers
+    } else {
+        &parts.headers
+    });
+    if status.code() != Code::Ok {
+        return Ok(unary_error(&status, &parts.headers, &trailers));
+    }
+
+    let message = next_envelope(&mut data).map(|(_, message)| message);
+    let message = match (protocol.codec, message) {
+        (Codec::Proto, Some(message)) => message,
+        (Codec::Json, Some(message)) => match codec.response_to_json(&message) {
+            Ok(json) => Bytes::from(json),
+            Err(status) => return Ok(unary_error(&status, &parts.headers, &trailers)),
+        },
+        (_, None) => {
+            let status = Status::internal("missing response message");
+            return Ok(unary_error(&status, &parts.headers, &trailers));
+        }
+    };
+
+    let mut response = http::Response::new(Body::new(Full::new(message)));
+    unary_metadata(&parts.headers, &trailers, response.headers_mut());
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(protocol.content_type()),
+    );
+    Ok(response)
+}
+
+async fn streaming<S>(
+    mut inner: S,
+    req: http::Request<Body>,
+    protocol: Protocol,
+    codec: MethodCodec,
+    payload: Arc<PayloadConfig>,
+) -> Result<http::Response<Body>, S::Error>
+where
+    S: Service<http::Request<Body>, Response = http::Response<Body>>,
+{
+    let (parts, body) = req.into_parts();
+    let req =
+        request_encoding(&parts.headers, STREAM_ENCODING_HEADER, &payload).and_then(|encoding| {
+            let body = match protocol.codec {
+                // Connect envelopes and gRPC frames are the same bytes, and
+                // the inner service inflates compressed ones itself.
+                Codec::Proto => body,
+                Codec::Json => transcode_requests(
+                    body,
+                    codec.clone(),
+                    encoding,
+                    payload.max_recv_message_bytes,
+                ),
+            };
+            let mut req = grpc_request(parts, body)?;
+            if let Some(name) = encoding
+                .filter(|_| protocol.codec == Codec::Proto)
+                .and_then(payload::encoding_name)
+            {
+                req.headers_mut()
+                    .insert("grpc-encoding", HeaderValue::from_static(name));
+            }
+            Ok(req)
+        });
+    let (tx, rx) = mpsc::channel(16);
+    let mut response = http::Response::new(Body::new(StreamBody::new(ReceiverStream::new(rx))));
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(protocol.content_type()),
+    );
+
+    match req {
+        Ok(req) => {
+            let (parts, body) = inner.call(req).await?.into_parts();
+            copy_metadata(&parts.headers, response.headers_mut(), "");
+            tokio::spawn(relay_responses(
+                body,
+                parts.headers,
+                protocol.codec,
+                codec,
+                tx,
+            ));
+        }
+        Err(status) => {
+            let end = end_stream(&status, &HeaderMap::new());
+            let _ = tx.try_send(Ok(Frame::data(end)));
+        }
+    }
+    Ok(response)
+}
+
+// Rewrites each JSON envelope of the request stream into a gRPC frame,
+// inflating compressed envelopes first.
+fn transcode_requests(
+    mut body: Body,
+    codec: MethodCodec,
+    encoding: Option<CompressionEncoding>,
+    max_len: usize,
+) -> Body {
+    let (tx, rx) = mpsc::channel::<Result<Frame<Bytes>, Status>>(16);
+    tokio::spawn(async move {
+        let mut buffer = BytesMut::new();
+        while let Some(frame) = body.frame().await {
+            let data = match frame {
+                Ok(frame) => match frame.into_data() {
+                    Ok(data) => data,
+                    Err(_) => continue,
+                },
+                Err(status) => {
+                    let _ = tx.send(Err(status)).await;
+                    return;
+                }
+            };
+            buffer.extend_from_slice(&data);
+            while let Some((flags, message)) = payload::split_frame(&mut buffer) {
+                let message = match encoding.filter(|_| flags & COMPRESSED != 0) {
+                    Some(encoding) => payload::decompress(encoding, &message, max_len),
+                    None if flags & COMPRESSED != 0 => Err(Status::internal(format!(
+                        "compressed message without {}",
+                        STREAM_ENCODING_HEADER
+                    ))),
+                    None => Ok(message),
+                };
+                let frame = message
+                    .and_then(|message| codec.request_to_binary(&message))
+                    .map(|binary| Frame::data(envelope(0, &binary)));
+                let failed = frame.is_err();
+                if tx.send(frame).await.is_err() || failed {
+                    return;
+                }
+            }
+        }
+    });
+    Body::new(StreamBody::new(ReceiverStream::new(rx)))
+}
+
+// Forwards the messages of a gRPC response stream as Connect envelopes and
+// finishes with the end-of-stream envelope carrying the status and trailers.
+async fn relay_responses(
+    mut body: Body,
+    headers: HeaderMap,
+    format: Codec,
+    codec: MethodCodec,
+    tx: mpsc::Sender<Result<Frame<Bytes>, Status>>,
+) {
+    let mut buffer = BytesMut::new();
+    let mut trailers = headers;
+    let mut status = trailers
+        .contains_key("grpc-status")
+        .then(|| grpc_status(&trailers));
+
+    while status.is_none() {
+        let Some(frame) = body.frame().await else {
+            break;
+        };
+        let frame = match frame {
+            Ok(frame) => frame,
+            Err(e) => {
+                status = Some(e);
+                break;
+            }
+        };
+        let data = match frame.into_data() {
+            Ok(data) => data,
+            Err(frame) => {
+                if let Ok(frame_trailers) = frame.into_trailers() {
+                    status = Some(grpc_status(&frame_trailers));
+                    trailers = frame_trailers;
+                }
+                continue;
+            }
+        };
+
+        buffer.extend_from_slice(&data);
+        while let Some((_, message)) = next_envelope(&mut buffer) {
+            let message = match format {
+                Codec::Proto => message,
+                Codec::Json => match codec.response_to_json(&message) {
+                    Ok(json) => Bytes::from(json),
+                    Err(e) => {
+                        status = Some(e);
+                        break;
+                    }
+                },
+            };
+            if tx
+                .send(Ok(Frame::data(envelope(0, &message))))
+                .await
+                .is_err()
+            {
+                return;
+            }
+        }
+    }
+
+    let status = status.unwrap_or_else(|| Status::internal("stream ended without a status"));
+    let _ = tx
+        .send(Ok(Frame::data(end_stream(&status, &trailers))))
+        .await;
+}
+
+fn grpc_request(
+    mut parts: http::request::Parts,
+    body: Body,
+) -> Result<http::Request<Body>, Status> {
+    let headers = &mut parts.headers;
+    if let Some(version) = headers.remove(PROTOCOL_VERSION_HEADER)
+        && version != "1"
+    {
+        return Err(Status::invalid_argument(format!(
+            "unsupported {}: {:?}",
+            PROTOCOL_VERSION_HEADER, version
+        )));
+    }
+    if let Some(timeout) = headers.remove(TIMEOUT_HEADER) {
+        headers.insert("grpc-timeout", grpc_timeout(&timeout)?);
+    }
+    // Responses are passed on uncompressed, so the gRPC side must not
+    // compress them; request encodings are set by the caller of this.
+    for name in [
+        header::CONTENT_ENCODING.as_str(),
+        header::ACCEPT_ENCODING.as_str(),
+        header::CONTENT_LENGTH.as_str(),
+        STREAM_ENCODING_HEADER,
+        STREAM_ACCEPT_ENCODING_HEADER,
+        "grpc-encoding",
+        "grpc-accept-encoding",
+    ] {
+        headers.remove(name);
+    }
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/grpc"),
+    );
+    headers.insert(header::TE, HeaderValue::from_static("trailers"));
+    Ok(http::Request::from_parts(parts, body))
+}
+
+// `connect-timeout-ms` allows up to 10 digits, `grpc-timeout` only 8.
+fn grpc_timeout(timeout: &HeaderValue) -> Result<HeaderValue, Status> {
+    let millis = timeout
+        .to_str()
+        .ok()
+        .filter(|millis| (1..=10).contains(&millis.len()))
+        .and_then(|millis| millis.parse::<u64>().ok())
+        .ok_or_else(|| {
+            Status::invalid_argument(format!("invalid {}: {:?}", TIMEOUT_HEADER, timeout))
+        })?;
+    let timeout = if millis < 100_000_000 {
+        format!("{}m", millis)
+    } else {
+        format!("{}S", millis / 1000)
+    };
+    Ok(HeaderValue::from_str(&timeout).expect("digits and a unit are a valid header value"))
+}
+
+// The encoding a request declares in `name`, if it is compressed at all.
+// Encodings the payload config does not accept are `unimplemented`.
+fn request_encoding(
+    headers: &HeaderMap,
+    name: &str,
+    payload: &PayloadConfig,
+) -> Result<Option<CompressionEncoding>, Status> {
+    let Some(value) = headers.get(name) else {
+        return Ok(None);
+    };
+    if value == "identity" {
+        return Ok(None);
+    }
+    value
+        .to_str()
+        .ok()
+        .and_then(|encoding| payload.accepted(encoding))
+        .map(Some)
+        .ok_or_else(|| Status::unimplemented(format!("unsupported {}: {:?}", name, value)))
+}
+
+fn grpc_status(headers: &HeaderMap) -> Status {
+    Status::from_header_map(headers)
+        .unwrap_or_else(|| Status::internal("response without grpc-status"))
+}
+
+// Connect envelopes and gRPC frames share one layout.
+fn next_envelope(buffer: &mut BytesMut) -> Option<(u8, Bytes)> {
+    payload::split_frame(buffer).map(|(flags, message)| (flags & !COMPRESSED, message))
+}
+
+fn envelope(flags: u8, message: &[u8]) -> Bytes {
+    payload::frame(flags, message)
+}
+
+fn end_stream(status: &Status, trailers: &HeaderMap) -> Bytes {
+    let mut end = Map::new();
+    if status.code() != Code::Ok {
+        end.insert("error".to_string(), error_json(status));
+    }
+    let mut metadata = Map::new();
+    for (name, value) in trailers.iter().filter(|(name, _)| !is_reserved(name)) {
+        if let Ok(value) = value.to_str() {
+            metadata
+                .entry(name.as_str())
+                .or_insert_with(|| Value::Array(Vec::new()))
+                .as_array_mut()
+                .unwrap()
+                .push(value.into());
+        }
+    }
+    if !metadata.is_empty() {
+        end.insert("metadata".to_string(), Value::Object(metadata));
+    }
+    envelope(END_STREAM, Value::Object(end).to_string().as_bytes())
+}
+
+fn unary_error(status: &Status, headers: &HeaderMap, trailers: &HeaderMap) -> http::Response<Body> {
+    let body = error_json(status).to_string();
+    let mut response = http::Response::new(Body::new(Full::new(Bytes::from(body))));
+    *response.status_mut() = match status.code() {
+        // Connect never answers errors with 200.
+        Code::Ok => StatusCode::INTERNAL_SERVER_ERROR,
+        code => http_status(code),
+    };
+    unary_metadata(headers, trailers, response.headers_mut());
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    response
+}
+
+fn error_json(status: &Status) -> Value {
+    let mut error = json!({ "code": error_code(status.code()) });
+    if !status.message().is_empty() {
+        error["message"] = status.message().into();
+    }
+    let details = error_details(status);
+    if !details.is_empty() {
+        error["details"] = details.into();
+    }
+    error
+}
+
+// google.rpc details travel as `{type, value}` with the type's full name and
+// the unpadded base64 of the message. The REST gateway renders them the same
+// way.
+pub fn error_details(status: &Status) -> Vec<Value> {
+    tonic_types::Status::decode(status.details())
+        .map(|status| status.details)
+        .unwrap_or_default()
+        .iter()
+        .map(|detail| {
+            json!({
+                "type": detail.type_url.rsplit('/').next().unwrap_or_default(),
+                "value": STANDARD_NO_PAD.encode(&detail.value),
+            })
+        })
+        .collect()
+}
+
+pub fn error_code(code: Code) -> &'static str {
+    match code {
+        Code::Ok => "ok",
+        Code::Cancelled => "canceled",
+        Code::Unknown => "unknown",
+        Code::InvalidArgument => "invalid_argument",
+        Code::DeadlineExceeded => "deadline_exceeded",
+        Code::NotFound => "not_found",
+        Code::AlreadyExists => "already_exists",
+        Code::PermissionDenied => "permission_denied",
+        Code::ResourceExhausted => "resource_exhausted",
+        Code::FailedPrecondition => "failed_precondition",
+        Code::Aborted => "aborted",
+        Code::OutOfRange => "out_of_range",
+        Code::Unimplemented => "unimplemented",
+        Code::Internal => "internal",
+        Code::Unavailable => "unavailable",
+        Code::DataLoss => "data_loss",
+        Code::Unauthenticated => "unauthenticated",
+    }
+}
+
+// gRPC framing headers stay behind; everything else is application metadata.
+fn is_reserved(name: &HeaderName) -> bool {
+    name.as_str().starts_with("grpc-")
+        || name == header::CONTENT_TYPE
+        || name == header::CONTENT_LENGTH
+        || name == header::TE
+        || name == header::TRAILER
+}
+
+// Unary Connect responses carry trailers as `trailer-` prefixed headers.
+fn unary_metadata(headers: &HeaderMap, trailers: &HeaderMap, to: &mut HeaderMap) {
+    copy_metadata(headers, to, "");
+    copy_metadata(trailers, to, "trailer-");
+}
+
+fn copy_metadata(from: &HeaderMap, to: &mut HeaderMap, prefix: &str) {
+    for (name, value) in from.iter().filter(|(name, _)| !is_reserved(name)) {
+        if let Ok(name) = HeaderName::try_from(format!("{}{}", prefix, name)) {
+            to.append(name, value.clone());
+        }
+    }
+}
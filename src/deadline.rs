@@ -0,0 +1,128 @@
+use std::{collections::HashMap, time::Duration};
+
+use tonic::Request;
+
+pub const GRPC_TIMEOUT_HEADER: &str = "grpc-timeout";
+
+// Methods whose deadlines can be configured.
+const METHODS: [&str; 3] = ["Hello", "Talk", "Background"];
+
+// Server-side bounds for one method's deadline.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MethodDeadline {
+    // Applied when the caller sets no `grpc-timeout`.
+    pub default: Option<Duration>,
+    // Caps whatever the caller asks for.
+    pub max: Option<Duration>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deadlines {
+    methods: HashMap<String, MethodDeadline>,
+    margin: Duration,
+}
+
+impl Default for Deadlines {
+    fn default() -> Self {
+        Self {
+            methods: HashMap::new(),
+            margin: Duration::from_millis(250),
+        }
+    }
+}
+
+impl Deadlines {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_method(mut self, method: &str, deadline: MethodDeadline) -> Self {
+        self.methods.insert(method.to_string(), deadline);
+        self
+    }
+
+    // How long before the deadline Background stops its workers, so the
+    // final snapshot still reaches the caller in time.
+    pub fn with_margin(mut self, margin: Duration) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    pub fn margin(&self) -> Duration {
+        self.margin
+    }
+
+    // DEADLINE_<METHOD>_DEFAULT_SECS and DEADLINE_<METHOD>_MAX_SECS for Hello,
+    // Talk and Background, and DEADLINE_MARGIN_MS.
+    pub fn from_env() -> Result<Self, String> {
+        fn var<T: std::str::FromStr>(key: &str) -> Result<Option<T>, String> {
+            match std::env::var(key) {
+                Ok(value) => value
+                    .parse()
+                    .map(Some)
+                    .map_err(|_| format!("invalid value for {}: {}", key, value)),
+                Err(_) => Ok(None),
+            }
+        }
+
+        let mut deadlines = Self::default();
+        for method in METHODS {
+            let prefix = format!("DEADLINE_{}", method.to_uppercase());
+            let deadline = MethodDeadline {
+                default: var(&format!("{}_DEFAULT_SECS", prefix))?.map(Duration::from_secs),
+                max: var(&format!("{}_MAX_SECS", prefix))?.map(Duration::from_secs),
+            };
+            if deadline != MethodDeadline::default() {
+                deadlines = deadlines.with_method(method, deadline);
+            }
+        }
+        if let Some(margin) = var("DEADLINE_MARGIN_MS")? {
+            deadlines.margin = Duration::from_millis(margin);
+        }
+        Ok(deadlines)
+    }
+
+    // The method's server-side maximum, which bounds work that outlives the
+    // call that started it.
+    pub fn max(&self, method: &str) -> Option<Duration> {
+        self.methods.get(method).and_then(|bounds| bounds.max)
+    }
+
+    // Time left for a call: the caller's `grpc-timeout`, or the method's
+    // default, capped at the method's maximum. `None` means unbounded.
+    pub fn resolve<T>(&self, method: &str, request: &Request<T>) -> Option<Duration> {
+        let bounds = self.methods.get(method).copied().unwrap_or_default();
+        let requested = request
+            .metadata()
+            .get(GRPC_TIMEOUT_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_grpc_timeout)
+            .or(bounds.default);
+        match (requested, bounds.max) {
+            (Some(requested), Some(max)) => Some(requested.min(max)),
+            (requested, max) => requested.or(max),
+        }
+    }
+}
+
+// `grpc-timeout` is at most 8 digits followed by a unit. Malformed values are
+// ignored, like a missing header.
+fn parse_grpc_timeout(value: &str) -> Option<Duration> {
+    if value.len() < 2 || value.len() > 9 {
+        return None;
+    }
+    let (amount, unit) = value.split_at(value.len() - 1);
+    if !amount.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let amount: u64 = amount.parse().ok()?;
+    Some(match unit {
+        "H" => Duration::from_secs(amount * 60 * 60),
+        "M" => Duration::from_secs(amount * 60),
+        "S" => Duration::from_secs(amount),
+        "m" => Duration::from_millis(amount),
+        "u" => Duration::from_micros(amount),
+        "n" => Duration::from_nanos(amount),
+        _ => return None,
+    })
+}
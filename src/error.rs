@@ -0,0 +1,179 @@
+use std::{collections::HashMap, fmt, time::Duration};
+
+use tonic::{Code, Status};
+use tonic_types::{ErrorDetails, FieldViolation, StatusExt};
+
+// `ErrorInfo.domain` of every error BasicService returns.
+pub const ERROR_DOMAIN: &str = "basic.io";
+
+// `ResourceInfo.resource_type` of Background and idempotency jobs.
+pub const JOB_RESOURCE_TYPE: &str = "basic.v1.Job";
+
+// Everything a BasicService call can fail with. Converting to a `Status`
+// attaches `google.rpc` details: always an `ErrorInfo` with `ERROR_DOMAIN`
+// and `reason()`, plus `BadRequest`, `RetryInfo` or `ResourceInfo` where the
+// caller can act on them.
+#[derive(Debug, Clone)]
+pub enum ServiceError {
+    // One or more request fields or metadata entries are invalid.
+    InvalidArgument(Vec<FieldViolation>),
+    // Too many Talk messages; retry after the delay.
+    RateLimited { retry_after: Duration },
+    // The Talk session ran for its maximum length.
+    SessionExpired { max_session: Duration },
+    DeadlineExceeded(String),
+    // A request message is larger than `max_recv_message_bytes`. The size is
+    // unknown when the limit was hit while reading or inflating the body.
+    MessageTooLarge { size: Option<usize>, limit: usize },
+    // Background was asked for processes with no workers registered.
+    NoWorkers,
+    // The job an idempotency key points at is gone or did not complete.
+    JobAborted { job: String, description: String },
+    // An idempotency key was sent again with a different request.
+    IdempotencyKeyReused { job: String },
+    Unauthenticated(String),
+    PermissionDenied { scope: String, method: String },
+    // Reading the next message of a client stream failed.
+    Receive(Status),
+}
+
+impl ServiceError {
+    pub fn invalid_field(field: impl Into<String>, description: impl Into<String>) -> Self {
+        ServiceError::InvalidArgument(vec![FieldViolation::new(field, description)])
+    }
+
+    pub fn code(&self) -> Code {
+        match self {
+            ServiceError::InvalidArgument(_) => Code::InvalidArgument,
+            ServiceError::RateLimited { .. }
+            | ServiceError::SessionExpired { .. }
+            | ServiceError::MessageTooLarge { .. } => Code::ResourceExhausted,
+            ServiceError::DeadlineExceeded(_) => Code::DeadlineExceeded,
+            ServiceError::NoWorkers | ServiceError::IdempotencyKeyReused { .. } => {
+                Code::FailedPrecondition
+            }
+            ServiceError::JobAborted { .. } => Code::Aborted,
+            ServiceError::Unauthenticated(_) => Code::Unauthenticated,
+            ServiceError::PermissionDenied { .. } => Code::PermissionDenied,
+            ServiceError::Receive(status) => match status.code() {
+                Code::Ok | Code::Unknown => Code::Internal,
+                code => code,
+            },
+        }
+    }
+
+    // `ErrorInfo.reason`: a stable identifier clients can match on instead
+    // of the message.
+    pub fn reason(&self) -> &'static str {
+        match self {
+            ServiceError::InvalidArgument(_) => "INVALID_ARGUMENT",
+            ServiceError::RateLimited { .. } => "RATE_LIMITED",
+            ServiceError::SessionExpired { .. } => "SESSION_EXPIRED",
+            ServiceError::DeadlineExceeded(_) => "DEADLINE_EXCEEDED",
+            ServiceError::MessageTooLarge { .. } => "MESSAGE_TOO_LARGE",
+            ServiceError::NoWorkers => "NO_WORKERS",
+            ServiceError::JobAborted { .. } => "JOB_ABORTED",
+            ServiceError::IdempotencyKeyReused { .. } => "IDEMPOTENCY_KEY_REUSED",
+            ServiceError::Unauthenticated(_) => "UNAUTHENTICATED",
+            ServiceError::PermissionDenied { .. } => "MISSING_SCOPE",
+            ServiceError::Receive(_) => "RECEIVE_FAILED",
+        }
+    }
+
+    fn details(&self) -> ErrorDetails {
+        let mut metadata = HashMap::new();
+        let mut details = ErrorDetails::new();
+        match self {
+            ServiceError::InvalidArgument(violations) => {
+                details.set_bad_request(violations.clone());
+            }
+            ServiceError::RateLimited { retry_after } => {
+                details.set_retry_info(Some(*retry_after));
+            }
+            ServiceError::SessionExpired { .. } => {
+                details.set_retry_info(Some(Duration::ZERO));
+            }
+            ServiceError::JobAborted { job, description } => {
+                details.set_resource_info(JOB_RESOURCE_TYPE, job, "", description);
+            }
+            ServiceError::IdempotencyKeyReused { job } => {
+                details.set_resource_info(JOB_RESOURCE_TYPE, job, "", self.to_string());
+            }
+            ServiceError::PermissionDenied { scope, method } => {
+                metadata.insert("scope".to_string(), scope.clone());
+                metadata.insert("method".to_string(), method.clone());
+            }
+            ServiceError::MessageTooLarge { size, limit } => {
+                metadata.insert("limit".to_string(), limit.to_string());
+                if let Some(size) = size {
+                    metadata.insert("size".to_string(), size.to_string());
+                }
+            }
+            ServiceError::DeadlineExceeded(_)
+            | ServiceError::NoWorkers
+            | ServiceError::Unauthenticated(_)
+            | ServiceError::Receive(_) => {}
+        }
+        details.set_error_info(self.reason(), ERROR_DOMAIN, metadata);
+        details
+    }
+}
+
+impl fmt::Display for ServiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServiceError::InvalidArgument(violations) => {
+                for (i, violation) in violations.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(f, "{}: {}", violation.field, violation.description)?;
+                }
+                Ok(())
+            }
+            ServiceError::RateLimited { .. } => write!(f, "too many messages, slow down"),
+            ServiceError::SessionExpired { max_session } => write!(
+                f,
+                "session exceeded the maximum duration of {}s",
+                max_session.as_secs()
+            ),
+            ServiceError::DeadlineExceeded(e) => write!(f, "{}", e),
+            ServiceError::MessageTooLarge {
+                size: Some(size),
+                limit,
+            } => write!(
+                f,
+                "message of {} bytes exceeds the {} byte limit",
+                size, limit
+            ),
+            ServiceError::MessageTooLarge { size: None, limit } => {
+                write!(f, "message exceeds the {} byte limit", limit)
+            }
+            ServiceError::NoWorkers => write!(f, "no background workers registered"),
+            ServiceError::JobAborted { description, .. } => write!(f, "{}", description),
+            ServiceError::IdempotencyKeyReused { .. } => write!(
+                f,
+                "idempotency key was already used for a different request"
+            ),
+            ServiceError::Unauthenticated(e) => write!(f, "{}", e),
+            ServiceError::PermissionDenied { scope, method } => {
+                write!(f, "missing scope '{}' for {}", scope, method)
+            }
+            ServiceError::Receive(status) => {
+                write!(f, "failed to receive message: {}", status.message())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ServiceError {}
+
+impl From<ServiceError> for Status {
+    fn from(e: ServiceError) -> Self {
+        match e {
+            // Already explained, e.g. a Talk message that failed validation.
+            ServiceError::Receive(status) if !status.details().is_empty() => status,
+            e => Status::with_error_details(e.code(), e.to_string(), e.details()),
+        }
+    }
+}
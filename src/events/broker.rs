@@ -0,0 +1,120 @@
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use super::{
+    EventSink, SinkError,
+    format::{binary_attributes, binary_body},
+};
+use crate::sdk::io::cloudevents::v1::CloudEvent;
+
+// A message as NATS (subject, headers) or Kafka (topic, key, headers) would
+// carry it, using the binary content mode with `ce_` headers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokerMessage {
+    pub subject: String,
+    pub key: String,
+    pub headers: Vec<(String, String)>,
+    pub payload: Vec<u8>,
+}
+
+impl BrokerMessage {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+// Client side of a message broker. Implement it over a NATS or Kafka client
+// to publish events there; `InMemoryBroker` stands in for tests.
+#[tonic::async_trait]
+pub trait Broker: fmt::Debug + Send + Sync + 'static {
+    async fn publish(&self, message: BrokerMessage) -> Result<(), SinkError>;
+}
+
+// Publishes every event to `<prefix>.<type>`, keyed by its source so events
+// from one source stay ordered within a partition.
+#[derive(Debug)]
+pub struct BrokerSink<B> {
+    name: String,
+    prefix: String,
+    broker: B,
+}
+
+impl<B: Broker> BrokerSink<B> {
+    pub fn new(broker: B, prefix: impl Into<String>) -> Self {
+        let prefix = prefix.into();
+        Self {
+            name: format!("broker:{}", prefix),
+            prefix,
+            broker,
+        }
+    }
+
+    pub fn message(&self, event: &CloudEvent) -> BrokerMessage {
+        let mut headers: Vec<_> = binary_attributes(event)
+            .into_iter()
+            .map(|(key, value)| (format!("ce_{}", key), value))
+            .collect();
+        let (content_type, payload) = binary_body(event);
+        headers.push(("content-type".to_string(), content_type.to_string()));
+
+        BrokerMessage {
+            subject: format!("{}.{}", self.prefix, event.r#type),
+            key: event.source.clone(),
+            headers,
+            payload,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl<B: Broker> EventSink for BrokerSink<B> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn deliver(&self, event: &CloudEvent) -> Result<(), SinkError> {
+        self.broker.publish(self.message(event)).await
+    }
+}
+
+// Keeps published messages in memory. `fail_next` makes the following
+// publishes fail with a retryable error to exercise retries and dead-letters.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryBroker {
+    messages: Arc<Mutex<Vec<BrokerMessage>>>,
+    failures: Arc<Mutex<u32>>,
+}
+
+impl InMemoryBroker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn fail_next(&self, count: u32) {
+        *self.failures.lock().unwrap() = count;
+    }
+
+    pub fn messages(&self) -> Vec<BrokerMessage> {
+        self.messages.lock().unwrap().clone()
+    }
+}
+
+#[tonic::async_trait]
+impl Broker for InMemoryBroker {
+    async fn publish(&self, message: BrokerMessage) -> Result<(), SinkError> {
+        {
+            let mut failures = self.failures.lock().unwrap();
+            if *failures > 0 {
+                *failures -= 1;
+                return Err(SinkError::Retryable("broker unavailable".to_string()));
+            }
+        }
+        self.messages.lock().unwrap().push(message);
+        Ok(())
+    }
+}
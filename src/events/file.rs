@@ -0,0 +1,117 @@
+use std::path::{Path, PathBuf};
+
+use tokio::{
+    fs::{self, File, OpenOptions},
+    io::AsyncWriteExt,
+    sync::Mutex,
+};
+
+use super::{EventSink, SinkError, format::structured_json};
+use crate::sdk::io::cloudevents::v1::CloudEvent;
+
+pub const DEFAULT_MAX_BYTES: u64 = 10 * 1024 * 1024;
+pub const DEFAULT_MAX_FILES: usize = 5;
+
+// Appends one structured JSON CloudEvent per line. Once the file would grow
+// past `max_bytes` it is rotated to `<path>.1`, shifting older files up to
+// `<path>.<max_files>`; anything beyond that is deleted.
+#[derive(Debug)]
+pub struct FileSink {
+    name: String,
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    file: Mutex<Option<(File, u64)>>,
+}
+
+impl FileSink {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        Self {
+            name: format!("file:{}", path.display()),
+            path,
+            max_bytes: DEFAULT_MAX_BYTES,
+            max_files: DEFAULT_MAX_FILES,
+            file: Mutex::new(None),
+        }
+    }
+
+    pub fn with_rotation(mut self, max_bytes: u64, max_files: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self.max_files = max_files;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn rotated(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        PathBuf::from(path)
+    }
+
+    async fn open(&self) -> std::io::Result<(File, u64)> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        let len = file.metadata().await?.len();
+        Ok((file, len))
+    }
+
+    async fn rotate(&self) -> std::io::Result<()> {
+        if self.max_files == 0 {
+            return fs::remove_file(&self.path).await;
+        }
+        let _ = fs::remove_file(self.rotated(self.max_files)).await;
+        for index in (1..self.max_files).rev() {
+            let from = self.rotated(index);
+            if fs::try_exists(&from).await? {
+                fs::rename(from, self.rotated(index + 1)).await?;
+            }
+        }
+        fs::rename(&self.path, self.rotated(1)).await
+    }
+
+    // On failure the handle is dropped and reopened by the next attempt.
+    async fn write(&self, line: &[u8]) -> std::io::Result<()> {
+        let mut guard = self.file.lock().await;
+        let (mut file, mut len) = match guard.take() {
+            Some(open) => open,
+            None => self.open().await?,
+        };
+
+        if len > 0 && len + line.len() as u64 > self.max_bytes {
+            drop(file);
+            self.rotate().await?;
+            (file, len) = self.open().await?;
+        }
+
+        file.write_all(line).await?;
+        file.flush().await?;
+        *guard = Some((file, len + line.len() as u64));
+        Ok(())
+    }
+}
+
+#[tonic::async_trait]
+impl EventSink for FileSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn deliver(&self, event: &CloudEvent) -> Result<(), SinkError> {
+        let mut line = serde_json::to_vec(&structured_json(event))
+            .map_err(|e| SinkError::Permanent(e.to_string()))?;
+        line.push(b'\n');
+        self.write(&line)
+            .await
+            .map_err(|e| SinkError::Retryable(format!("{}: {}", self.path.display(), e)))
+    }
+}
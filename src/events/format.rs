@@ -0,0 +1,104 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
+use serde_json::{Map, Value, json};
+
+use crate::sdk::io::cloudevents::v1::{
+    CloudEvent,
+    cloud_event::{CloudEventAttributeValue, Data, cloud_event_attribute_value::Attr},
+};
+use crate::utils;
+
+pub const STRUCTURED_CONTENT_TYPE: &str = "application/cloudevents+json";
+
+// Renders an event in the CloudEvents JSON format. Protobuf payloads are
+// carried as `data_base64`; events without a `dataschema` get one from the
+// `Any` type URL.
+pub fn structured_json(event: &CloudEvent) -> Value {
+    let mut out = Map::new();
+    out.insert("specversion".into(), json!(event.spec_version));
+    out.insert("id".into(), json!(event.id));
+    out.insert("source".into(), json!(event.source));
+    out.insert("type".into(), json!(event.r#type));
+    for (key, value) in &event.attributes {
+        out.insert(key.clone(), attribute_json(value));
+    }
+
+    match &event.data {
+        Some(Data::ProtoData(any)) => {
+            out.insert("datacontenttype".into(), json!("application/protobuf"));
+            out.entry("dataschema")
+                .or_insert(json!(utils::dataschema(&any.type_url)));
+            out.insert("data_base64".into(), json!(STANDARD.encode(&any.value)));
+        }
+        Some(Data::TextData(text)) => {
+            out.insert("data".into(), json!(text));
+        }
+        Some(Data::BinaryData(bytes)) => {
+            out.insert("data_base64".into(), json!(STANDARD.encode(bytes)));
+        }
+        None => {}
+    }
+    Value::Object(out)
+}
+
+fn attribute_json(value: &CloudEventAttributeValue) -> Value {
+    match &value.attr {
+        Some(Attr::CeBoolean(b)) => json!(b),
+        Some(Attr::CeInteger(i)) => json!(i),
+        Some(Attr::CeString(s)) | Some(Attr::CeUri(s)) | Some(Attr::CeUriRef(s)) => json!(s),
+        Some(Attr::CeBytes(b)) => json!(STANDARD.encode(b)),
+        Some(Attr::CeTimestamp(ts)) => json!(ts.to_string()),
+        None => Value::Null,
+    }
+}
+
+// Context attributes for binary mode, unprefixed; transports add `ce-` (HTTP)
+// or `ce_` (Kafka, NATS) themselves.
+pub fn binary_attributes(event: &CloudEvent) -> Vec<(String, String)> {
+    let mut attributes = vec![
+        ("specversion".to_string(), event.spec_version.clone()),
+        ("id".to_string(), event.id.clone()),
+        ("source".to_string(), event.source.clone()),
+        ("type".to_string(), event.r#type.clone()),
+    ];
+    for (key, value) in &event.attributes {
+        let value = match &value.attr {
+            Some(Attr::CeBoolean(b)) => b.to_string(),
+            Some(Attr::CeInteger(i)) => i.to_string(),
+            Some(Attr::CeString(s)) | Some(Attr::CeUri(s)) | Some(Attr::CeUriRef(s)) => s.clone(),
+            Some(Attr::CeBytes(b)) => STANDARD.encode(b),
+            Some(Attr::CeTimestamp(ts)) => ts.to_string(),
+            None => continue,
+        };
+        attributes.push((key.clone(), value));
+    }
+    if let Some(Data::ProtoData(any)) = &event.data
+        && !event.attributes.contains_key("dataschema")
+    {
+        attributes.push(("dataschema".to_string(), utils::dataschema(&any.type_url)));
+    }
+    attributes
+}
+
+// Content type and body for binary mode.
+pub fn binary_body(event: &CloudEvent) -> (&'static str, Vec<u8>) {
+    match &event.data {
+        Some(Data::ProtoData(any)) => ("application/protobuf", any.value.clone()),
+        Some(Data::TextData(text)) => ("text/plain; charset=utf-8", text.clone().into_bytes()),
+        Some(Data::BinaryData(bytes)) => ("application/octet-stream", bytes.clone()),
+        None => ("application/octet-stream", Vec::new()),
+    }
+}
+
+// HTTP header values must be visible ASCII; everything else is
+// percent-encoded as the HTTP binding requires.
+pub fn percent_encode(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if (0x20..0x7f).contains(&byte) && byte != b'%' && byte != b'"' {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
@@ -0,0 +1,212 @@
+mod broker;
+mod file;
+mod format;
+mod sink;
+mod webhook;
+
+use std::{
+    collections::{HashMap, HashSet},
+    pin::Pin,
+    sync::Arc,
+};
+
+use futures_core::Stream;
+use tokio::sync::broadcast;
+use tokio_stream::{
+    StreamExt,
+    wrappers::{BroadcastStream, errors::BroadcastStreamRecvError},
+};
+use tonic::Status;
+use uuid::Uuid;
+
+use crate::{
+    auth::PRINCIPAL_ATTRIBUTE,
+    clock::{Clock, SharedClock, SystemClock},
+    sdk::{
+        basic::service::v1::{SubscribeLagEvent, SubscribeRequest, SubscribeResponse},
+        io::cloudevents::v1::{
+            CloudEvent,
+            cloud_event::{CloudEventAttributeValue, cloud_event_attribute_value::Attr},
+        },
+    },
+    utils::{self, EventKind},
+};
+
+pub use broker::{Broker, BrokerMessage, BrokerSink, InMemoryBroker};
+pub use file::FileSink;
+pub use format::{binary_attributes, structured_json};
+pub use sink::{
+    EventSink, RetryPolicy, SharedSink, SinkDispatcher, SinkError, SinkOptions,
+    dispatchers_from_env,
+};
+pub use webhook::{WebhookMode, WebhookSink};
+
+pub const EVENT_BUFFER_ENV: &str = "EVENT_BUFFER";
+pub const EVENTS_INCLUDE_TALK_ENV: &str = "EVENTS_INCLUDE_TALK";
+pub const DEFAULT_BUFFER: usize = 256;
+
+pub type SubscribeStream =
+    Pin<Box<dyn Stream<Item = Result<SubscribeResponse, Status>> + Send + Sync + 'static>>;
+
+// Fans every CloudEvent the service emits out to `Subscribe` callers and the
+// configured sinks. The buffer is bounded: publishing never waits, and a
+// subscriber that falls more than `capacity` events behind gets a lag notice
+// instead.
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<CloudEvent>,
+    clock: SharedClock,
+    include_talk: bool,
+    sinks: Vec<SinkDispatcher>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(DEFAULT_BUFFER)
+    }
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            sender: broadcast::channel(capacity.max(1)).0,
+            clock: Arc::new(SystemClock),
+            include_talk: false,
+            sinks: Vec::new(),
+        }
+    }
+
+    pub fn with_sink(mut self, sink: SinkDispatcher) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    // Stamps lag notices.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    // Talk turns carry what users typed, so they are only published on request.
+    pub fn with_talk(mut self, include_talk: bool) -> Self {
+        self.include_talk = include_talk;
+        self
+    }
+
+    // EVENT_BUFFER sets the per-subscriber buffer, EVENTS_INCLUDE_TALK=true
+    // publishes Talk turns.
+    pub fn from_env() -> Result<Self, String> {
+        let capacity = match std::env::var(EVENT_BUFFER_ENV) {
+            Ok(value) => value
+                .parse()
+                .map_err(|_| format!("invalid value for {}: {}", EVENT_BUFFER_ENV, value))?,
+            Err(_) => DEFAULT_BUFFER,
+        };
+        let include_talk = match std::env::var(EVENTS_INCLUDE_TALK_ENV) {
+            Ok(value) => value
+                .parse()
+                .map_err(|_| format!("invalid value for {}: {}", EVENTS_INCLUDE_TALK_ENV, value))?,
+            Err(_) => false,
+        };
+        Ok(Self::new(capacity).with_talk(include_talk))
+    }
+
+    pub fn publish(&self, event: &CloudEvent) {
+        if self.sender.receiver_count() > 0 {
+            let _ = self.sender.send(event.clone());
+        }
+        for sink in &self.sinks {
+            sink.enqueue(event);
+        }
+    }
+
+    pub fn publish_talk(&self, event: &CloudEvent) {
+        if self.include_talk {
+            self.publish(event);
+        }
+    }
+
+    pub fn subscribe(&self, filter: EventFilter) -> SubscribeStream {
+        let events = BroadcastStream::new(self.sender.subscribe());
+        let subscription = Uuid::new_v4().to_string();
+        let clock = self.clock.clone();
+        Box::pin(events.filter_map(move |event| match event {
+            Ok(event) if filter.matches(&event) => Some(Ok(SubscribeResponse {
+                cloud_event: Some(event),
+            })),
+            Ok(_) => None,
+            Err(BroadcastStreamRecvError::Lagged(missed)) => Some(Ok(SubscribeResponse {
+                cloud_event: Some(lag_notice(&subscription, missed, &*clock)),
+            })),
+        }))
+    }
+}
+
+// Empty lists match everything; every listed attribute has to match.
+#[derive(Debug, Clone, Default)]
+pub struct EventFilter {
+    types: HashSet<String>,
+    sources: HashSet<String>,
+    attributes: HashMap<String, String>,
+    principal: Option<String>,
+}
+
+impl From<SubscribeRequest> for EventFilter {
+    fn from(request: SubscribeRequest) -> Self {
+        Self {
+            types: request.types.into_iter().collect(),
+            sources: request.sources.into_iter().collect(),
+            attributes: request.attributes,
+            principal: None,
+        }
+    }
+}
+
+impl EventFilter {
+    // Restricts the filter to events caused by `principal`, whatever the
+    // request asked for.
+    pub fn with_principal(mut self, principal: impl Into<String>) -> Self {
+        self.principal = Some(principal.into());
+        self
+    }
+
+    pub fn matches(&self, event: &CloudEvent) -> bool {
+        let own = self.principal.as_ref().is_none_or(|principal| {
+            event
+                .attributes
+                .get(PRINCIPAL_ATTRIBUTE)
+                .and_then(attribute_string)
+                .is_some_and(|actual| actual == *principal)
+        });
+        own && (self.types.is_empty() || self.types.contains(&event.r#type))
+            && (self.sources.is_empty() || self.sources.contains(&event.source))
+            && self.attributes.iter().all(|(key, expected)| {
+                event
+                    .attributes
+                    .get(key)
+                    .and_then(attribute_string)
+                    .is_some_and(|actual| actual == *expected)
+            })
+    }
+}
+
+fn attribute_string(value: &CloudEventAttributeValue) -> Option<String> {
+    match value.attr.as_ref()? {
+        Attr::CeString(s) | Attr::CeUri(s) | Attr::CeUriRef(s) => Some(s.clone()),
+        Attr::CeBoolean(b) => Some(b.to_string()),
+        Attr::CeInteger(i) => Some(i.to_string()),
+        Attr::CeTimestamp(ts) => Some(ts.to_string()),
+        Attr::CeBytes(_) => None,
+    }
+}
+
+// The subject is the id of the lagging subscription.
+pub fn lag_notice(subscription: &str, missed: u64, clock: &dyn Clock) -> CloudEvent {
+    utils::proto_event(
+        EventKind::SUBSCRIBE_LAGGED,
+        subscription,
+        clock.timestamp(),
+        &SubscribeLagEvent { missed },
+        &HashMap::new(),
+    )
+}
@@ -0,0 +1,285 @@
+use std::{
+    fmt,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+use super::{FileSink, WebhookMode, WebhookSink};
+use crate::{
+    clock::{SharedClock, SystemClock},
+    info,
+    random::RandomSource,
+    sdk::io::cloudevents::v1::{
+        CloudEvent,
+        cloud_event::{CloudEventAttributeValue, cloud_event_attribute_value::Attr},
+    },
+    warning,
+};
+
+pub const SINK_FILE_ENV: &str = "EVENT_SINK_FILE";
+pub const SINK_WEBHOOK_ENV: &str = "EVENT_SINK_WEBHOOK";
+pub const SINK_WEBHOOK_MODE_ENV: &str = "EVENT_SINK_WEBHOOK_MODE";
+pub const DEAD_LETTER_FILE_ENV: &str = "EVENT_DEAD_LETTER_FILE";
+
+// Destination for the CloudEvents the service emits. Deliveries run on a
+// dedicated task per sink (see `SinkDispatcher`), never on the request path.
+#[tonic::async_trait]
+pub trait EventSink: fmt::Debug + Send + Sync + 'static {
+    fn name(&self) -> &str;
+
+    async fn deliver(&self, event: &CloudEvent) -> Result<(), SinkError>;
+}
+
+pub type SharedSink = Arc<dyn EventSink>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SinkError {
+    // Worth trying again, e.g. a timeout or a 503.
+    Retryable(String),
+    // Will fail the same way every time, e.g. a 400.
+    Permanent(String),
+}
+
+impl SinkError {
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, SinkError::Retryable(_))
+    }
+}
+
+impl fmt::Display for SinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SinkError::Retryable(e) => write!(f, "{}", e),
+            SinkError::Permanent(e) => write!(f, "{} (permanent)", e),
+        }
+    }
+}
+
+impl std::error::Error for SinkError {}
+
+// Backoff doubles from `initial_backoff` up to `max_backoff`. Each wait is
+// drawn from the upper half of the current backoff, so sinks failing together
+// do not retry in lockstep.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    // Attempts including the first one.
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SinkOptions {
+    pub retry: RetryPolicy,
+    // Receives events that ran out of attempts or did not fit in the queue,
+    // tagged with `deadlettersink` and `deadletterreason` attributes.
+    pub dead_letter: Option<SharedSink>,
+    // Bounds both the sink's queue and the queue in front of its dead-letter
+    // sink.
+    pub queue_capacity: usize,
+    pub clock: SharedClock,
+    pub rng: RandomSource,
+}
+
+impl Default for SinkOptions {
+    fn default() -> Self {
+        Self {
+            retry: RetryPolicy::default(),
+            dead_letter: None,
+            queue_capacity: 1024,
+            clock: Arc::new(SystemClock),
+            rng: RandomSource::default(),
+        }
+    }
+}
+
+// Queues events for one sink and delivers them in order on a background task,
+// retrying with exponential backoff before dead-lettering. Events that do not
+// fit in the queue go to a second bounded queue drained by its own task into
+// the dead-letter sink. When that one is full too, the event is dropped and
+// counted; only the start and the end of a run of drops are logged.
+#[derive(Debug, Clone)]
+pub struct SinkDispatcher {
+    name: String,
+    queue: mpsc::Sender<CloudEvent>,
+    overflow: Option<mpsc::Sender<CloudEvent>>,
+    dropped: Arc<AtomicU64>,
+    dropping: Arc<AtomicBool>,
+}
+
+impl SinkDispatcher {
+    pub fn spawn(sink: SharedSink, options: SinkOptions) -> Self {
+        let capacity = options.queue_capacity.max(1);
+        let (queue, mut rx) = mpsc::channel::<CloudEvent>(capacity);
+        let name = sink.name().to_string();
+        let overflow = options.dead_letter.clone().map(|dead_letter| {
+            let (overflow, mut rx) = mpsc::channel::<CloudEvent>(capacity);
+            let name = name.clone();
+            tokio::spawn(async move {
+                while let Some(event) = rx.recv().await {
+                    send_to_dead_letter(Some(&dead_letter), &name, event, "queue full").await;
+                }
+            });
+            overflow
+        });
+
+        tokio::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                deliver_with_retry(&sink, &options, event).await;
+            }
+        });
+
+        Self {
+            name,
+            queue,
+            overflow,
+            dropped: Arc::new(AtomicU64::new(0)),
+            dropping: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // Events dropped because both the queue and the dead-letter queue were
+    // full (or no dead-letter sink is configured).
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    pub fn enqueue(&self, event: &CloudEvent) {
+        match self.queue.try_send(event.clone()) {
+            Ok(()) => {
+                if self.dropping.swap(false, Ordering::Relaxed) {
+                    info!(
+                        "Event sink {} caught up ({} events dropped so far)",
+                        self.name,
+                        self.dropped()
+                    );
+                }
+            }
+            Err(TrySendError::Full(event)) => {
+                if let Some(overflow) = &self.overflow
+                    && overflow.try_send(event).is_ok()
+                {
+                    return;
+                }
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                if !self.dropping.swap(true, Ordering::Relaxed) {
+                    warning!(
+                        "Event sink {} is full; dropping events until it catches up",
+                        self.name
+                    );
+                }
+            }
+            Err(TrySendError::Closed(_)) => {
+                warning!("Event sink {} is no longer running", self.name);
+            }
+        }
+    }
+}
+
+async fn deliver_with_retry(sink: &SharedSink, options: &SinkOptions, event: CloudEvent) {
+    let mut backoff = options.retry.initial_backoff;
+    let mut attempt = 1;
+
+    loop {
+        match sink.deliver(&event).await {
+            Ok(()) => return,
+            Err(e) if e.is_retryable() && attempt < options.retry.max_attempts => {
+                options.clock.sleep(jittered(backoff, &options.rng)).await;
+                backoff = (backoff * 2).min(options.retry.max_backoff);
+                attempt += 1;
+            }
+            Err(e) => {
+                let reason = format!("{} after {} attempt(s)", e, attempt);
+                send_to_dead_letter(options.dead_letter.as_ref(), sink.name(), event, &reason)
+                    .await;
+                return;
+            }
+        }
+    }
+}
+
+// Picks a wait between half of `backoff` and all of it.
+fn jittered(backoff: Duration, rng: &RandomSource) -> Duration {
+    let half = backoff / 2;
+    half + rng.random_range(Duration::ZERO..=backoff - half)
+}
+
+async fn send_to_dead_letter(
+    dead_letter: Option<&SharedSink>,
+    sink: &str,
+    mut event: CloudEvent,
+    reason: &str,
+) {
+    let Some(dead_letter) = dead_letter else {
+        warning!("Dropped event {} for sink {}: {}", event.id, sink, reason);
+        return;
+    };
+
+    for (key, value) in [("deadlettersink", sink), ("deadletterreason", reason)] {
+        event.attributes.insert(
+            key.to_string(),
+            CloudEventAttributeValue {
+                attr: Some(Attr::CeString(value.to_string())),
+            },
+        );
+    }
+    if let Err(e) = dead_letter.deliver(&event).await {
+        warning!(
+            "Dropped event {} for sink {}: {} (dead letter failed: {})",
+            event.id,
+            sink,
+            reason,
+            e
+        );
+    }
+}
+
+// Builds the sinks configured through EVENT_SINK_FILE, EVENT_SINK_WEBHOOK
+// (with EVENT_SINK_WEBHOOK_MODE) and EVENT_DEAD_LETTER_FILE. Must be called
+// from within the Tokio runtime.
+pub fn dispatchers_from_env() -> Result<Vec<SinkDispatcher>, String> {
+    let options = SinkOptions {
+        dead_letter: std::env::var(DEAD_LETTER_FILE_ENV)
+            .ok()
+            .map(|path| Arc::new(FileSink::new(path)) as SharedSink),
+        ..Default::default()
+    };
+
+    let mut sinks: Vec<SharedSink> = Vec::new();
+    if let Ok(path) = std::env::var(SINK_FILE_ENV) {
+        sinks.push(Arc::new(FileSink::new(path)));
+    }
+    if let Ok(url) = std::env::var(SINK_WEBHOOK_ENV) {
+        let mode = match std::env::var(SINK_WEBHOOK_MODE_ENV) {
+            Ok(mode) => mode.parse()?,
+            Err(_) => WebhookMode::default(),
+        };
+        sinks.push(Arc::new(
+            WebhookSink::new(url, mode).map_err(|e| e.to_string())?,
+        ));
+    }
+
+    Ok(sinks
+        .into_iter()
+        .map(|sink| SinkDispatcher::spawn(sink, options.clone()))
+        .collect())
+}
@@ -0,0 +1,121 @@
+use std::{str::FromStr, time::Duration};
+
+use reqwest::{
+    Client, StatusCode,
+    header::{CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue},
+};
+
+use super::{
+    EventSink, SinkError,
+    format::{
+        STRUCTURED_CONTENT_TYPE, binary_attributes, binary_body, percent_encode, structured_json,
+    },
+};
+use crate::sdk::io::cloudevents::v1::CloudEvent;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WebhookMode {
+    // Attributes in `ce-*` headers, the payload as the body.
+    #[default]
+    Binary,
+    // The whole event as `application/cloudevents+json`.
+    Structured,
+}
+
+impl FromStr for WebhookMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "binary" => Ok(WebhookMode::Binary),
+            "structured" => Ok(WebhookMode::Structured),
+            other => Err(format!("unknown webhook mode: {}", other)),
+        }
+    }
+}
+
+// POSTs every event to an HTTP endpoint using the CloudEvents HTTP binding.
+// Connection errors, timeouts, 408, 429 and 5xx responses are retried.
+#[derive(Debug)]
+pub struct WebhookSink {
+    name: String,
+    url: String,
+    mode: WebhookMode,
+    client: Client,
+}
+
+impl WebhookSink {
+    pub fn new(url: impl Into<String>, mode: WebhookMode) -> Result<Self, SinkError> {
+        let url = url.into();
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .map_err(|e| SinkError::Permanent(e.to_string()))?;
+        Ok(Self {
+            name: format!("webhook:{}", url),
+            url,
+            mode,
+            client,
+        })
+    }
+
+    fn binary_request(&self, event: &CloudEvent) -> Result<(HeaderMap, Vec<u8>), SinkError> {
+        let mut headers = HeaderMap::new();
+        for (key, value) in binary_attributes(event) {
+            let name = HeaderName::from_str(&format!("ce-{}", key.to_ascii_lowercase()))
+                .map_err(|e| SinkError::Permanent(format!("attribute {}: {}", key, e)))?;
+            let value = HeaderValue::from_str(&percent_encode(&value))
+                .map_err(|e| SinkError::Permanent(format!("attribute {}: {}", key, e)))?;
+            headers.insert(name, value);
+        }
+        let (content_type, body) = binary_body(event);
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+        Ok((headers, body))
+    }
+
+    fn structured_request(&self, event: &CloudEvent) -> Result<(HeaderMap, Vec<u8>), SinkError> {
+        let body = serde_json::to_vec(&structured_json(event))
+            .map_err(|e| SinkError::Permanent(e.to_string()))?;
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static(STRUCTURED_CONTENT_TYPE),
+        );
+        Ok((headers, body))
+    }
+}
+
+#[tonic::async_trait]
+impl EventSink for WebhookSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn deliver(&self, event: &CloudEvent) -> Result<(), SinkError> {
+        let (headers, body) = match self.mode {
+            WebhookMode::Binary => self.binary_request(event)?,
+            WebhookMode::Structured => self.structured_request(event)?,
+        };
+
+        let response = self
+            .client
+            .post(&self.url)
+            .headers(headers)
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| SinkError::Retryable(e.to_string()))?;
+
+        let status = response.status();
+        if status.is_success() {
+            Ok(())
+        } else if status.is_server_error()
+            || status == StatusCode::TOO_MANY_REQUESTS
+            || status == StatusCode::REQUEST_TIMEOUT
+        {
+            Err(SinkError::Retryable(format!("webhook returned {}", status)))
+        } else {
+            Err(SinkError::Permanent(format!("webhook returned {}", status)))
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use prost::Message;
+use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor, SerializeOptions};
+use tonic::Status;
+
+use crate::FILE_DESCRIPTOR_SET;
+
+// The descriptors of every message the service speaks, so requests and
+// responses follow the proto3 JSON mapping of the current `.proto` files.
+pub fn descriptor_pool() -> Result<DescriptorPool, prost_reflect::DescriptorError> {
+    // The global pool carries the well-known types (Any, Timestamp).
+    let mut pool = DescriptorPool::global();
+    pool.decode_file_descriptor_set(FILE_DESCRIPTOR_SET)?;
+    Ok(pool)
+}
+
+// JSON codec for the request and response messages of one RPC.
+#[derive(Debug, Clone)]
+pub struct MethodCodec {
+    path: String,
+    input: MessageDescriptor,
+    output: MessageDescriptor,
+}
+
+impl MethodCodec {
+    pub fn new(pool: &DescriptorPool, service: &str, method: &str) -> Result<Self, String> {
+        let method = pool
+            .get_service_by_name(service)
+            .and_then(|service| service.methods().find(|m| m.name() == method))
+            .ok_or_else(|| format!("{}/{} is not in the descriptor set", service, method))?;
+        Ok(Self {
+            path: format!("/{}/{}", service, method.name()),
+            input: method.input(),
+            output: method.output(),
+        })
+    }
+
+    // One codec per method of every service in the pool.
+    pub fn all(pool: &DescriptorPool) -> Vec<Self> {
+        pool.services()
+            .flat_map(|service| service.methods().collect::<Vec<_>>())
+            .map(|method| Self {
+                path: format!("/{}/{}", method.parent_service().full_name(), method.name()),
+                input: method.input(),
+                output: method.output(),
+            })
+            .collect()
+    }
+
+    // The gRPC path, e.g. `/basic.v1.BasicService/Hello`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    // An empty body decodes to the default message.
+    pub fn decode<T: Message + Default>(&self, json: &[u8]) -> Result<T, Status> {
+        self.decode_dynamic(json)?
+            .transcode_to()
+            .map_err(|e| Status::invalid_argument(e.to_string()))
+    }
+
+    pub fn encode<T: Message>(&self, message: &T) -> Result<String, Status> {
+        let mut dynamic = DynamicMessage::new(self.output.clone());
+        dynamic
+            .transcode_from(message)
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Self::to_json(&dynamic)
+    }
+
+    // JSON request to its protobuf encoding.
+    pub fn request_to_binary(&self, json: &[u8]) -> Result<Vec<u8>, Status> {
+        Ok(self.decode_dynamic(json)?.encode_to_vec())
+    }
+
+    // Protobuf-encoded response to JSON.
+    pub fn response_to_json(&self, binary: &[u8]) -> Result<String, Status> {
+        let dynamic = DynamicMessage::decode(self.output.clone(), binary)
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Self::to_json(&dynamic)
+    }
+
+    fn decode_dynamic(&self, json: &[u8]) -> Result<DynamicMessage, Status> {
+        if json.iter().all(u8::is_ascii_whitespace) {
+            return Ok(DynamicMessage::new(self.input.clone()));
+        }
+        let mut deserializer = serde_json::Deserializer::from_slice(json);
+        DynamicMessage::deserialize(self.input.clone(), &mut deserializer)
+            .and_then(|message| deserializer.end().map(|()| message))
+            .map_err(|e| {
+                Status::invalid_argument(format!("invalid {} JSON: {}", self.input.full_name(), e))
+            })
+    }
+
+    fn to_json(message: &DynamicMessage) -> Result<String, Status> {
+        let mut json = Vec::new();
+        let mut serializer = serde_json::Serializer::new(&mut json);
+        message
+            .serialize_with_options(&mut serializer, &SerializeOptions::new())
+            .map_err(|e| Status::internal(e.to_string()))?;
+        String::from_utf8(json).map_err(|e| Status::internal(e.to_string()))
+    }
+}
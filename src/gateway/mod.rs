@@ -0,0 +1,340 @@
+mod json;
+
+use std::{convert::Infallible, fmt, net::SocketAddr};
+
+use axum::{
+    Router,
+    body::{Body, Bytes},
+    extract::{
+        ConnectInfo, State,
+        ws::{Message as WsMessage, WebSocket, WebSocketUpgrade},
+    },
+    http::{Extensions, HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio_stream::{StreamExt, wrappers::ReceiverStream};
+use tonic::{Code, Status, Streaming, metadata::MetadataMap, transport::server::TcpConnectInfo};
+use tower::Layer;
+
+pub use json::{MethodCodec, descriptor_pool};
+
+use crate::{
+    auth::{AuthLayer, AuthService, Authenticator},
+    connect::error_details,
+    idempotency::IDEMPOTENCY_KEY_HEADER,
+    payload::{PayloadConfig, PayloadService},
+    rate_limit::IDENTITY_METADATA_KEY,
+    sdk::basic::{
+        service::v1::{
+            BackgroundRequest, HelloBatchRequest, HelloRequest, TalkRequest, TalkResponse,
+        },
+        v1::{basic_service_client::BasicServiceClient, basic_service_server::BasicServiceServer},
+    },
+    server::BoxError,
+    service::BasicServiceV1,
+};
+
+pub const GATEWAY_ADDR_ENV: &str = "GATEWAY_ADDR";
+
+const SERVICE: &str = "basic.v1.BasicService";
+const NDJSON: &str = "application/x-ndjson";
+const EVENT_STREAM: &str = "text/event-stream";
+
+// Request headers passed on to the RPC as metadata.
+const FORWARDED_HEADERS: [&str; 4] = [
+    "authorization",
+    "accept-language",
+    IDEMPOTENCY_KEY_HEADER,
+    IDENTITY_METADATA_KEY,
+];
+
+type InProcess =
+    BasicServiceClient<AuthService<PayloadService<BasicServiceServer<BasicServiceV1>>>>;
+
+// REST/JSON facade over `BasicServiceV1`:
+//
+//   POST /v1/hello       HelloRequest -> HelloResponse
+//   POST /v1/hello:batch HelloBatchRequest -> CloudEventBatch
+//   POST /v1/background  BackgroundRequest -> BackgroundResponse per line
+//                        (NDJSON), or per event with `Accept: text/event-stream`
+//   GET  /v1/talk        WebSocket, one TalkRequest/TalkResponse per text frame
+//
+// Calls go through the regular gRPC stack in-process, authentication and
+// payload limits included, and bodies use the proto3 JSON mapping of
+// FILE_DESCRIPTOR_SET.
+#[derive(Clone)]
+pub struct Gateway {
+    client: InProcess,
+    hello: MethodCodec,
+    hello_batch: MethodCodec,
+    background: MethodCodec,
+    talk: MethodCodec,
+}
+
+impl fmt::Debug for Gateway {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Gateway")
+            .field("hello", &self.hello)
+            .field("hello_batch", &self.hello_batch)
+            .field("background", &self.background)
+            .field("talk", &self.talk)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Gateway {
+    pub fn new(
+        service: BasicServiceV1,
+        authenticator: Option<Authenticator>,
+        payload: &PayloadConfig,
+    ) -> Result<Self, BoxError> {
+        let pool = descriptor_pool()?;
+        let server =
+            AuthLayer::new(authenticator).layer(payload.apply(BasicServiceServer::new(service)));
+        Ok(Self {
+            client: BasicServiceClient::new(server)
+                .max_decoding_message_size(payload.max_send_message_bytes),
+            hello: MethodCodec::new(&pool, SERVICE, "Hello")?,
+            hello_batch: MethodCodec::new(&pool, SERVICE, "HelloBatch")?,
+            background: MethodCodec::new(&pool, SERVICE, "Background")?,
+            talk: MethodCodec::new(&pool, SERVICE, "Talk")?,
+        })
+    }
+
+    // Serve with `into_make_service_with_connect_info::<SocketAddr>()` so
+    // per-connection limits see the caller's address.
+    pub fn router(self) -> Router {
+        Router::new()
+            .route("/v1/hello", post(hello))
+            .route("/v1/hello:batch", post(hello_batch))
+            .route("/v1/background", post(background))
+            .route("/v1/talk", get(talk))
+            .with_state(self)
+    }
+}
+
+fn request<T>(message: T, headers: &HeaderMap, extensions: &Extensions) -> tonic::Request<T> {
+    let mut forwarded = HeaderMap::new();
+    for name in FORWARDED_HEADERS {
+        if let Some(value) = headers.get(name) {
+            forwarded.insert(name, value.clone());
+        }
+    }
+
+    let mut request = tonic::Request::new(message);
+    *request.metadata_mut() = MetadataMap::from_headers(forwarded);
+    if let Some(ConnectInfo(addr)) = extensions.get::<ConnectInfo<SocketAddr>>() {
+        request.extensions_mut().insert(TcpConnectInfo {
+            local_addr: None,
+            remote_addr: Some(*addr),
+        });
+    }
+    request
+}
+
+async fn hello(
+    State(gateway): State<Gateway>,
+    headers: HeaderMap,
+    extensions: Extensions,
+    body: Bytes,
+) -> Response {
+    let result = async {
+        let message: HelloRequest = gateway.hello.decode(&body)?;
+        let response = gateway
+            .client
+            .clone()
+            .hello(request(message, &headers, &extensions))
+            .await?;
+        gateway.hello.encode(response.get_ref())
+    }
+    .await;
+
+    match result {
+        Ok(json) => ([(header::CONTENT_TYPE, "application/json")], json).into_response(),
+        Err(status) => error_response(&status),
+    }
+}
+
+async fn hello_batch(
+    State(gateway): State<Gateway>,
+    headers: HeaderMap,
+    extensions: Extensions,
+    body: Bytes,
+) -> Response {
+    let result = async {
+        let message: HelloBatchRequest = gateway.hello_batch.decode(&body)?;
+        let response = gateway
+            .client
+            .clone()
+            .hello_batch(request(message, &headers, &extensions))
+            .await?;
+        gateway.hello_batch.encode(response.get_ref())
+    }
+    .await;
+
+    match result {
+        Ok(json) => ([(header::CONTENT_TYPE, "application/json")], json).into_response(),
+        Err(status) => error_response(&status),
+    }
+}
+
+async fn background(
+    State(gateway): State<Gateway>,
+    headers: HeaderMap,
+    extensions: Extensions,
+    body: Bytes,
+) -> Response {
+    let result = async {
+        let message: BackgroundRequest = gateway.background.decode(&body)?;
+        let response = gateway
+            .client
+            .clone()
+            .background(request(message, &headers, &extensions))
+            .await?;
+        Ok::<_, Status>(response.into_inner())
+    }
+    .await;
+    let updates = match result {
+        Ok(updates) => updates,
+        Err(status) => return error_response(&status),
+    };
+
+    let sse = headers
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains(EVENT_STREAM));
+    let codec = gateway.background;
+    let lines = updates.map(move |update| {
+        let (event, json) = match update.and_then(|update| codec.encode(&update)) {
+            Ok(json) => ("snapshot", json),
+            Err(status) => ("error", error_json(&status).to_string()),
+        };
+        let chunk = if sse {
+            format!("event: {}\ndata: {}\n\n", event, json)
+        } else {
+            format!("{}\n", json)
+        };
+        Ok::<_, Infallible>(Bytes::from(chunk))
+    });
+
+    let content_type = if sse { EVENT_STREAM } else { NDJSON };
+    (
+        [(header::CONTENT_TYPE, HeaderValue::from_static(content_type))],
+        Body::from_stream(lines),
+    )
+        .into_response()
+}
+
+// The RPC is opened before upgrading, so authentication failures are plain
+// HTTP errors.
+async fn talk(
+    State(gateway): State<Gateway>,
+    headers: HeaderMap,
+    extensions: Extensions,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    let (tx, rx) = mpsc::channel(16);
+    let outbound = request(ReceiverStream::new(rx), &headers, &extensions);
+    let inbound = match gateway.client.clone().talk(outbound).await {
+        Ok(response) => response.into_inner(),
+        Err(status) => return error_response(&status),
+    };
+    upgrade.on_upgrade(move |socket| relay_talk(socket, gateway.talk, tx, inbound))
+}
+
+async fn relay_talk(
+    mut socket: WebSocket,
+    codec: MethodCodec,
+    tx: mpsc::Sender<TalkRequest>,
+    mut inbound: Streaming<TalkResponse>,
+) {
+    let mut tx = Some(tx);
+    loop {
+        tokio::select! {
+            frame = socket.recv(), if tx.is_some() => match frame {
+                Some(Ok(WsMessage::Text(text))) => {
+                    match codec.decode::<TalkRequest>(text.as_bytes()) {
+                        Ok(message) => {
+                            if let Some(sender) = &tx
+                                && sender.send(message).await.is_err()
+                            {
+                                tx = None;
+                            }
+                        }
+                        Err(status) => return close_with(socket, &status).await,
+                    }
+                }
+                Some(Ok(WsMessage::Close(_))) | None | Some(Err(_)) => tx = None,
+                Some(Ok(_)) => {}
+            },
+            response = inbound.message() => match response {
+                Ok(Some(response)) => {
+                    let json = match codec.encode(&response) {
+                        Ok(json) => json,
+                        Err(status) => return close_with(socket, &status).await,
+                    };
+                    if socket.send(WsMessage::Text(json.into())).await.is_err() {
+                        return;
+                    }
+                }
+                Ok(None) => {
+                    let _ = socket.send(WsMessage::Close(None)).await;
+                    return;
+                }
+                Err(status) => return close_with(socket, &status).await,
+            },
+        }
+    }
+}
+
+// Sends the error as a final JSON frame and closes the socket.
+async fn close_with(mut socket: WebSocket, status: &Status) {
+    let _ = socket
+        .send(WsMessage::Text(error_json(status).to_string().into()))
+        .await;
+    let _ = socket.send(WsMessage::Close(None)).await;
+}
+
+fn error_json(status: &Status) -> serde_json::Value {
+    let mut error = json!({
+        "code": status.code() as i32,
+        "message": status.message(),
+    });
+    let details = error_details(status);
+    if !details.is_empty() {
+        error["details"] = details.into();
+    }
+    error
+}
+
+fn error_response(status: &Status) -> Response {
+    (
+        http_status(status.code()),
+        [(header::CONTENT_TYPE, "application/json")],
+        error_json(status).to_string(),
+    )
+        .into_response()
+}
+
+// The mapping google.api.http transcoders use.
+pub fn http_status(code: Code) -> StatusCode {
+    match code {
+        Code::Ok => StatusCode::OK,
+        Code::Cancelled => StatusCode::from_u16(499).unwrap(),
+        Code::InvalidArgument | Code::FailedPrecondition | Code::OutOfRange => {
+            StatusCode::BAD_REQUEST
+        }
+        Code::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
+        Code::NotFound => StatusCode::NOT_FOUND,
+        Code::AlreadyExists | Code::Aborted => StatusCode::CONFLICT,
+        Code::PermissionDenied => StatusCode::FORBIDDEN,
+        Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+        Code::ResourceExhausted => StatusCode::TOO_MANY_REQUESTS,
+        Code::Unimplemented => StatusCode::NOT_IMPLEMENTED,
+        Code::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        Code::Unknown | Code::Internal | Code::DataLoss => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
@@ -0,0 +1,217 @@
+use std::{collections::HashMap, io, path::Path};
+
+use tonic::{Request, metadata::MetadataMap};
+
+use crate::sdk::basic::service::v1::HelloRequest;
+
+pub const GREETINGS_DIR_ENV: &str = "GREETINGS_DIR";
+pub const DEFAULT_LOCALE_ENV: &str = "GREETING_DEFAULT_LOCALE";
+
+// CloudEvent attribute that carries the locale a greeting was rendered in.
+pub const LOCALE_ATTRIBUTE: &str = "locale";
+
+// Consulted in order after `HelloRequest.locale`.
+pub const ACCEPT_LANGUAGE_HEADERS: [&str; 2] = ["grpc-accept-language", "accept-language"];
+
+const NAME_PLACEHOLDER: &str = "{name}";
+
+const BUILTIN: [(&str, &str); 8] = [
+    ("en", "Hello, {name}!"),
+    ("de", "Hallo, {name}!"),
+    ("es", "¡Hola, {name}!"),
+    ("fr", "Bonjour, {name} !"),
+    ("it", "Ciao, {name}!"),
+    ("nl", "Hallo, {name}!"),
+    ("pt", "Olá, {name}!"),
+    ("ja", "こんにちは、{name}さん！"),
+];
+
+// A rendered greeting, the sanitized name it greets and the locale whose
+// template produced it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Greeting {
+    pub name: String,
+    pub locale: String,
+    pub text: String,
+}
+
+// Greeting templates keyed by lowercase BCP 47 tag. Each template contains
+// `{name}` once or more.
+#[derive(Debug, Clone)]
+pub struct Greetings {
+    templates: HashMap<String, String>,
+    default_locale: String,
+}
+
+impl Default for Greetings {
+    fn default() -> Self {
+        Self {
+            templates: BUILTIN
+                .iter()
+                .map(|(locale, template)| (locale.to_string(), template.to_string()))
+                .collect(),
+            default_locale: "en".to_string(),
+        }
+    }
+}
+
+impl Greetings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_template(mut self, locale: &str, template: impl Into<String>) -> Self {
+        self.templates
+            .insert(locale.to_ascii_lowercase(), template.into());
+        self
+    }
+
+    // Used when no requested locale has a template. Fails unless the locale
+    // has a template itself, so add templates first.
+    pub fn with_default_locale(mut self, locale: &str) -> Result<Self, String> {
+        let locale = locale.to_ascii_lowercase();
+        if !self.templates.contains_key(&locale) {
+            return Err(format!("no template for {}", locale));
+        }
+        self.default_locale = locale;
+        Ok(self)
+    }
+
+    // Adds or replaces templates from `<locale>.txt` files, e.g. `de-ch.txt`.
+    pub fn with_dir(mut self, dir: impl AsRef<Path>) -> io::Result<Self> {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|extension| extension != "txt") {
+                continue;
+            }
+            let Some(locale) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let template = std::fs::read_to_string(&path)?.trim().to_string();
+            if !template.contains(NAME_PLACEHOLDER) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{} has no {} placeholder", path.display(), NAME_PLACEHOLDER),
+                ));
+            }
+            self = self.with_template(locale, template);
+        }
+        Ok(self)
+    }
+
+    // GREETINGS_DIR adds templates on top of the built-in ones and
+    // GREETING_DEFAULT_LOCALE picks the last resort (default `en`).
+    pub fn from_env() -> Result<Self, String> {
+        let mut greetings = Self::default();
+        if let Ok(dir) = std::env::var(GREETINGS_DIR_ENV) {
+            greetings = greetings
+                .with_dir(&dir)
+                .map_err(|e| format!("invalid value for {}: {}: {}", GREETINGS_DIR_ENV, dir, e))?;
+        }
+        if let Ok(locale) = std::env::var(DEFAULT_LOCALE_ENV) {
+            greetings = greetings
+                .with_default_locale(&locale)
+                .map_err(|e| format!("invalid value for {}: {}", DEFAULT_LOCALE_ENV, e))?;
+        }
+        Ok(greetings)
+    }
+
+    // Renders the greeting for `HelloRequest.name`, or `message` when no name
+    // is given, in the best available locale.
+    pub fn greet(&self, request: &Request<HelloRequest>) -> Greeting {
+        let hello = request.get_ref();
+        let name = hello
+            .name
+            .as_deref()
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or(&hello.message);
+        let locale = self.negotiate(hello.locale.as_deref(), request.metadata());
+        self.render(locale, name)
+    }
+
+    // `name` greeted with the template of a locale `negotiate` returned.
+    pub fn render(&self, locale: &str, name: &str) -> Greeting {
+        let template = self
+            .templates
+            .get(locale)
+            .map_or("Hello, {name}!", String::as_str);
+        let name = sanitize_name(name);
+        Greeting {
+            text: template.replace(NAME_PLACEHOLDER, &name),
+            locale: locale.to_string(),
+            name,
+        }
+    }
+
+    // Tries the requested locale, then the accept-language metadata by
+    // descending quality, and finally the default locale. Each tag falls back
+    // to its shorter prefixes, so `de-CH-1996` tries `de-ch-1996`, `de-ch`
+    // and `de`.
+    pub fn negotiate<'a>(&'a self, requested: Option<&str>, metadata: &MetadataMap) -> &'a str {
+        let requested = requested.map(str::to_string).into_iter();
+        let accepted = ACCEPT_LANGUAGE_HEADERS.iter().flat_map(|header| {
+            metadata
+                .get(*header)
+                .and_then(|value| value.to_str().ok())
+                .map(accept_language)
+                .unwrap_or_default()
+        });
+        requested
+            .chain(accepted)
+            .find_map(|tag| self.lookup(&tag))
+            .unwrap_or(&self.default_locale)
+    }
+
+    fn lookup(&self, tag: &str) -> Option<&str> {
+        let mut tag = tag.trim().to_ascii_lowercase();
+        loop {
+            if let Some((locale, _)) = self.templates.get_key_value(&tag) {
+                return Some(locale);
+            }
+            let cut = tag.rfind('-')?;
+            tag.truncate(cut);
+        }
+    }
+}
+
+// Language ranges of an accept-language value, best first. Ranges with
+// `q=0` and the `*` wildcard are dropped.
+fn accept_language(value: &str) -> Vec<String> {
+    let mut ranges: Vec<(String, f32)> = value
+        .split(',')
+        .filter_map(|range| {
+            let mut parts = range.split(';');
+            let tag = parts.next()?.trim();
+            let quality = parts
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            (!tag.is_empty() && tag != "*" && quality > 0.0).then(|| (tag.to_string(), quality))
+        })
+        .collect();
+    // Stable, so equal qualities keep the caller's order.
+    ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranges.into_iter().map(|(tag, _)| tag).collect()
+}
+
+// Names end up in HTML pages and terminals downstream: control and bidi
+// override characters are dropped and HTML special characters escaped.
+pub fn sanitize_name(name: &str) -> String {
+    let mut sanitized = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            '&' => sanitized.push_str("&amp;"),
+            '<' => sanitized.push_str("&lt;"),
+            '>' => sanitized.push_str("&gt;"),
+            '"' => sanitized.push_str("&quot;"),
+            '\'' => sanitized.push_str("&#39;"),
+            c if c.is_control() || is_bidi_control(c) => {}
+            c => sanitized.push(c),
+        }
+    }
+    sanitized
+}
+
+fn is_bidi_control(c: char) -> bool {
+    matches!(c, '\u{200e}' | '\u{200f}' | '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}')
+}
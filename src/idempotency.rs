@@ -0,0 +1,117 @@
+use prost::Message;
+use ring::digest::{SHA256, digest};
+use tokio_stream::StreamExt;
+use tonic::Request;
+
+use crate::{
+    auth::Principal,
+    error::ServiceError,
+    sdk::{basic::service::v1::State, io::cloudevents::v1::CloudEvent},
+    state::StateManager,
+};
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+pub const MAX_KEY_LEN: usize = 255;
+
+// Client-chosen key making retries of a call safe. Keys are scoped to the RPC
+// and the caller: the authenticated principal, or the peer address for
+// anonymous callers. They live in the job state store as a job under a
+// derived hash, so they share its TTL, capacity bound and persistence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdempotencyKey {
+    job: String,
+    // SHA-256 of the encoded request, so a key reused for a different
+    // request is rejected instead of replaying the wrong result.
+    fingerprint: String,
+}
+
+impl IdempotencyKey {
+    // The key sent in the `idempotency-key` header, if any. Keys must be
+    // 1..=255 visible ASCII characters.
+    pub fn from_request<T: Message>(
+        request: &Request<T>,
+        method: &str,
+    ) -> Result<Option<Self>, ServiceError> {
+        let Some(value) = request.metadata().get(IDEMPOTENCY_KEY_HEADER) else {
+            return Ok(None);
+        };
+        let key = value
+            .to_str()
+            .ok()
+            .filter(|key| {
+                !key.is_empty()
+                    && key.len() <= MAX_KEY_LEN
+                    && key.bytes().all(|b| b.is_ascii_graphic())
+            })
+            .ok_or_else(|| {
+                ServiceError::invalid_field(
+                    IDEMPOTENCY_KEY_HEADER,
+                    format!("must be 1 to {} visible ASCII characters", MAX_KEY_LEN),
+                )
+            })?;
+        let caller = match request.extensions().get::<Principal>() {
+            Some(principal) => format!("principal:{}", principal.subject),
+            None => request.remote_addr().map_or_else(
+                || "anonymous".to_string(),
+                |addr| format!("peer:{}", addr.ip()),
+            ),
+        };
+        let fingerprint = digest(&SHA256, &request.get_ref().encode_to_vec())
+            .as_ref()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+
+        Ok(Some(Self {
+            job: format!("idempotency:{}:{}:{}", method, caller, key),
+            fingerprint,
+        }))
+    }
+
+    // Hash of the job that holds whatever the first call produced.
+    pub fn job(&self) -> &str {
+        &self.job
+    }
+
+    // Starts the job for this key. Returns `false` if an earlier call already
+    // did, and fails if that call sent a different request.
+    pub fn claim(&self, state: &StateManager) -> Result<bool, ServiceError> {
+        match state.start_once(&self.job, State::Process, Some(self.fingerprint.clone())) {
+            None => Ok(true),
+            Some(record) if record.fingerprint.as_ref() == Some(&self.fingerprint) => Ok(false),
+            Some(_) => Err(ServiceError::IdempotencyKeyReused {
+                job: self.job.clone(),
+            }),
+        }
+    }
+
+    // Remembers the event a unary call answered with.
+    pub fn remember(&self, state: &StateManager, event: &CloudEvent) {
+        state.set_event(&self.job, event.clone());
+        state.finish(&self.job, State::Complete);
+    }
+
+    // The event remembered for this key. Waits for the first call if it is
+    // still running.
+    pub async fn replay(&self, state: &StateManager) -> Result<CloudEvent, ServiceError> {
+        let mut watch = state.subscribe(&self.job);
+        if state.job(&self.job).is_none() {
+            return Err(self.aborted("idempotency key expired, retry"));
+        }
+        while let Some(update) = watch.next().await {
+            if update.record.is_finished() {
+                return update.record.event.ok_or_else(|| {
+                    self.aborted("the first request with this idempotency key did not complete")
+                });
+            }
+        }
+        Err(self.aborted("idempotency key expired, retry"))
+    }
+
+    fn aborted(&self, description: &str) -> ServiceError {
+        ServiceError::JobAborted {
+            job: self.job.clone(),
+            description: description.to_string(),
+        }
+    }
+}
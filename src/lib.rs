@@ -17,8 +17,27 @@ pub mod sdk {
         }
     }
 }
+pub mod auth;
+pub mod client;
+pub mod clock;
+pub mod connect;
+pub mod deadline;
+pub mod error;
+pub mod events;
+pub mod gateway;
+pub mod greeting;
+pub mod idempotency;
+pub mod payload;
+pub mod random;
+pub mod rate_limit;
+pub mod server;
+pub mod service;
+pub mod state;
 pub mod talk;
 pub mod utils;
+pub mod validation;
+pub mod web;
+pub mod workers;
 
 pub const FILE_DESCRIPTOR_SET: &[u8] = include_bytes!("sdk/descriptor.bin");
 
@@ -0,0 +1,341 @@
+use std::{
+    convert::Infallible,
+    future::Future,
+    io::{Read, Write},
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use http::{HeaderValue, header::HeaderName};
+use http_body::Frame;
+use tonic::{Status, body::Body, codec::CompressionEncoding, server::NamedService};
+use tower::{Layer, Service};
+
+use crate::{
+    error::ServiceError, sdk::basic::v1::basic_service_server::BasicServiceServer,
+    service::BasicServiceV1,
+};
+
+pub const COMPRESSION_ENV: &str = "GRPC_COMPRESSION";
+pub const COMPRESSION_MIN_BYTES_ENV: &str = "GRPC_COMPRESSION_MIN_BYTES";
+pub const MAX_RECV_MESSAGE_BYTES_ENV: &str = "GRPC_MAX_RECV_MESSAGE_BYTES";
+pub const MAX_SEND_MESSAGE_BYTES_ENV: &str = "GRPC_MAX_SEND_MESSAGE_BYTES";
+
+const GRPC_ENCODING: HeaderName = HeaderName::from_static("grpc-encoding");
+const GRPC_ACCEPT_ENCODING: HeaderName = HeaderName::from_static("grpc-accept-encoding");
+
+// Flag of a compressed message in the gRPC length-prefixed framing.
+const COMPRESSED: u8 = 0x01;
+
+// Compression and message size limits for `BasicServiceServer`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PayloadConfig {
+    // Encodings offered to callers, most preferred first. Requests may use
+    // any of them; responses use the first one the caller accepts.
+    pub compression: Vec<CompressionEncoding>,
+    // Messages smaller than this go out uncompressed.
+    pub compression_min_bytes: usize,
+    pub max_recv_message_bytes: usize,
+    pub max_send_message_bytes: usize,
+}
+
+impl Default for PayloadConfig {
+    fn default() -> Self {
+        Self {
+            compression: vec![CompressionEncoding::Zstd, CompressionEncoding::Gzip],
+            compression_min_bytes: 1024,
+            max_recv_message_bytes: 4 * 1024 * 1024,
+            max_send_message_bytes: 4 * 1024 * 1024,
+        }
+    }
+}
+
+impl PayloadConfig {
+    // GRPC_COMPRESSION takes a comma-separated list of `zstd` and `gzip`, or
+    // `none`.
+    pub fn from_env() -> Result<Self, String> {
+        let mut config = Self::default();
+        if let Ok(encodings) = std::env::var(COMPRESSION_ENV) {
+            config.compression = match encodings.trim() {
+                "none" | "" => Vec::new(),
+                encodings => encodings
+                    .split(',')
+                    .map(|encoding| match encoding.trim() {
+                        "zstd" => Ok(CompressionEncoding::Zstd),
+                        "gzip" => Ok(CompressionEncoding::Gzip),
+                        other => Err(format!("invalid value for {}: {}", COMPRESSION_ENV, other)),
+                    })
+                    .collect::<Result<_, _>>()?,
+            };
+        }
+        for (name, field) in [
+            (COMPRESSION_MIN_BYTES_ENV, &mut config.compression_min_bytes),
+            (
+                MAX_RECV_MESSAGE_BYTES_ENV,
+                &mut config.max_recv_message_bytes,
+            ),
+            (
+                MAX_SEND_MESSAGE_BYTES_ENV,
+                &mut config.max_send_message_bytes,
+            ),
+        ] {
+            if let Ok(value) = std::env::var(name) {
+                *field = value
+                    .parse()
+                    .map_err(|_| format!("invalid value for {}: {}", name, value))?;
+            }
+        }
+        Ok(config)
+    }
+
+    // Wraps the service with request decompression, the size limits and
+    // response compression.
+    pub fn apply(
+        &self,
+        server: BasicServiceServer<BasicServiceV1>,
+    ) -> PayloadService<BasicServiceServer<BasicServiceV1>> {
+        let server = self
+            .compression
+            .iter()
+            .fold(server, |server, encoding| {
+                server.accept_compressed(*encoding)
+            })
+            .max_decoding_message_size(self.max_recv_message_bytes)
+            .max_encoding_message_size(self.max_send_message_bytes);
+        PayloadLayer::new(self.clone()).layer(server)
+    }
+
+    // The configured encoding called `name`, e.g. from a Connect
+    // `content-encoding` header.
+    pub(crate) fn accepted(&self, name: &str) -> Option<CompressionEncoding> {
+        self.compression
+            .iter()
+            .copied()
+            .find(|encoding| encoding_name(*encoding) == Some(name))
+    }
+
+    // The first configured encoding listed in `grpc-accept-encoding`.
+    fn negotiate(&self, accept: Option<&HeaderValue>) -> Option<CompressionEncoding> {
+        let accept = accept?.to_str().ok()?;
+        self.compression.iter().copied().find(|encoding| {
+            accept
+                .split(',')
+                .any(|accepted| Some(accepted.trim()) == encoding_name(*encoding))
+        })
+    }
+}
+
+// Rejects oversized request messages before they are buffered and compresses
+// response messages of at least `compression_min_bytes`, message by message.
+#[derive(Debug, Clone)]
+pub struct PayloadLayer {
+    config: PayloadConfig,
+}
+
+impl PayloadLayer {
+    pub fn new(config: PayloadConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl<S> Layer<S> for PayloadLayer {
+    type Service = PayloadService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        PayloadService {
+            inner,
+            config: self.config.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PayloadService<S> {
+    inner: S,
+    config: PayloadConfig,
+}
+
+impl<S: NamedService> NamedService for PayloadService<S> {
+    const NAME: &'static str = S::NAME;
+}
+
+impl<S> Service<http::Request<Body>> for PayloadService<S>
+where
+    S: Service<http::Request<Body>, Response = http::Response<Body>, Error = Infallible> + Send,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<Body>) -> Self::Future {
+        let limit = self.config.max_recv_message_bytes;
+        let encoding = self
+            .config
+            .negotiate(req.headers().get(GRPC_ACCEPT_ENCODING));
+        let min_bytes = self.config.compression_min_bytes;
+
+        let req = req.map(|body| {
+            Body::new(Reframed::new(body, limit, |flags, message: Bytes| {
+                Ok(frame(flags, &message))
+            }))
+        });
+        let response = self.inner.call(req);
+
+        Box::pin(async move {
+            let response = response.await?;
+            let Some(encoding) = encoding else {
+                return Ok(response);
+            };
+            let (mut parts, body) = response.into_parts();
+            if let Some(name) = encoding_name(encoding) {
+                parts
+                    .headers
+                    .insert(GRPC_ENCODING, HeaderValue::from_static(name));
+            }
+            let body = Reframed::new(body, usize::MAX, move |flags, message: Bytes| {
+                if flags & COMPRESSED != 0 || message.len() < min_bytes {
+                    return Ok(frame(flags, &message));
+                }
+                let compressed = compress(encoding, &message)?;
+                Ok(frame(flags | COMPRESSED, &compressed))
+            });
+            Ok(http::Response::from_parts(parts, Body::new(body)))
+        })
+    }
+}
+
+// Only the encodings this crate enables are ever negotiated.
+pub(crate) fn encoding_name(encoding: CompressionEncoding) -> Option<&'static str> {
+    match encoding {
+        CompressionEncoding::Zstd => Some("zstd"),
+        CompressionEncoding::Gzip => Some("gzip"),
+        _ => None,
+    }
+}
+
+fn compress(encoding: CompressionEncoding, message: &[u8]) -> Result<Vec<u8>, Status> {
+    let compressed = match encoding {
+        CompressionEncoding::Zstd => zstd::bulk::compress(message, 0),
+        CompressionEncoding::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(message).and_then(|()| encoder.finish())
+        }
+        _ => return Err(Status::internal("unsupported response encoding")),
+    };
+    compressed.map_err(|e| Status::internal(format!("failed to compress response: {}", e)))
+}
+
+// Inflates a request message, failing as soon as it grows past `max_len`.
+pub(crate) fn decompress(
+    encoding: CompressionEncoding,
+    message: &[u8],
+    max_len: usize,
+) -> Result<Bytes, Status> {
+    let limit = max_len as u64 + 1;
+    let mut inflated = Vec::new();
+    let read = match encoding {
+        CompressionEncoding::Zstd => zstd::stream::read::Decoder::new(message)
+            .and_then(|decoder| decoder.take(limit).read_to_end(&mut inflated)),
+        CompressionEncoding::Gzip => GzDecoder::new(message)
+            .take(limit)
+            .read_to_end(&mut inflated),
+        _ => return Err(Status::internal("unsupported request encoding")),
+    };
+    read.map_err(|e| Status::invalid_argument(format!("failed to decompress request: {}", e)))?;
+    if inflated.len() > max_len {
+        return Err(ServiceError::MessageTooLarge {
+            size: None,
+            limit: max_len,
+        }
+        .into());
+    }
+    Ok(Bytes::from(inflated))
+}
+
+// A gRPC body passed through message by message. Messages longer than
+// `max_len` fail the body as soon as their length prefix arrives.
+struct Reframed<F> {
+    inner: Body,
+    buffer: BytesMut,
+    max_len: usize,
+    map: F,
+}
+
+impl<F> Reframed<F> {
+    fn new(inner: Body, max_len: usize, map: F) -> Self {
+        Self {
+            inner,
+            buffer: BytesMut::new(),
+            max_len,
+            map,
+        }
+    }
+}
+
+impl<F> http_body::Body for Reframed<F>
+where
+    F: FnMut(u8, Bytes) -> Result<Bytes, Status> + Unpin,
+{
+    type Data = Bytes;
+    type Error = Status;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, Status>>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(len) = frame_len(&this.buffer)
+                && len > this.max_len
+            {
+                let error = ServiceError::MessageTooLarge {
+                    size: Some(len),
+                    limit: this.max_len,
+                };
+                return Poll::Ready(Some(Err(error.into())));
+            }
+            if let Some((flags, message)) = split_frame(&mut this.buffer) {
+                return Poll::Ready(Some((this.map)(flags, message).map(Frame::data)));
+            }
+            match ready!(Pin::new(&mut this.inner).poll_frame(cx)) {
+                Some(Ok(frame)) => match frame.into_data() {
+                    Ok(data) => this.buffer.extend_from_slice(&data),
+                    Err(frame) => return Poll::Ready(Some(Ok(frame))),
+                },
+                other => return Poll::Ready(other),
+            }
+        }
+    }
+}
+
+// Splits the next complete `flags | length | message` frame off the buffer.
+// gRPC, gRPC-Web and Connect streams all share this layout.
+pub(crate) fn split_frame(buffer: &mut BytesMut) -> Option<(u8, Bytes)> {
+    let len = frame_len(buffer)?;
+    if buffer.len() < 5 + len {
+        return None;
+    }
+    let flags = buffer.get_u8();
+    buffer.advance(4);
+    Some((flags, buffer.split_to(len).freeze()))
+}
+
+fn frame_len(buffer: &[u8]) -> Option<usize> {
+    let prefix = buffer.get(1..5)?;
+    Some(u32::from_be_bytes(prefix.try_into().unwrap()) as usize)
+}
+
+pub(crate) fn frame(flags: u8, message: &[u8]) -> Bytes {
+    let mut frame = BytesMut::with_capacity(5 + message.len());
+    frame.put_u8(flags);
+    frame.put_u32(message.len() as u32);
+    frame.put_slice(message);
+    frame.freeze()
+}
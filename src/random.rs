@@ -0,0 +1,54 @@
+use std::sync::{Arc, Mutex};
+
+use rand::{
+    Rng, RngCore, SeedableRng,
+    distr::uniform::{SampleRange, SampleUniform},
+    prelude::IndexedRandom,
+};
+use rand_chacha::ChaCha12Rng;
+
+// Shared, injectable source of randomness. Seed it to make worker delays,
+// protocols and Talk seeds reproducible; ChaCha12 keeps a seed's sequence
+// stable across rand releases.
+#[derive(Debug, Clone)]
+pub struct RandomSource {
+    rng: Arc<Mutex<ChaCha12Rng>>,
+}
+
+impl Default for RandomSource {
+    fn default() -> Self {
+        Self::from_os()
+    }
+}
+
+impl RandomSource {
+    pub fn from_os() -> Self {
+        Self::from_rng(ChaCha12Rng::from_os_rng())
+    }
+
+    pub fn seeded(seed: u64) -> Self {
+        Self::from_rng(ChaCha12Rng::seed_from_u64(seed))
+    }
+
+    fn from_rng(rng: ChaCha12Rng) -> Self {
+        Self {
+            rng: Arc::new(Mutex::new(rng)),
+        }
+    }
+
+    pub fn next_u64(&self) -> u64 {
+        self.rng.lock().unwrap().next_u64()
+    }
+
+    pub fn random_range<T, R>(&self, range: R) -> T
+    where
+        T: SampleUniform,
+        R: SampleRange<T>,
+    {
+        self.rng.lock().unwrap().random_range(range)
+    }
+
+    pub fn choose<'a, T>(&self, items: &'a [T]) -> Option<&'a T> {
+        items.choose(&mut *self.rng.lock().unwrap())
+    }
+}
@@ -0,0 +1,248 @@
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{auth::Principal, error::ServiceError};
+
+pub const IDENTITY_METADATA_KEY: &str = "x-client-id";
+
+// Full buckets are dropped at most this often.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone)]
+pub struct TalkLimits {
+    pub connection_rate: f64,
+    pub connection_burst: f64,
+    pub identity_rate: f64,
+    pub identity_burst: f64,
+    pub max_message_len: usize,
+    pub max_session: Duration,
+    // Peers whose `x-client-id` header is taken as the identity, e.g. a
+    // reverse proxy that sets it. Anyone else could rotate it at will.
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+impl Default for TalkLimits {
+    fn default() -> Self {
+        Self {
+            connection_rate: 5.0,
+            connection_burst: 10.0,
+            identity_rate: 10.0,
+            identity_burst: 20.0,
+            max_message_len: 1024,
+            max_session: Duration::from_secs(30 * 60),
+            trusted_proxies: Vec::new(),
+        }
+    }
+}
+
+impl TalkLimits {
+    // Every limit can be overridden through TALK_* environment variables.
+    pub fn from_env() -> Result<Self, String> {
+        fn var<T: std::str::FromStr>(key: &str, default: T) -> Result<T, String> {
+            match std::env::var(key) {
+                Ok(value) => value
+                    .parse()
+                    .map_err(|_| format!("invalid value for {}: {}", key, value)),
+                Err(_) => Ok(default),
+            }
+        }
+
+        let defaults = Self::default();
+        Ok(Self {
+            connection_rate: var("TALK_CONNECTION_RATE", defaults.connection_rate)?,
+            connection_burst: var("TALK_CONNECTION_BURST", defaults.connection_burst)?,
+            identity_rate: var("TALK_IDENTITY_RATE", defaults.identity_rate)?,
+            identity_burst: var("TALK_IDENTITY_BURST", defaults.identity_burst)?,
+            max_message_len: var("TALK_MAX_MESSAGE_LEN", defaults.max_message_len)?,
+            max_session: Duration::from_secs(var(
+                "TALK_MAX_SESSION_SECS",
+                defaults.max_session.as_secs(),
+            )?),
+            trusted_proxies: match std::env::var("TALK_TRUSTED_PROXIES") {
+                Ok(value) => value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|ip| !ip.is_empty())
+                    .map(|ip| {
+                        ip.parse()
+                            .map_err(|_| format!("invalid value for TALK_TRUSTED_PROXIES: {}", ip))
+                    })
+                    .collect::<Result<_, _>>()?,
+                Err(_) => defaults.trusted_proxies,
+            },
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    capacity: f64,
+    rate: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(capacity: f64, rate: f64) -> Self {
+        Self {
+            capacity,
+            rate,
+            tokens: capacity,
+            updated: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.updated = now;
+    }
+
+    // How long the caller has to wait for a token, if at all. A non-positive
+    // rate disables the bucket.
+    fn wait(&mut self, now: Instant) -> Option<Duration> {
+        if self.rate <= 0.0 {
+            return None;
+        }
+        self.refill(now);
+        (self.tokens < 1.0).then(|| Duration::from_secs_f64((1.0 - self.tokens) / self.rate))
+    }
+
+    fn take(&mut self) {
+        if self.rate > 0.0 {
+            self.tokens -= 1.0;
+        }
+    }
+
+    fn is_full(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        self.tokens >= self.capacity
+    }
+}
+
+#[derive(Debug)]
+struct Buckets {
+    connections: HashMap<String, TokenBucket>,
+    identities: HashMap<String, TokenBucket>,
+    pruned: Instant,
+}
+
+impl Default for Buckets {
+    fn default() -> Self {
+        Self {
+            connections: HashMap::new(),
+            identities: HashMap::new(),
+            pruned: Instant::now(),
+        }
+    }
+}
+
+impl Buckets {
+    // Full buckets behave like new ones, so they can go.
+    fn prune(&mut self, now: Instant) {
+        if now.saturating_duration_since(self.pruned) < PRUNE_INTERVAL {
+            return;
+        }
+        self.connections.retain(|_, bucket| !bucket.is_full(now));
+        self.identities.retain(|_, bucket| !bucket.is_full(now));
+        self.pruned = now;
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+    limits: TalkLimits,
+    buckets: Arc<Mutex<Buckets>>,
+}
+
+impl RateLimiter {
+    pub fn new(limits: TalkLimits) -> Self {
+        Self {
+            limits,
+            ..Default::default()
+        }
+    }
+
+    pub fn limits(&self) -> &TalkLimits {
+        &self.limits
+    }
+
+    // Identifies the caller of a request: the peer address is the connection,
+    // the authenticated principal, the x-client-id header of a trusted proxy
+    // or the peer IP is the identity.
+    pub fn keys<T>(&self, request: &tonic::Request<T>) -> (String, String) {
+        let peer = request.remote_addr();
+        let connection = peer
+            .map(|addr| addr.to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        let trusted = peer.is_some_and(|addr| self.limits.trusted_proxies.contains(&addr.ip()));
+        let identity = request
+            .extensions()
+            .get::<Principal>()
+            .map(|principal| format!("principal:{}", principal.subject))
+            .or_else(|| {
+                request
+                    .metadata()
+                    .get(IDENTITY_METADATA_KEY)
+                    .filter(|_| trusted)
+                    .and_then(|value| value.to_str().ok())
+                    .map(|client| format!("client:{}", client))
+            })
+            .or_else(|| peer.map(|addr| format!("peer:{}", addr.ip())))
+            .unwrap_or_else(|| "anonymous".to_string());
+        (connection, identity)
+    }
+
+    pub fn check_message(
+        &self,
+        connection: &str,
+        identity: &str,
+        message: &str,
+    ) -> Result<(), ServiceError> {
+        if message.len() > self.limits.max_message_len {
+            return Err(ServiceError::invalid_field(
+                "message",
+                format!("must be at most {} bytes", self.limits.max_message_len),
+            ));
+        }
+
+        // Both buckets are checked before either is charged, so a message
+        // rejected by one limit costs nothing from the other.
+        let now = Instant::now();
+        let limits = &self.limits;
+        let mut buckets = self.buckets.lock().unwrap();
+        buckets.prune(now);
+        let Buckets {
+            connections,
+            identities,
+            ..
+        } = &mut *buckets;
+        let connection = connections
+            .entry(connection.to_string())
+            .or_insert_with(|| TokenBucket::new(limits.connection_burst, limits.connection_rate));
+        let identity = identities
+            .entry(identity.to_string())
+            .or_insert_with(|| TokenBucket::new(limits.identity_burst, limits.identity_rate));
+
+        match (connection.wait(now), identity.wait(now)) {
+            (None, None) => {
+                connection.take();
+                identity.take();
+                Ok(())
+            }
+            (a, b) => Err(ServiceError::RateLimited {
+                retry_after: a.max(b).unwrap_or_default(),
+            }),
+        }
+    }
+
+    pub fn session_expired(&self) -> ServiceError {
+        ServiceError::SessionExpired {
+            max_session: self.limits.max_session,
+        }
+    }
+}
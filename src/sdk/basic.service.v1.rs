@@ -6,6 +6,16 @@ pub struct SomeServiceData {
     #[prost(string, tag = "2")]
     pub r#type: ::prost::alloc::string::String,
 }
+impl ::prost::Name for SomeServiceData {
+    const NAME: &'static str = "SomeServiceData";
+    const PACKAGE: &'static str = "basic.service.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "basic.service.v1.SomeServiceData".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "type.googleapis.com/basic.service.v1.SomeServiceData".into()
+    }
+}
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
 pub struct SomeServiceResponse {
     #[prost(string, tag = "1")]
@@ -17,15 +27,73 @@ pub struct SomeServiceResponse {
     #[prost(message, optional, tag = "4")]
     pub data: ::core::option::Option<SomeServiceData>,
 }
+impl ::prost::Name for SomeServiceResponse {
+    const NAME: &'static str = "SomeServiceResponse";
+    const PACKAGE: &'static str = "basic.service.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "basic.service.v1.SomeServiceResponse".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "type.googleapis.com/basic.service.v1.SomeServiceResponse".into()
+    }
+}
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SomeServiceResponses {
     #[prost(message, repeated, tag = "1")]
     pub responses: ::prost::alloc::vec::Vec<SomeServiceResponse>,
 }
+impl ::prost::Name for SomeServiceResponses {
+    const NAME: &'static str = "SomeServiceResponses";
+    const PACKAGE: &'static str = "basic.service.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "basic.service.v1.SomeServiceResponses".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "type.googleapis.com/basic.service.v1.SomeServiceResponses".into()
+    }
+}
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
 pub struct HelloRequest {
+    /// Who to greet when `name` is unset. Must not be blank unless `name` is set.
     #[prost(string, tag = "1")]
     pub message: ::prost::alloc::string::String,
+    /// Who to greet. Must not be blank when set.
+    #[prost(string, optional, tag = "2")]
+    pub name: ::core::option::Option<::prost::alloc::string::String>,
+    /// Preferred greeting language as a BCP 47 tag, e.g. `de-CH`. Takes
+    /// precedence over the `grpc-accept-language` and `accept-language`
+    /// metadata.
+    #[prost(string, optional, tag = "3")]
+    pub locale: ::core::option::Option<::prost::alloc::string::String>,
+}
+impl ::prost::Name for HelloRequest {
+    const NAME: &'static str = "HelloRequest";
+    const PACKAGE: &'static str = "basic.service.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "basic.service.v1.HelloRequest".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "type.googleapis.com/basic.service.v1.HelloRequest".into()
+    }
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct HelloBatchRequest {
+    /// Who to greet. Between 1 and 1000 entries, none of them blank.
+    #[prost(string, repeated, tag = "1")]
+    pub names: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Preferred greeting language for every name, as in `HelloRequest.locale`.
+    #[prost(string, optional, tag = "2")]
+    pub locale: ::core::option::Option<::prost::alloc::string::String>,
+}
+impl ::prost::Name for HelloBatchRequest {
+    const NAME: &'static str = "HelloBatchRequest";
+    const PACKAGE: &'static str = "basic.service.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "basic.service.v1.HelloBatchRequest".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "type.googleapis.com/basic.service.v1.HelloBatchRequest".into()
+    }
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct HelloResponse {
@@ -34,26 +102,118 @@ pub struct HelloResponse {
         super::super::super::io::cloudevents::v1::CloudEvent,
     >,
 }
+impl ::prost::Name for HelloResponse {
+    const NAME: &'static str = "HelloResponse";
+    const PACKAGE: &'static str = "basic.service.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "basic.service.v1.HelloResponse".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "type.googleapis.com/basic.service.v1.HelloResponse".into()
+    }
+}
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
 pub struct HelloResponseEvent {
     #[prost(string, tag = "1")]
     pub greeting: ::prost::alloc::string::String,
 }
+impl ::prost::Name for HelloResponseEvent {
+    const NAME: &'static str = "HelloResponseEvent";
+    const PACKAGE: &'static str = "basic.service.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "basic.service.v1.HelloResponseEvent".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "type.googleapis.com/basic.service.v1.HelloResponseEvent".into()
+    }
+}
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
 pub struct TalkRequest {
+    /// Must not be blank.
     #[prost(string, tag = "1")]
     pub message: ::prost::alloc::string::String,
+    /// Wraps the answer to this message as a CloudEvent in
+    /// `TalkResponse.cloud_event`.
+    #[prost(bool, tag = "2")]
+    pub include_cloud_event: bool,
 }
-#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+impl ::prost::Name for TalkRequest {
+    const NAME: &'static str = "TalkRequest";
+    const PACKAGE: &'static str = "basic.service.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "basic.service.v1.TalkRequest".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "type.googleapis.com/basic.service.v1.TalkRequest".into()
+    }
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct TalkResponse {
     #[prost(string, tag = "1")]
     pub answer: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "2")]
+    pub sequence: u64,
+    #[prost(string, tag = "3")]
+    pub rule: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub fragment: ::prost::alloc::string::String,
+    #[prost(bool, tag = "5")]
+    pub ended: bool,
+    /// Only set when the request asked for it with `include_cloud_event`.
+    #[prost(message, optional, tag = "6")]
+    pub cloud_event: ::core::option::Option<
+        super::super::super::io::cloudevents::v1::CloudEvent,
+    >,
+}
+impl ::prost::Name for TalkResponse {
+    const NAME: &'static str = "TalkResponse";
+    const PACKAGE: &'static str = "basic.service.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "basic.service.v1.TalkResponse".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "type.googleapis.com/basic.service.v1.TalkResponse".into()
+    }
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct TalkResponseEvent {
+    #[prost(string, tag = "1")]
+    pub answer: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "2")]
+    pub sequence: u64,
+    #[prost(string, tag = "3")]
+    pub rule: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub fragment: ::prost::alloc::string::String,
+    #[prost(bool, tag = "5")]
+    pub ended: bool,
+}
+impl ::prost::Name for TalkResponseEvent {
+    const NAME: &'static str = "TalkResponseEvent";
+    const PACKAGE: &'static str = "basic.service.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "basic.service.v1.TalkResponseEvent".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "type.googleapis.com/basic.service.v1.TalkResponseEvent".into()
+    }
 }
 #[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
 pub struct BackgroundRequest {
+    /// Between 0 and 100.
     #[prost(int64, tag = "1")]
     pub processes: i64,
 }
+impl ::prost::Name for BackgroundRequest {
+    const NAME: &'static str = "BackgroundRequest";
+    const PACKAGE: &'static str = "basic.service.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "basic.service.v1.BackgroundRequest".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "type.googleapis.com/basic.service.v1.BackgroundRequest".into()
+    }
+}
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct BackgroundResponse {
     #[prost(message, optional, tag = "1")]
@@ -61,6 +221,16 @@ pub struct BackgroundResponse {
         super::super::super::io::cloudevents::v1::CloudEvent,
     >,
 }
+impl ::prost::Name for BackgroundResponse {
+    const NAME: &'static str = "BackgroundResponse";
+    const PACKAGE: &'static str = "basic.service.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "basic.service.v1.BackgroundResponse".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "type.googleapis.com/basic.service.v1.BackgroundResponse".into()
+    }
+}
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct BackgroundResponseEvent {
     #[prost(enumeration = "State", tag = "1")]
@@ -72,6 +242,77 @@ pub struct BackgroundResponseEvent {
     #[prost(message, repeated, tag = "4")]
     pub responses: ::prost::alloc::vec::Vec<SomeServiceResponse>,
 }
+impl ::prost::Name for BackgroundResponseEvent {
+    const NAME: &'static str = "BackgroundResponseEvent";
+    const PACKAGE: &'static str = "basic.service.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "basic.service.v1.BackgroundResponseEvent".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "type.googleapis.com/basic.service.v1.BackgroundResponseEvent".into()
+    }
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SubscribeRequest {
+    /// Event types to deliver; empty delivers every type. Entries must not be
+    /// empty.
+    #[prost(string, repeated, tag = "1")]
+    pub types: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Event sources to deliver; empty delivers every source. Entries must not
+    /// be empty.
+    #[prost(string, repeated, tag = "2")]
+    pub sources: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Attributes that must be present with exactly these values. Keys must not
+    /// be empty.
+    #[prost(map = "string, string", tag = "3")]
+    pub attributes: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+impl ::prost::Name for SubscribeRequest {
+    const NAME: &'static str = "SubscribeRequest";
+    const PACKAGE: &'static str = "basic.service.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "basic.service.v1.SubscribeRequest".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "type.googleapis.com/basic.service.v1.SubscribeRequest".into()
+    }
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SubscribeResponse {
+    #[prost(message, optional, tag = "1")]
+    pub cloud_event: ::core::option::Option<
+        super::super::super::io::cloudevents::v1::CloudEvent,
+    >,
+}
+impl ::prost::Name for SubscribeResponse {
+    const NAME: &'static str = "SubscribeResponse";
+    const PACKAGE: &'static str = "basic.service.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "basic.service.v1.SubscribeResponse".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "type.googleapis.com/basic.service.v1.SubscribeResponse".into()
+    }
+}
+/// Sent in place of the events a slow subscriber missed.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct SubscribeLagEvent {
+    #[prost(uint64, tag = "1")]
+    pub missed: u64,
+}
+impl ::prost::Name for SubscribeLagEvent {
+    const NAME: &'static str = "SubscribeLagEvent";
+    const PACKAGE: &'static str = "basic.service.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "basic.service.v1.SubscribeLagEvent".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "type.googleapis.com/basic.service.v1.SubscribeLagEvent".into()
+    }
+}
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
 pub enum State {
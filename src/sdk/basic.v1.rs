@@ -105,7 +105,7 @@ pub mod basic_service_client {
                         format!("Service was not ready: {}", e.into()),
                     )
                 })?;
-            let codec = tonic_prost::ProstCodec::default();
+            let codec = crate::validation::ValidatingCodec::default();
             let path = http::uri::PathAndQuery::from_static(
                 "/basic.v1.BasicService/Hello",
             );
@@ -133,7 +133,7 @@ pub mod basic_service_client {
                         format!("Service was not ready: {}", e.into()),
                     )
                 })?;
-            let codec = tonic_prost::ProstCodec::default();
+            let codec = crate::validation::ValidatingCodec::default();
             let path = http::uri::PathAndQuery::from_static(
                 "/basic.v1.BasicService/Talk",
             );
@@ -161,7 +161,7 @@ pub mod basic_service_client {
                         format!("Service was not ready: {}", e.into()),
                     )
                 })?;
-            let codec = tonic_prost::ProstCodec::default();
+            let codec = crate::validation::ValidatingCodec::default();
             let path = http::uri::PathAndQuery::from_static(
                 "/basic.v1.BasicService/Background",
             );
@@ -170,6 +170,91 @@ pub mod basic_service_client {
                 .insert(GrpcMethod::new("basic.v1.BasicService", "Background"));
             self.inner.server_streaming(req, path, codec).await
         }
+        pub async fn subscribe(
+            &mut self,
+            request: impl tonic::IntoRequest<super::super::service::v1::SubscribeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<
+                tonic::codec::Streaming<super::super::service::v1::SubscribeResponse>,
+            >,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = crate::validation::ValidatingCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/basic.v1.BasicService/Subscribe",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("basic.v1.BasicService", "Subscribe"));
+            self.inner.server_streaming(req, path, codec).await
+        }
+        /// One Hello event per name, in request order.
+        pub async fn hello_batch(
+            &mut self,
+            request: impl tonic::IntoRequest<
+                super::super::service::v1::HelloBatchRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::super::super::io::cloudevents::v1::CloudEventBatch>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = crate::validation::ValidatingCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/basic.v1.BasicService/HelloBatch",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("basic.v1.BasicService", "HelloBatch"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// HelloBatch for inputs too large for one message: every request is
+        /// answered with the batch for its names.
+        pub async fn hello_batch_stream(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<
+                Message = super::super::service::v1::HelloBatchRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<
+                tonic::codec::Streaming<
+                    super::super::super::io::cloudevents::v1::CloudEventBatch,
+                >,
+            >,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = crate::validation::ValidatingCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/basic.v1.BasicService/HelloBatchStream",
+            );
+            let mut req = request.into_streaming_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("basic.v1.BasicService", "HelloBatchStream"));
+            self.inner.streaming(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -220,6 +305,47 @@ pub mod basic_service_server {
             &self,
             request: tonic::Request<super::super::service::v1::BackgroundRequest>,
         ) -> std::result::Result<tonic::Response<Self::BackgroundStream>, tonic::Status>;
+        /// Server streaming response type for the Subscribe method.
+        type SubscribeStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<
+                    super::super::service::v1::SubscribeResponse,
+                    tonic::Status,
+                >,
+            >
+            + std::marker::Send
+            + 'static;
+        async fn subscribe(
+            &self,
+            request: tonic::Request<super::super::service::v1::SubscribeRequest>,
+        ) -> std::result::Result<tonic::Response<Self::SubscribeStream>, tonic::Status>;
+        /// One Hello event per name, in request order.
+        async fn hello_batch(
+            &self,
+            request: tonic::Request<super::super::service::v1::HelloBatchRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::super::io::cloudevents::v1::CloudEventBatch>,
+            tonic::Status,
+        >;
+        /// Server streaming response type for the HelloBatchStream method.
+        type HelloBatchStreamStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<
+                    super::super::super::io::cloudevents::v1::CloudEventBatch,
+                    tonic::Status,
+                >,
+            >
+            + std::marker::Send
+            + 'static;
+        /// HelloBatch for inputs too large for one message: every request is
+        /// answered with the batch for its names.
+        async fn hello_batch_stream(
+            &self,
+            request: tonic::Request<
+                tonic::Streaming<super::super::service::v1::HelloBatchRequest>,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<Self::HelloBatchStreamStream>,
+            tonic::Status,
+        >;
     }
     #[derive(Debug)]
     pub struct BasicServiceServer<T> {
@@ -330,7 +456,7 @@ pub mod basic_service_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let method = HelloSvc(inner);
-                        let codec = tonic_prost::ProstCodec::default();
+                        let codec = crate::validation::ValidatingCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
                                 accept_compression_encodings,
@@ -379,7 +505,7 @@ pub mod basic_service_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let method = TalkSvc(inner);
-                        let codec = tonic_prost::ProstCodec::default();
+                        let codec = crate::validation::ValidatingCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
                                 accept_compression_encodings,
@@ -428,7 +554,7 @@ pub mod basic_service_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let method = BackgroundSvc(inner);
-                        let codec = tonic_prost::ProstCodec::default();
+                        let codec = crate::validation::ValidatingCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
                                 accept_compression_encodings,
@@ -443,6 +569,155 @@ pub mod basic_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/basic.v1.BasicService/Subscribe" => {
+                    #[allow(non_camel_case_types)]
+                    struct SubscribeSvc<T: BasicService>(pub Arc<T>);
+                    impl<
+                        T: BasicService,
+                    > tonic::server::ServerStreamingService<
+                        super::super::service::v1::SubscribeRequest,
+                    > for SubscribeSvc<T> {
+                        type Response = super::super::service::v1::SubscribeResponse;
+                        type ResponseStream = T::SubscribeStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::service::v1::SubscribeRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as BasicService>::subscribe(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = SubscribeSvc(inner);
+                        let codec = crate::validation::ValidatingCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/basic.v1.BasicService/HelloBatch" => {
+                    #[allow(non_camel_case_types)]
+                    struct HelloBatchSvc<T: BasicService>(pub Arc<T>);
+                    impl<
+                        T: BasicService,
+                    > tonic::server::UnaryService<
+                        super::super::service::v1::HelloBatchRequest,
+                    > for HelloBatchSvc<T> {
+                        type Response = super::super::super::io::cloudevents::v1::CloudEventBatch;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::service::v1::HelloBatchRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as BasicService>::hello_batch(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = HelloBatchSvc(inner);
+                        let codec = crate::validation::ValidatingCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/basic.v1.BasicService/HelloBatchStream" => {
+                    #[allow(non_camel_case_types)]
+                    struct HelloBatchStreamSvc<T: BasicService>(pub Arc<T>);
+                    impl<
+                        T: BasicService,
+                    > tonic::server::StreamingService<
+                        super::super::service::v1::HelloBatchRequest,
+                    > for HelloBatchStreamSvc<T> {
+                        type Response = super::super::super::io::cloudevents::v1::CloudEventBatch;
+                        type ResponseStream = T::HelloBatchStreamStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                tonic::Streaming<
+                                    super::super::service::v1::HelloBatchRequest,
+                                >,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as BasicService>::hello_batch_stream(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = HelloBatchStreamSvc(inner);
+                        let codec = crate::validation::ValidatingCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         let mut response = http::Response::new(
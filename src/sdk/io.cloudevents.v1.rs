@@ -51,6 +51,17 @@ pub mod cloud_event {
             CeTimestamp(::prost_types::Timestamp),
         }
     }
+    impl ::prost::Name for CloudEventAttributeValue {
+        const NAME: &'static str = "CloudEventAttributeValue";
+        const PACKAGE: &'static str = "io.cloudevents.v1";
+        fn full_name() -> ::prost::alloc::string::String {
+            "io.cloudevents.v1.CloudEvent.CloudEventAttributeValue".into()
+        }
+        fn type_url() -> ::prost::alloc::string::String {
+            "type.googleapis.com/io.cloudevents.v1.CloudEvent.CloudEventAttributeValue"
+                .into()
+        }
+    }
     /// -- CloudEvent Data (Bytes, Text, or Proto)
     #[derive(Clone, PartialEq, Eq, Hash, ::prost::Oneof)]
     pub enum Data {
@@ -62,8 +73,28 @@ pub mod cloud_event {
         ProtoData(::prost_types::Any),
     }
 }
+impl ::prost::Name for CloudEvent {
+    const NAME: &'static str = "CloudEvent";
+    const PACKAGE: &'static str = "io.cloudevents.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "io.cloudevents.v1.CloudEvent".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "type.googleapis.com/io.cloudevents.v1.CloudEvent".into()
+    }
+}
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CloudEventBatch {
     #[prost(message, repeated, tag = "1")]
     pub events: ::prost::alloc::vec::Vec<CloudEvent>,
 }
+impl ::prost::Name for CloudEventBatch {
+    const NAME: &'static str = "CloudEventBatch";
+    const PACKAGE: &'static str = "io.cloudevents.v1";
+    fn full_name() -> ::prost::alloc::string::String {
+        "io.cloudevents.v1.CloudEventBatch".into()
+    }
+    fn type_url() -> ::prost::alloc::string::String {
+        "type.googleapis.com/io.cloudevents.v1.CloudEventBatch".into()
+    }
+}
@@ -0,0 +1,161 @@
+use std::{convert::Infallible, path::Path};
+
+use rustls::crypto::{CryptoProvider, ring};
+use tonic::{
+    body::Body,
+    server::NamedService,
+    service::RoutesBuilder,
+    transport::{Identity, Server, ServerTlsConfig, server::Router},
+};
+use tonic_reflection::server::Builder as ReflectionBuilder;
+use tower::{
+    Service,
+    layer::util::{Identity as NoLayer, Stack},
+    util::{Either, option_layer},
+};
+
+use crate::{
+    FILE_DESCRIPTOR_SET,
+    auth::{AuthLayer, Authenticator},
+    connect::ConnectLayer,
+    info,
+    payload::PayloadConfig,
+    sdk::basic::v1::basic_service_server::BasicServiceServer,
+    service::BasicServiceV1,
+    web::{CorsConfig, WebLayer, web_layer},
+};
+
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+pub type BasicRouter = Router<
+    Stack<
+        AuthLayer,
+        Stack<Either<ConnectLayer, NoLayer>, Stack<Either<WebLayer, NoLayer>, NoLayer>>,
+    >,
+>;
+
+// Assembles a tonic server around `BasicServiceV1`: TLS, reflection,
+// authentication, gRPC-Web, Connect and any extra services the embedding
+// binary wants to mount.
+pub struct ServerBuilder {
+    service: BasicServiceV1,
+    tls: Option<Identity>,
+    reflection: bool,
+    authenticator: Option<Authenticator>,
+    grpc_web: Option<CorsConfig>,
+    connect: bool,
+    payload: PayloadConfig,
+    routes: RoutesBuilder,
+}
+
+impl ServerBuilder {
+    pub fn new(service: BasicServiceV1) -> Self {
+        Self {
+            service,
+            tls: None,
+            reflection: true,
+            authenticator: None,
+            grpc_web: None,
+            connect: false,
+            payload: PayloadConfig::default(),
+            routes: RoutesBuilder::default(),
+        }
+    }
+
+    pub fn tls(mut self, identity: Identity) -> Self {
+        self.tls = Some(identity);
+        self
+    }
+
+    pub async fn tls_from_pem_files(
+        self,
+        cert: impl AsRef<Path>,
+        key: impl AsRef<Path>,
+    ) -> std::io::Result<Self> {
+        let cert = tokio::fs::read(cert).await?;
+        let key = tokio::fs::read(key).await?;
+        Ok(self.tls(Identity::from_pem(cert, key)))
+    }
+
+    pub fn reflection(mut self, enabled: bool) -> Self {
+        self.reflection = enabled;
+        self
+    }
+
+    pub fn authenticator(mut self, authenticator: Option<Authenticator>) -> Self {
+        self.authenticator = authenticator;
+        self
+    }
+
+    // Serves browser clients over gRPC-Web, including HTTP/1.1, with the
+    // given CORS policy. Off by default.
+    pub fn grpc_web(mut self, cors: Option<CorsConfig>) -> Self {
+        self.grpc_web = cors;
+        self
+    }
+
+    // Serves the Connect protocol, unary and streaming with the proto and
+    // JSON codecs, on the same port. Off by default.
+    pub fn connect(mut self, enabled: bool) -> Self {
+        self.connect = enabled;
+        self
+    }
+
+    // Compression and message size limits of BasicService.
+    pub fn payload(mut self, payload: PayloadConfig) -> Self {
+        self.payload = payload;
+        self
+    }
+
+    pub fn add_service<S>(mut self, svc: S) -> Self
+    where
+        S: Service<http::Request<Body>, Error = Infallible>
+            + NamedService
+            + Clone
+            + Send
+            + Sync
+            + 'static,
+        S::Response: axum::response::IntoResponse,
+        S::Future: Send + 'static,
+    {
+        self.routes.add_service(svc);
+        self
+    }
+
+    pub fn build(mut self) -> Result<BasicRouter, BoxError> {
+        let mut server = Server::builder().accept_http1(self.grpc_web.is_some() || self.connect);
+        if let Some(identity) = self.tls {
+            // rustls needs a process-wide provider; keep one installed by the
+            // embedding application.
+            if CryptoProvider::install_default(ring::default_provider()).is_err() {
+                info!("Using the already installed rustls crypto provider");
+            }
+            server = server.tls_config(ServerTlsConfig::new().identity(identity))?;
+        }
+
+        self.routes
+            .add_service(self.payload.apply(BasicServiceServer::new(self.service)));
+        if self.reflection {
+            self.routes.add_service(
+                ReflectionBuilder::configure()
+                    .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+                    .build_v1()?,
+            );
+            self.routes.add_service(
+                ReflectionBuilder::configure()
+                    .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+                    .build_v1alpha()?,
+            );
+        }
+
+        let web = self.grpc_web.as_ref().map(web_layer).transpose()?;
+        let connect = self
+            .connect
+            .then(|| ConnectLayer::new().map(|layer| layer.with_payload(self.payload.clone())))
+            .transpose()?;
+        Ok(server
+            .layer(option_layer(web))
+            .layer(option_layer(connect))
+            .layer(AuthLayer::new(self.authenticator))
+            .add_routes(self.routes.routes()))
+    }
+}
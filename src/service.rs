@@ -0,0 +1,579 @@
+use std::{collections::HashMap, pin::Pin, sync::Arc};
+
+use futures_core::Stream;
+use tokio::{sync::mpsc, task::JoinSet, time::timeout};
+use tokio_stream::{StreamExt, wrappers::ReceiverStream};
+use tonic::metadata::MetadataMap;
+use uuid::Uuid;
+
+use crate::{
+    auth,
+    clock::{Clock, SharedClock, SystemClock},
+    deadline::Deadlines,
+    error::ServiceError,
+    events::{EventBus, EventFilter, SubscribeStream},
+    greeting::Greetings,
+    idempotency::IdempotencyKey,
+    info,
+    random::RandomSource,
+    rate_limit::RateLimiter,
+    sdk::{
+        basic::{
+            service::v1::{
+                BackgroundRequest, BackgroundResponse, BackgroundResponseEvent, HelloBatchRequest,
+                HelloRequest, HelloResponse, SomeServiceResponse, State, SubscribeRequest,
+                TalkRequest, TalkResponse,
+            },
+            v1::basic_service_server::BasicService,
+        },
+        io::cloudevents::v1::{CloudEventBatch, cloud_event::CloudEventAttributeValue},
+    },
+    state::JobWatch,
+    talk::{ConversationBackend, Eliza, Transcript, TranscriptRecorder},
+    utils::{self, StateManager},
+    warning,
+    workers::WorkerRegistry,
+};
+
+#[derive(Debug, Clone)]
+pub struct BasicServiceV1 {
+    clock: SharedClock,
+    rng: RandomSource,
+    state: StateManager,
+    workers: WorkerRegistry,
+    conversations: Arc<dyn ConversationBackend>,
+    transcripts: Option<TranscriptRecorder>,
+    limiter: RateLimiter,
+    events: EventBus,
+    deadlines: Deadlines,
+    greetings: Greetings,
+    // Set once the caller replaced the simulated workers or the state
+    // manager; `with_clock` and `with_rng` leave those alone.
+    custom_workers: bool,
+    custom_state: bool,
+}
+
+impl Default for BasicServiceV1 {
+    fn default() -> Self {
+        let rng = RandomSource::default();
+        let clock: SharedClock = Arc::new(SystemClock);
+        Self {
+            workers: WorkerRegistry::simulated(rng.clone(), clock.clone()),
+            state: StateManager::new().with_clock(clock.clone()),
+            clock,
+            rng,
+            conversations: Arc::new(Eliza),
+            transcripts: None,
+            limiter: RateLimiter::default(),
+            events: EventBus::default(),
+            deadlines: Deadlines::default(),
+            greetings: Greetings::default(),
+            custom_workers: false,
+            custom_state: false,
+        }
+    }
+}
+
+// Every dependency defaults to the production implementation and can be
+// swapped out with the `with_*` methods.
+impl BasicServiceV1 {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Also drives the event bus, and the state manager and simulated workers
+    // unless they were replaced with `with_state_manager` or `with_workers`.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        if !self.custom_workers {
+            self.workers = WorkerRegistry::simulated(self.rng.clone(), clock.clone());
+        }
+        if !self.custom_state {
+            self.state = self.state.with_clock(clock.clone());
+        }
+        self.events = self.events.with_clock(clock.clone());
+        self.clock = clock;
+        self
+    }
+
+    // Also reseeds the simulated workers unless they were replaced with
+    // `with_workers`.
+    pub fn with_rng(mut self, rng: RandomSource) -> Self {
+        if !self.custom_workers {
+            self.workers = WorkerRegistry::simulated(rng.clone(), self.clock.clone());
+        }
+        self.rng = rng;
+        self
+    }
+
+    // The manager keeps its own clock; set it with `StateManager::with_clock`.
+    pub fn with_state_manager(mut self, state: StateManager) -> Self {
+        self.state = state;
+        self.custom_state = true;
+        self
+    }
+
+    pub fn with_workers(mut self, workers: WorkerRegistry) -> Self {
+        self.workers = workers;
+        self.custom_workers = true;
+        self
+    }
+
+    pub fn with_conversations(mut self, conversations: Arc<dyn ConversationBackend>) -> Self {
+        self.conversations = conversations;
+        self
+    }
+
+    pub fn with_transcripts(mut self, transcripts: Option<TranscriptRecorder>) -> Self {
+        self.transcripts = transcripts;
+        self
+    }
+
+    pub fn with_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.limiter = limiter;
+        self
+    }
+
+    // The bus is switched over to the service clock.
+    pub fn with_event_bus(mut self, events: EventBus) -> Self {
+        self.events = events.with_clock(self.clock.clone());
+        self
+    }
+
+    pub fn with_deadlines(mut self, deadlines: Deadlines) -> Self {
+        self.deadlines = deadlines;
+        self
+    }
+
+    pub fn with_greetings(mut self, greetings: Greetings) -> Self {
+        self.greetings = greetings;
+        self
+    }
+
+    pub fn state_manager(&self) -> &StateManager {
+        &self.state
+    }
+
+    pub fn event_bus(&self) -> &EventBus {
+        &self.events
+    }
+}
+
+#[tonic::async_trait]
+impl BasicService for BasicServiceV1 {
+    type TalkStream =
+        Pin<Box<dyn Stream<Item = Result<TalkResponse, tonic::Status>> + Send + Sync + 'static>>;
+    type BackgroundStream = Pin<
+        Box<dyn Stream<Item = Result<BackgroundResponse, tonic::Status>> + Send + Sync + 'static>,
+    >;
+    type SubscribeStream = SubscribeStream;
+    type HelloBatchStreamStream =
+        Pin<Box<dyn Stream<Item = Result<CloudEventBatch, tonic::Status>> + Send + Sync + 'static>>;
+
+    async fn hello(
+        &self,
+        request: tonic::Request<HelloRequest>,
+    ) -> Result<tonic::Response<HelloResponse>, tonic::Status> {
+        let key = IdempotencyKey::from_request(&request, "Hello")?;
+        if let Some(key) = &key
+            && !key.claim(&self.state)?
+        {
+            let cloud_event = match self.deadlines.resolve("Hello", &request) {
+                Some(deadline) => {
+                    timeout(deadline, key.replay(&self.state))
+                        .await
+                        .map_err(|_| {
+                            ServiceError::DeadlineExceeded(
+                                "deadline exceeded waiting for the original call".to_string(),
+                            )
+                        })??
+                }
+                None => key.replay(&self.state).await?,
+            };
+            return Ok(tonic::Response::new(HelloResponse {
+                cloud_event: Some(cloud_event),
+            }));
+        }
+
+        let attributes = auth::principal_attributes(&request);
+        let cloudevent = utils::create_hello_event(
+            self.greetings.greet(&request),
+            self.clock.timestamp(),
+            &attributes,
+        );
+        if let Some(key) = &key {
+            key.remember(&self.state, &cloudevent);
+        }
+        self.events.publish(&cloudevent);
+
+        let response = HelloResponse {
+            cloud_event: Some(cloudevent),
+        };
+
+        Ok(tonic::Response::new(response))
+    }
+
+    async fn talk(
+        &self,
+        request: tonic::Request<tonic::Streaming<TalkRequest>>,
+    ) -> Result<tonic::Response<Self::TalkStream>, tonic::Status> {
+        let (connection, identity) = self.limiter.keys(&request);
+        let attributes = auth::principal_attributes(&request);
+        let call_deadline = self.deadlines.resolve("Talk", &request);
+        let mut inbound = request.into_inner();
+        let (tx, rx) = mpsc::channel(4);
+        let recorder = self.transcripts.clone();
+        let limiter = self.limiter.clone();
+        // The session ends at the call deadline or after the maximum session
+        // length, whichever comes first. Both are measured on the service
+        // clock.
+        let max_session = limiter.limits().max_session;
+        let call_limited = call_deadline.is_some_and(|deadline| deadline < max_session);
+        let lifetime = call_deadline
+            .filter(|_| call_limited)
+            .unwrap_or(max_session);
+        let seed = self.rng.next_u64();
+        let mut session = self.conversations.start(seed);
+        let session_id = Uuid::new_v4().to_string();
+        let events = self.events.clone();
+        let clock = self.clock.clone();
+        let timer = self.clock.clone();
+
+        tokio::spawn(async move {
+            let mut transcript = recorder
+                .as_ref()
+                .map(|_| Transcript::new(seed).with_session_id(session_id.clone()));
+            let mut sequence = 0;
+            let expiry = timer.sleep(lifetime);
+            tokio::pin!(expiry);
+
+            loop {
+                let req = tokio::select! {
+                    req = inbound.message() => req,
+                    _ = &mut expiry => {
+                        let error = if call_limited {
+                            ServiceError::DeadlineExceeded("Talk deadline exceeded".to_string())
+                        } else {
+                            limiter.session_expired()
+                        };
+                        let _ = tx.send(Err(error.into())).await;
+                        break;
+                    }
+                };
+                let Some(req) = req.transpose() else {
+                    break;
+                };
+
+                match req {
+                    Ok(talk_req) => {
+                        if let Err(e) =
+                            limiter.check_message(&connection, &identity, &talk_req.message)
+                        {
+                            let _ = tx.send(Err(e.into())).await;
+                            break;
+                        }
+
+                        let answer = session.reply(&talk_req.message);
+                        let time = clock.timestamp();
+                        if let Some(transcript) = transcript.as_mut() {
+                            transcript.record(&talk_req.message, &answer, time);
+                        }
+                        sequence += 1;
+                        let mut response = utils::create_talk_response(
+                            &session_id,
+                            sequence,
+                            &answer,
+                            time,
+                            &attributes,
+                        );
+                        if let Some(cloud_event) = &response.cloud_event {
+                            events.publish_talk(cloud_event);
+                        }
+                        if !talk_req.include_cloud_event {
+                            response.cloud_event = None;
+                        }
+                        if tx.send(Ok(response)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        let _ = tx.send(Err(ServiceError::Receive(e).into())).await;
+                        break;
+                    }
+                }
+            }
+
+            if let (Some(recorder), Some(transcript)) = (recorder, transcript) {
+                match recorder.save(&transcript).await {
+                    Ok(path) => info!("Talk transcript written to {}", path.display()),
+                    Err(e) => warning!("Failed to write talk transcript: {}", e),
+                }
+            }
+        });
+
+        Ok(tonic::Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+
+    async fn background(
+        &self,
+        request: tonic::Request<BackgroundRequest>,
+    ) -> Result<tonic::Response<Self::BackgroundStream>, tonic::Status> {
+        let key = IdempotencyKey::from_request(&request, "Background")?;
+        let attributes = auth::principal_attributes(&request);
+        // Workers are stopped a little before the deadline, leaving time to
+        // deliver the final snapshot. Keyed jobs belong to the key rather
+        // than to the first call, so only the server-side maximum stops them;
+        // retries can attach until they finish.
+        let deadline = match key {
+            Some(_) => self.deadlines.max("Background"),
+            None => self.deadlines.resolve("Background", &request),
+        };
+        let cutoff = deadline.map(|deadline| deadline.saturating_sub(self.deadlines.margin()));
+        let processes = request.into_inner().processes.max(0) as usize;
+        if processes > 0 && self.workers.is_empty() {
+            return Err(ServiceError::NoWorkers.into());
+        }
+
+        // A retry with the same idempotency key follows the job the first
+        // call started instead of spawning new workers.
+        let job = key
+            .as_ref()
+            .map_or_else(|| Uuid::new_v4().to_string(), |key| key.job().to_string());
+        let mut watch = self.state.subscribe(&job);
+        let started = match &key {
+            Some(key) => key.claim(&self.state)?,
+            None => self.state.start_once(&job, State::Process, None).is_none(),
+        };
+        if !started {
+            return Ok(tonic::Response::new(attach(
+                job,
+                watch,
+                self.clock.clone(),
+                attributes,
+            )));
+        }
+
+        // Stream to the client (channels need a capacity of at least one)
+        let (tx_out, rx_out) =
+            mpsc::channel::<Result<BackgroundResponse, tonic::Status>>(processes.max(1));
+
+        // Internal channel for worker results
+        let (tx_res, mut rx_res) = mpsc::channel::<SomeServiceResponse>(processes.max(1));
+
+        let state = self.state.clone();
+        let workers = self.workers.clone();
+        let events = self.events.clone();
+        let clock = self.clock.clone();
+        let timer = self.clock.clone();
+        let detachable = key.is_some();
+
+        tokio::spawn(async move {
+            // 1) spawn workers; they are aborted when the coordinator returns
+            let mut running = JoinSet::new();
+            for i in 1..=processes {
+                let tx_res = tx_res.clone();
+                let Some(worker) = workers.worker(i) else {
+                    break;
+                };
+                running.spawn(async move {
+                    let some_response = worker.call(i).await;
+
+                    // ignore send error if coordinator is gone
+                    let _ = tx_res.send(some_response).await;
+                });
+            }
+            drop(tx_res); // Important: close so rx_res ends when all workers finish
+
+            let expiry = async move {
+                match cutoff {
+                    Some(cutoff) => timer.sleep(cutoff).await,
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::pin!(expiry);
+            let mut finished = 0;
+
+            // 2) coordinator records results in the state manager and streams
+            // every transition of the job back to the client
+            let mut client = Some(tx_out);
+            let mut collecting = true;
+
+            loop {
+                tokio::select! {
+                    update = watch.next() => {
+                        let Some(update) = update else {
+                            return;
+                        };
+                        let event = BackgroundResponseEvent::from(&update.record);
+                        let response = utils::create_background_response(
+                            &job,
+                            &event,
+                            clock.timestamp(),
+                            &attributes,
+                        );
+                        if let Some(cloud_event) = &response.cloud_event {
+                            events.publish(cloud_event);
+                        }
+                        if let Some(tx_out) = &client
+                            && tx_out.send(Ok(response)).await.is_err()
+                            && !update.record.is_finished()
+                        {
+                            // client disconnected; keyed jobs keep running so
+                            // a retry can attach to them
+                            if !detachable {
+                                return abandon(&state, &job);
+                            }
+                            client = None;
+                        }
+                    }
+                    resp = rx_res.recv(), if collecting => match resp {
+                        Some(resp) => {
+                            finished += 1;
+                            state.add_result(&job, resp);
+                        }
+                        // 3) all done -> mark complete; the watch sends the
+                        // final snapshot and ends
+                        None => {
+                            collecting = false;
+                            state.finish(&job, State::Complete);
+                        }
+                    },
+                    // 3b) out of time -> stop the workers and finish with the
+                    // results collected so far
+                    () = &mut expiry, if collecting => {
+                        collecting = false;
+                        running.abort_all();
+                        state.set_error(
+                            &job,
+                            Some(format!(
+                                "deadline exceeded with {} of {} processes finished",
+                                finished, processes
+                            )),
+                        );
+                        state.finish(&job, State::CompleteWithError);
+                    }
+                }
+            }
+        });
+
+        Ok(tonic::Response::new(Box::pin(ReceiverStream::new(rx_out))))
+    }
+
+    async fn subscribe(
+        &self,
+        request: tonic::Request<SubscribeRequest>,
+    ) -> Result<tonic::Response<Self::SubscribeStream>, tonic::Status> {
+        let principal = request
+            .extensions()
+            .get::<auth::Principal>()
+            .filter(|principal| !principal.has_scope(auth::SUBSCRIBE_ALL_SCOPE))
+            .map(|principal| principal.subject.clone());
+        let mut filter = EventFilter::from(request.into_inner());
+        if let Some(principal) = principal {
+            filter = filter.with_principal(principal);
+        }
+        Ok(tonic::Response::new(self.events.subscribe(filter)))
+    }
+
+    async fn hello_batch(
+        &self,
+        request: tonic::Request<HelloBatchRequest>,
+    ) -> Result<tonic::Response<CloudEventBatch>, tonic::Status> {
+        let attributes = auth::principal_attributes(&request);
+        let batch = greet_batch(
+            &self.greetings,
+            &self.events,
+            &*self.clock,
+            request.metadata(),
+            request.get_ref(),
+            &attributes,
+        );
+        Ok(tonic::Response::new(batch))
+    }
+
+    async fn hello_batch_stream(
+        &self,
+        request: tonic::Request<tonic::Streaming<HelloBatchRequest>>,
+    ) -> Result<tonic::Response<Self::HelloBatchStreamStream>, tonic::Status> {
+        let attributes = auth::principal_attributes(&request);
+        let (metadata, _, mut inbound) = request.into_parts();
+        let (tx, rx) = mpsc::channel(4);
+        let greetings = self.greetings.clone();
+        let events = self.events.clone();
+        let clock = self.clock.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let batch = match inbound.message().await {
+                    Ok(Some(names)) => Ok(greet_batch(
+                        &greetings,
+                        &events,
+                        &*clock,
+                        &metadata,
+                        &names,
+                        &attributes,
+                    )),
+                    Ok(None) => break,
+                    Err(e) => Err(ServiceError::Receive(e).into()),
+                };
+                let failed = batch.is_err();
+                if tx.send(batch).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        Ok(tonic::Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+}
+
+// One Hello event per name, all in the locale negotiated for the batch.
+fn greet_batch(
+    greetings: &Greetings,
+    events: &EventBus,
+    clock: &dyn Clock,
+    metadata: &MetadataMap,
+    batch: &HelloBatchRequest,
+    attributes: &HashMap<String, CloudEventAttributeValue>,
+) -> CloudEventBatch {
+    let locale = greetings.negotiate(batch.locale.as_deref(), metadata);
+    let events = batch
+        .names
+        .iter()
+        .map(|name| {
+            let event = utils::create_hello_event(
+                greetings.render(locale, name),
+                clock.timestamp(),
+                attributes,
+            );
+            events.publish(&event);
+            event
+        })
+        .collect();
+    CloudEventBatch { events }
+}
+
+// Streams an existing job to a retried Background call.
+fn attach(
+    job: String,
+    mut watch: JobWatch,
+    clock: SharedClock,
+    attributes: HashMap<String, CloudEventAttributeValue>,
+) -> <BasicServiceV1 as BasicService>::BackgroundStream {
+    let (tx, rx) = mpsc::channel(1);
+    tokio::spawn(async move {
+        while let Some(update) = watch.next().await {
+            let event = BackgroundResponseEvent::from(&update.record);
+            let response =
+                utils::create_background_response(&job, &event, clock.timestamp(), &attributes);
+            if tx.send(Ok(response)).await.is_err() {
+                break;
+            }
+        }
+    });
+    Box::pin(ReceiverStream::new(rx))
+}
+
+fn abandon(state: &StateManager, job: &str) {
+    state.set_error(job, Some("client disconnected".to_string()));
+    state.finish(job, State::Error);
+}
@@ -0,0 +1,183 @@
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{JobRecord, JobStore, store::StoredJob};
+
+pub const SCHEMA_VERSION: u32 = 1;
+// The log is compacted once it grows past this size, or twice its size after
+// the last compaction, whichever is larger.
+pub const COMPACT_THRESHOLD: u64 = 16 * 1024 * 1024;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Header {
+    schema: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum Op {
+    Put { job: StoredJob },
+    Delete { hash: String },
+}
+
+// Append-only log of job changes, one JSON object per line after a schema
+// header. `load`, and any append that pushes the log past its compaction
+// threshold, replays the log and compacts it down to the live jobs.
+#[derive(Debug)]
+pub struct JsonlStore {
+    path: PathBuf,
+    threshold: u64,
+    log: Mutex<Log>,
+}
+
+#[derive(Debug)]
+struct Log {
+    file: File,
+    size: u64,
+    compact_at: u64,
+}
+
+impl JsonlStore {
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let exists = std::fs::metadata(&path).is_ok_and(|meta| meta.len() > 0);
+        if exists {
+            let schema = read_header(&path)?.schema;
+            if schema > SCHEMA_VERSION {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "job log schema v{} is newer than this build (v{})",
+                        schema, SCHEMA_VERSION
+                    ),
+                ));
+            }
+            if schema < SCHEMA_VERSION {
+                // Rewriting the log in the current format is the migration.
+                let jobs = replay(&path)?;
+                rewrite(&path, &jobs)?;
+            }
+        } else {
+            rewrite(&path, &HashMap::new())?;
+        }
+
+        let file = OpenOptions::new().append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            threshold: COMPACT_THRESHOLD,
+            log: Mutex::new(Log {
+                file,
+                size,
+                compact_at: COMPACT_THRESHOLD.max(size * 2),
+            }),
+        })
+    }
+
+    pub fn with_compact_threshold(mut self, bytes: u64) -> Self {
+        self.threshold = bytes;
+        let log = self.log.get_mut().unwrap();
+        log.compact_at = bytes.max(log.size * 2);
+        self
+    }
+
+    fn append(&self, op: &Op) -> io::Result<()> {
+        let mut line = serde_json::to_vec(op)?;
+        line.push(b'\n');
+        let mut log = self.log.lock().unwrap();
+        log.file.write_all(&line)?;
+        log.size += line.len() as u64;
+        if log.size >= log.compact_at {
+            self.compact(&mut log)?;
+        }
+        Ok(())
+    }
+
+    fn compact(&self, log: &mut Log) -> io::Result<HashMap<String, StoredJob>> {
+        let jobs = replay(&self.path)?;
+        rewrite(&self.path, &jobs)?;
+        log.file = OpenOptions::new().append(true).open(&self.path)?;
+        log.size = log.file.metadata()?.len();
+        log.compact_at = self.threshold.max(log.size * 2);
+        Ok(jobs)
+    }
+}
+
+impl JobStore for JsonlStore {
+    fn save(&self, hash: &str, record: &JobRecord) -> io::Result<()> {
+        self.append(&Op::Put {
+            job: StoredJob::from_record(hash, record),
+        })
+    }
+
+    fn delete(&self, hash: &str) -> io::Result<()> {
+        self.append(&Op::Delete {
+            hash: hash.to_string(),
+        })
+    }
+
+    fn load(&self) -> io::Result<Vec<(String, JobRecord)>> {
+        let jobs = self.compact(&mut self.log.lock().unwrap())?;
+        jobs.into_values().map(StoredJob::into_record).collect()
+    }
+}
+
+fn read_header(path: &Path) -> io::Result<Header> {
+    let mut line = String::new();
+    BufReader::new(File::open(path)?).read_line(&mut line)?;
+    serde_json::from_str(&line).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("missing job log header: {}", e),
+        )
+    })
+}
+
+fn replay(path: &Path) -> io::Result<HashMap<String, StoredJob>> {
+    let mut jobs = HashMap::new();
+    for line in BufReader::new(File::open(path)?).lines().skip(1) {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line)? {
+            Op::Put { job } => {
+                jobs.insert(job.hash.clone(), job);
+            }
+            Op::Delete { hash } => {
+                jobs.remove(&hash);
+            }
+        }
+    }
+    Ok(jobs)
+}
+
+// Writes the header and one `put` per job to a temporary file and swaps it in.
+fn rewrite(path: &Path, jobs: &HashMap<String, StoredJob>) -> io::Result<()> {
+    let tmp = path.with_extension("jsonl.tmp");
+    let mut out = io::BufWriter::new(File::create(&tmp)?);
+    serde_json::to_writer(
+        &mut out,
+        &Header {
+            schema: SCHEMA_VERSION,
+        },
+    )?;
+    out.write_all(b"\n")?;
+    for job in jobs.values() {
+        serde_json::to_writer(&mut out, &Op::Put { job: job.clone() })?;
+        out.write_all(b"\n")?;
+    }
+    out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    std::fs::rename(tmp, path)
+}
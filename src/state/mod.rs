@@ -0,0 +1,518 @@
+mod jsonl;
+mod sqlite;
+mod store;
+mod watch;
+mod writer;
+
+use std::{
+    collections::{BTreeSet, HashMap, hash_map::RandomState},
+    hash::BuildHasher,
+    io,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, SystemTime},
+};
+
+use prost_types::Timestamp;
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+
+pub use jsonl::{COMPACT_THRESHOLD, JsonlStore};
+pub use sqlite::SqliteStore;
+pub use store::{JOB_STORE_ENV, JobStore, MemoryStore, SharedJobStore, store_from_env};
+pub use watch::{EVENTS_CAPACITY, JobChange, JobEvent, JobEvents, JobWatch, WATCH_CAPACITY};
+
+pub use writer::STORE_QUEUE_CAPACITY;
+
+use writer::StoreWriter;
+
+use crate::{
+    clock::{SharedClock, SystemClock},
+    sdk::{
+        basic::service::v1::{SomeServiceResponse, State},
+        io::cloudevents::v1::CloudEvent,
+    },
+};
+
+const SHARDS: usize = 16;
+
+pub const DEFAULT_TTL: Duration = Duration::from_secs(10 * 60);
+pub const DEFAULT_CAPACITY: usize = 10_000;
+// How often `spawn_sweeper` drops expired jobs by default.
+pub const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+pub const INTERRUPTED: &str = "interrupted by restart";
+
+pub type JobState = (
+    Option<State>,
+    Option<Timestamp>,
+    Option<Timestamp>,
+    Option<Vec<String>>,
+);
+
+// Everything known about one Background job. Records are updated under a
+// single lock, so readers never see a half-applied transition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobRecord {
+    pub state: State,
+    pub started_at: Option<Timestamp>,
+    pub completed_at: Option<Timestamp>,
+    pub errors: Vec<String>,
+    pub results: Vec<SomeServiceResponse>,
+    // The event a unary call answered with, kept for idempotency key replays.
+    pub event: Option<CloudEvent>,
+    // Hash of the request that started a keyed job, see `IdempotencyKey`.
+    pub fingerprint: Option<String>,
+    finished: Option<SystemTime>,
+}
+
+impl JobRecord {
+    fn new(state: State) -> Self {
+        Self {
+            state,
+            started_at: None,
+            completed_at: None,
+            errors: Vec::new(),
+            results: Vec::new(),
+            event: None,
+            fingerprint: None,
+            finished: None,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished.is_some()
+    }
+
+    fn expired(&self, now: SystemTime, ttl: Duration) -> bool {
+        self.finished
+            .is_some_and(|finished| now.duration_since(finished).unwrap_or_default() >= ttl)
+    }
+}
+
+// The jobs of one shard, with the finished ones indexed by completion time so
+// eviction only ever looks at the oldest finished jobs. Jobs somebody watches
+// have a channel of their own until they finish.
+#[derive(Debug, Default)]
+struct Jobs {
+    records: HashMap<String, JobRecord>,
+    finished: BTreeSet<(SystemTime, String)>,
+    watchers: HashMap<String, broadcast::Sender<JobEvent>>,
+}
+
+impl Jobs {
+    fn get(&self, hash: &str) -> Option<&JobRecord> {
+        self.records.get(hash)
+    }
+
+    fn insert(&mut self, hash: &str, record: JobRecord) {
+        if let Some(at) = record.finished {
+            self.finished.insert((at, hash.to_string()));
+        }
+        if let Some(old) = self.records.insert(hash.to_string(), record)
+            && let Some(at) = old.finished
+        {
+            self.finished.remove(&(at, hash.to_string()));
+        }
+    }
+
+    // Drops the oldest finished job and returns its hash.
+    fn pop_oldest(&mut self) -> Option<String> {
+        let (_, hash) = self.finished.pop_first()?;
+        self.records.remove(&hash);
+        Some(hash)
+    }
+
+    fn oldest_finished(&self) -> Option<SystemTime> {
+        self.finished.first().map(|(at, _)| *at)
+    }
+}
+
+type Shard = Mutex<Jobs>;
+
+// Job records spread over a fixed number of independently locked shards.
+// Finished jobs are dropped once they are older than the TTL, and the oldest
+// finished jobs are dropped first when a shard grows past its share of the
+// capacity. Running jobs are never evicted.
+#[derive(Debug, Clone)]
+pub struct StateManager {
+    clock: SharedClock,
+    shards: Arc<[Shard]>,
+    hasher: RandomState,
+    ttl: Duration,
+    capacity: usize,
+    store: Option<SharedJobStore>,
+    writer: Option<Arc<StoreWriter>>,
+    events: broadcast::Sender<JobEvent>,
+}
+
+impl Default for StateManager {
+    fn default() -> Self {
+        Self {
+            clock: Arc::new(SystemClock),
+            shards: (0..SHARDS).map(|_| Shard::default()).collect(),
+            hasher: RandomState::new(),
+            ttl: DEFAULT_TTL,
+            capacity: DEFAULT_CAPACITY,
+            store: None,
+            writer: None,
+            events: broadcast::channel(EVENTS_CAPACITY).0,
+        }
+    }
+}
+
+impl StateManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Start and completion times are read from `clock`. The job records stay
+    // shared with the manager this one was cloned from.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    // How long finished jobs stay visible.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    // Upper bound on the number of finished jobs kept around.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    // Every change is written through to `store` from a background thread;
+    // call `recover` once on startup to load what a previous process left
+    // behind, and `shutdown` before exiting. Changes are dropped while
+    // `STORE_QUEUE_CAPACITY` writes are waiting for the store.
+    pub fn with_store(mut self, store: SharedJobStore) -> Self {
+        self.writer = Some(Arc::new(StoreWriter::spawn(store.clone())));
+        self.store = Some(store);
+        self
+    }
+
+    // Blocks until every change made so far has reached the store.
+    pub fn flush(&self) {
+        if let Some(writer) = &self.writer {
+            writer.flush();
+        }
+    }
+
+    // Blocks until the queued changes have reached the store and stops
+    // writing to it; later changes stay in memory. Shared by every clone.
+    pub fn shutdown(&self) {
+        if let Some(writer) = &self.writer {
+            writer.shutdown();
+        }
+    }
+
+    // Changes that never reached the store because its queue was full.
+    pub fn dropped_writes(&self) -> u64 {
+        self.writer.as_ref().map_or(0, |writer| writer.dropped())
+    }
+
+    // Loads all jobs from the store. Jobs that were still running when the
+    // previous process stopped are marked STATE_ERROR; returns how many.
+    pub fn recover(&self) -> io::Result<usize> {
+        let Some(store) = &self.store else {
+            return Ok(0);
+        };
+
+        let now = self.clock.now();
+        let mut interrupted = 0;
+        for (hash, mut record) in store.load()? {
+            if !record.is_finished() {
+                record.state = State::Error;
+                record.errors.push(INTERRUPTED.to_string());
+                record.completed_at = Some(now.into());
+                record.finished = Some(now);
+                store.save(&hash, &record)?;
+                interrupted += 1;
+            }
+            self.shard(&hash).insert(&hash, record);
+        }
+
+        self.evict_expired();
+        Ok(interrupted)
+    }
+
+    // Drops expired jobs every `every` on the manager's clock, so they do not
+    // linger until the next `finish` on their shard. The task ends once every
+    // other clone of the manager is gone.
+    pub fn spawn_sweeper(&self, every: Duration) -> tokio::task::JoinHandle<()> {
+        let state = self.clone();
+        tokio::spawn(async move {
+            loop {
+                state.clock.sleep(every).await;
+                if Arc::strong_count(&state.shards) == 1 {
+                    return;
+                }
+                state.evict_expired();
+            }
+        })
+    }
+
+    // Queued under the shard lock, so the store sees the changes of a job in
+    // the order they were applied.
+    fn persist(&self, hash: &str, record: &JobRecord) {
+        if let Some(writer) = &self.writer {
+            writer.save(hash, record);
+        }
+    }
+
+    // Called with the shard lock held, so watchers see transitions of a job in
+    // the order they were applied. The record is only cloned for a job that is
+    // watched, or while somebody follows `subscribe_all`.
+    fn notify(
+        &self,
+        watchers: &HashMap<String, broadcast::Sender<JobEvent>>,
+        hash: &str,
+        change: JobChange,
+        record: &JobRecord,
+    ) {
+        let watcher = watchers.get(hash).filter(|tx| tx.receiver_count() > 0);
+        let everyone = Some(&self.events).filter(|tx| tx.receiver_count() > 0);
+        if watcher.is_none() && everyone.is_none() {
+            return;
+        }
+        let event = JobEvent {
+            hash: hash.to_string(),
+            change,
+            record: record.clone(),
+        };
+        if let Some(everyone) = everyone {
+            let _ = everyone.send(event.clone());
+        }
+        if let Some(watcher) = watcher {
+            let _ = watcher.send(event);
+        }
+    }
+
+    // Streams the transitions of one job; see `JobWatch`.
+    pub fn subscribe(&self, hash: &str) -> JobWatch {
+        let now = self.clock.now();
+        let mut shard = self.shard(hash);
+        let snapshot = shard
+            .get(hash)
+            .filter(|record| !record.expired(now, self.ttl))
+            .cloned();
+        // A finished job has nothing left to send.
+        let events = match &snapshot {
+            Some(record) if record.is_finished() => None,
+            _ => Some(BroadcastStream::new(
+                shard
+                    .watchers
+                    .entry(hash.to_string())
+                    .or_insert_with(|| broadcast::channel(WATCH_CAPACITY).0)
+                    .subscribe(),
+            )),
+        };
+        drop(shard);
+        JobWatch::new(hash, self.clone(), snapshot, events)
+    }
+
+    // Drops the channel of a job once its last watcher is gone.
+    pub(super) fn unwatch(&self, hash: &str) {
+        let mut shard = self.shard(hash);
+        if shard
+            .watchers
+            .get(hash)
+            .is_some_and(|tx| tx.receiver_count() == 0)
+        {
+            shard.watchers.remove(hash);
+        }
+    }
+
+    // Streams the transitions of every job.
+    pub fn subscribe_all(&self) -> JobEvents {
+        BroadcastStream::new(self.events.subscribe())
+    }
+
+    fn forget(&self, hash: &str) {
+        if let Some(writer) = &self.writer {
+            writer.delete(hash);
+        }
+    }
+
+    fn shard(&self, hash: &str) -> MutexGuard<'_, Jobs> {
+        let index = self.hasher.hash_one(hash) as usize % self.shards.len();
+        self.shards[index].lock().unwrap()
+    }
+
+    pub fn start(&self, hash: &str, state: State) {
+        let mut record = JobRecord::new(state);
+        record.started_at = Some(self.clock.timestamp());
+
+        let mut shard = self.shard(hash);
+        self.persist(hash, &record);
+        self.notify(&shard.watchers, hash, JobChange::Started, &record);
+        shard.insert(hash, record);
+    }
+
+    // Like `start`, but leaves an existing unexpired job alone and returns it.
+    // A job that is started records `fingerprint`.
+    pub fn start_once(
+        &self,
+        hash: &str,
+        state: State,
+        fingerprint: Option<String>,
+    ) -> Option<JobRecord> {
+        let now = self.clock.now();
+        let mut shard = self.shard(hash);
+        if let Some(record) = shard
+            .get(hash)
+            .filter(|record| !record.expired(now, self.ttl))
+        {
+            return Some(record.clone());
+        }
+
+        let mut record = JobRecord::new(state);
+        record.started_at = Some(now.into());
+        record.fingerprint = fingerprint;
+        self.persist(hash, &record);
+        self.notify(&shard.watchers, hash, JobChange::Started, &record);
+        shard.insert(hash, record);
+        None
+    }
+
+    pub fn finish(&self, hash: &str, state: State) {
+        let now = self.clock.now();
+        let mut shard = self.shard(hash);
+        let jobs = &mut *shard;
+
+        let record = jobs
+            .records
+            .entry(hash.to_string())
+            .or_insert_with(|| JobRecord::new(state));
+        if let Some(at) = record.finished.replace(now) {
+            jobs.finished.remove(&(at, hash.to_string()));
+        }
+        jobs.finished.insert((now, hash.to_string()));
+        record.state = state;
+        record.completed_at = Some(now.into());
+        self.persist(hash, record);
+        self.notify(&jobs.watchers, hash, JobChange::Finished, record);
+        // Watchers end with this event.
+        jobs.watchers.remove(hash);
+
+        // Only finished jobs count against the capacity; running ones are
+        // never evicted.
+        let limit = self.capacity.div_ceil(self.shards.len());
+        if shard.finished.len() > limit {
+            self.evict(&mut shard, now, limit);
+        }
+    }
+
+    pub fn add_result(&self, hash: &str, result: SomeServiceResponse) {
+        let mut shard = self.shard(hash);
+        let jobs = &mut *shard;
+        if let Some(record) = jobs.records.get_mut(hash) {
+            record.results.push(result);
+            self.persist(hash, record);
+            self.notify(&jobs.watchers, hash, JobChange::Result, record);
+        }
+    }
+
+    pub fn set_event(&self, hash: &str, event: CloudEvent) {
+        let mut shard = self.shard(hash);
+        let jobs = &mut *shard;
+        if let Some(record) = jobs.records.get_mut(hash) {
+            record.event = Some(event);
+            self.persist(hash, record);
+            self.notify(&jobs.watchers, hash, JobChange::Result, record);
+        }
+    }
+
+    pub fn set_error(&self, hash: &str, err: Option<String>) {
+        if let Some(error) = err {
+            let mut shard = self.shard(hash);
+            let jobs = &mut *shard;
+            let record = jobs
+                .records
+                .entry(hash.to_string())
+                .or_insert_with(|| JobRecord::new(State::Error));
+            record.errors.push(error);
+            self.persist(hash, record);
+            self.notify(&jobs.watchers, hash, JobChange::Error, record);
+        }
+    }
+
+    // A consistent snapshot of a job, or `None` if it is unknown or expired.
+    pub fn job(&self, hash: &str) -> Option<JobRecord> {
+        let now = self.clock.now();
+        self.shard(hash)
+            .get(hash)
+            .filter(|record| !record.expired(now, self.ttl))
+            .cloned()
+    }
+
+    pub fn get_state(&self, hash: &str) -> JobState {
+        match self.job(hash) {
+            Some(record) => (
+                Some(record.state),
+                record.started_at,
+                record.completed_at,
+                Some(record.errors).filter(|errors| !errors.is_empty()),
+            ),
+            None => (None, None, None, None),
+        }
+    }
+
+    pub fn has_errors(&self, hash: &str) -> bool {
+        self.job(hash)
+            .is_some_and(|record| !record.errors.is_empty())
+    }
+
+    pub fn get_errors(&self, hash: &str) -> Vec<String> {
+        self.job(hash)
+            .map(|record| record.errors)
+            .unwrap_or_default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.lock().unwrap().records.len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Drops every finished job older than the TTL and returns how many were
+    // removed. `spawn_sweeper` calls this periodically, and `finish` evicts
+    // once a shard holds too many finished jobs.
+    pub fn evict_expired(&self) -> usize {
+        let now = self.clock.now();
+        self.shards
+            .iter()
+            .map(|shard| self.drop_expired(&mut shard.lock().unwrap(), now))
+            .sum()
+    }
+
+    fn drop_expired(&self, jobs: &mut Jobs, now: SystemTime) -> usize {
+        let mut dropped = 0;
+        while jobs
+            .oldest_finished()
+            .is_some_and(|at| now.duration_since(at).unwrap_or_default() >= self.ttl)
+            && let Some(hash) = jobs.pop_oldest()
+        {
+            self.forget(&hash);
+            dropped += 1;
+        }
+        dropped
+    }
+
+    fn evict(&self, jobs: &mut Jobs, now: SystemTime, limit: usize) {
+        self.drop_expired(jobs, now);
+        // Trim below the limit so the next eviction is a while away.
+        while jobs.finished.len() > limit - limit / 4
+            && let Some(hash) = jobs.pop_oldest()
+        {
+            self.forget(&hash);
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use std::{io, path::Path, sync::Mutex};
+
+use rusqlite::{Connection, params};
+
+use super::{JobRecord, JobStore, store::StoredJob};
+
+// Applied in order; `PRAGMA user_version` records how many already ran.
+// Append new steps, never edit released ones.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE jobs (
+        hash TEXT PRIMARY KEY NOT NULL,
+        state TEXT NOT NULL,
+        started_at TEXT,
+        completed_at TEXT,
+        errors TEXT NOT NULL DEFAULT '[]',
+        results TEXT NOT NULL DEFAULT '[]'
+    )",
+    "CREATE INDEX jobs_state ON jobs (state)",
+    "ALTER TABLE jobs ADD COLUMN event TEXT;
+     ALTER TABLE jobs ADD COLUMN fingerprint TEXT",
+];
+
+// Embedded SQLite database holding one row per job.
+#[derive(Debug)]
+pub struct SqliteStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    pub const SCHEMA_VERSION: usize = MIGRATIONS.len();
+
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut conn = Connection::open(path).map_err(io::Error::other)?;
+        conn.pragma_update(None, "journal_mode", "WAL")
+            .map_err(io::Error::other)?;
+        migrate(&mut conn)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    pub fn schema_version(&self) -> io::Result<usize> {
+        user_version(&self.conn.lock().unwrap())
+    }
+}
+
+fn user_version(conn: &Connection) -> io::Result<usize> {
+    conn.pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(io::Error::other)
+}
+
+fn migrate(conn: &mut Connection) -> io::Result<()> {
+    let version = user_version(conn)?;
+    if version > MIGRATIONS.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "job database schema v{} is newer than this build (v{})",
+                version,
+                MIGRATIONS.len()
+            ),
+        ));
+    }
+
+    let tx = conn.transaction().map_err(io::Error::other)?;
+    for sql in &MIGRATIONS[version..] {
+        tx.execute_batch(sql).map_err(io::Error::other)?;
+    }
+    tx.pragma_update(None, "user_version", MIGRATIONS.len())
+        .map_err(io::Error::other)?;
+    tx.commit().map_err(io::Error::other)
+}
+
+impl JobStore for SqliteStore {
+    fn save(&self, hash: &str, record: &JobRecord) -> io::Result<()> {
+        let job = StoredJob::from_record(hash, record);
+        self.conn
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO jobs
+                    (hash, state, started_at, completed_at, errors, results, event, fingerprint)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                 ON CONFLICT (hash) DO UPDATE SET
+                    state = excluded.state,
+                    started_at = excluded.started_at,
+                    completed_at = excluded.completed_at,
+                    errors = excluded.errors,
+                    results = excluded.results,
+                    event = excluded.event,
+                    fingerprint = excluded.fingerprint",
+                params![
+                    job.hash,
+                    job.state,
+                    job.started_at,
+                    job.completed_at,
+                    serde_json::to_string(&job.errors)?,
+                    serde_json::to_string(&job.results)?,
+                    job.event,
+                    job.fingerprint,
+                ],
+            )
+            .map(|_| ())
+            .map_err(io::Error::other)
+    }
+
+    fn delete(&self, hash: &str) -> io::Result<()> {
+        self.conn
+            .lock()
+            .unwrap()
+            .execute("DELETE FROM jobs WHERE hash = ?1", [hash])
+            .map(|_| ())
+            .map_err(io::Error::other)
+    }
+
+    fn load(&self) -> io::Result<Vec<(String, JobRecord)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT hash, state, started_at, completed_at, errors, results, event, fingerprint
+                 FROM jobs",
+            )
+            .map_err(io::Error::other)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, String>(5)?,
+                    row.get::<_, Option<String>>(6)?,
+                    row.get::<_, Option<String>>(7)?,
+                ))
+            })
+            .map_err(io::Error::other)?;
+
+        rows.map(|row| {
+            let (hash, state, started_at, completed_at, errors, results, event, fingerprint) =
+                row.map_err(io::Error::other)?;
+            StoredJob {
+                hash,
+                state,
+                started_at,
+                completed_at,
+                errors: serde_json::from_str(&errors)?,
+                results: serde_json::from_str(&results)?,
+                event,
+                fingerprint,
+            }
+            .into_record()
+        })
+        .collect()
+    }
+}
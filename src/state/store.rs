@@ -0,0 +1,201 @@
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    io,
+    sync::{Arc, Mutex},
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use prost::Message;
+use prost_types::Timestamp;
+use serde::{Deserialize, Serialize};
+
+use super::{JobRecord, JsonlStore, SqliteStore};
+use crate::sdk::{
+    basic::service::v1::{SomeServiceData, SomeServiceResponse, State},
+    io::cloudevents::v1::CloudEvent,
+};
+
+pub const JOB_STORE_ENV: &str = "JOB_STORE";
+
+// Durable home for job records. `StateManager` writes every change through to
+// its store and reads the store back once on startup in `recover`.
+pub trait JobStore: Debug + Send + Sync + 'static {
+    fn save(&self, hash: &str, record: &JobRecord) -> io::Result<()>;
+
+    fn delete(&self, hash: &str) -> io::Result<()>;
+
+    fn load(&self) -> io::Result<Vec<(String, JobRecord)>>;
+}
+
+pub type SharedJobStore = Arc<dyn JobStore>;
+
+// Picks a store from JOB_STORE: `memory`, `sqlite:<path>` or `jsonl:<path>`.
+// Without the variable jobs are only kept in the state manager itself.
+pub fn store_from_env() -> Result<Option<SharedJobStore>, String> {
+    let Ok(spec) = std::env::var(JOB_STORE_ENV) else {
+        return Ok(None);
+    };
+    let store: SharedJobStore = match spec.split_once(':') {
+        Some(("sqlite", path)) => {
+            Arc::new(SqliteStore::open(path).map_err(|e| format!("cannot open {}: {}", path, e))?)
+        }
+        Some(("jsonl", path)) => {
+            Arc::new(JsonlStore::open(path).map_err(|e| format!("cannot open {}: {}", path, e))?)
+        }
+        None if spec == "memory" => Arc::new(MemoryStore::new()),
+        _ => return Err(format!("unknown job store: {}", spec)),
+    };
+    Ok(Some(store))
+}
+
+// Keeps records for the lifetime of the process. Managers sharing one
+// `MemoryStore` can recover each other's jobs, which is handy in tests.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore {
+    records: Arc<Mutex<HashMap<String, JobRecord>>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl JobStore for MemoryStore {
+    fn save(&self, hash: &str, record: &JobRecord) -> io::Result<()> {
+        self.records
+            .lock()
+            .unwrap()
+            .insert(hash.to_string(), record.clone());
+        Ok(())
+    }
+
+    fn delete(&self, hash: &str) -> io::Result<()> {
+        self.records.lock().unwrap().remove(hash);
+        Ok(())
+    }
+
+    fn load(&self) -> io::Result<Vec<(String, JobRecord)>> {
+        Ok(self
+            .records
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(hash, record)| (hash.clone(), record.clone()))
+            .collect())
+    }
+}
+
+// Serialized form of a job shared by the SQLite and JSONL stores.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(super) struct StoredJob {
+    pub hash: String,
+    pub state: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<String>,
+    #[serde(default)]
+    pub errors: Vec<String>,
+    #[serde(default)]
+    pub results: Vec<StoredResult>,
+    // Base64 of the protobuf-encoded CloudEvent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(super) struct StoredResult {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_value: Option<String>,
+}
+
+impl StoredJob {
+    pub fn from_record(hash: &str, record: &JobRecord) -> Self {
+        Self {
+            hash: hash.to_string(),
+            state: record.state.as_str_name().to_string(),
+            started_at: record.started_at.map(|ts| ts.to_string()),
+            completed_at: record.completed_at.map(|ts| ts.to_string()),
+            errors: record.errors.clone(),
+            results: record
+                .results
+                .iter()
+                .map(|result| StoredResult {
+                    id: result.id.clone(),
+                    name: result.name.clone(),
+                    version: result.version.clone(),
+                    data_type: result.data.as_ref().map(|data| data.r#type.clone()),
+                    data_value: result.data.as_ref().map(|data| data.value.clone()),
+                })
+                .collect(),
+            event: record
+                .event
+                .as_ref()
+                .map(|event| STANDARD.encode(event.encode_to_vec())),
+            fingerprint: record.fingerprint.clone(),
+        }
+    }
+
+    pub fn into_record(self) -> io::Result<(String, JobRecord)> {
+        let invalid = |what: &str, value: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("job {}: invalid {}: {}", self.hash, what, value),
+            )
+        };
+        let timestamp = |value: &Option<String>| -> io::Result<Option<Timestamp>> {
+            value
+                .as_deref()
+                .map(|ts| ts.parse().map_err(|_| invalid("timestamp", ts)))
+                .transpose()
+        };
+
+        let state =
+            State::from_str_name(&self.state).ok_or_else(|| invalid("state", &self.state))?;
+        let mut record = JobRecord::new(state);
+        record.started_at = timestamp(&self.started_at)?;
+        record.completed_at = timestamp(&self.completed_at)?;
+        record.finished = record
+            .completed_at
+            .and_then(|ts| std::time::SystemTime::try_from(ts).ok());
+        record.errors = self.errors;
+        record.results = self
+            .results
+            .into_iter()
+            .map(|result| SomeServiceResponse {
+                id: result.id,
+                name: result.name,
+                version: result.version,
+                data: match (result.data_type, result.data_value) {
+                    (None, None) => None,
+                    (r#type, value) => Some(SomeServiceData {
+                        r#type: r#type.unwrap_or_default(),
+                        value: value.unwrap_or_default(),
+                    }),
+                },
+            })
+            .collect();
+        record.event = self
+            .event
+            .as_deref()
+            .map(|event| {
+                STANDARD
+                    .decode(event)
+                    .ok()
+                    .and_then(|bytes| CloudEvent::decode(bytes.as_slice()).ok())
+                    .ok_or_else(|| invalid("event", event))
+            })
+            .transpose()?;
+        record.fingerprint = self.fingerprint;
+        Ok((self.hash, record))
+    }
+}
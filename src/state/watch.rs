@@ -0,0 +1,133 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+use tokio_stream::wrappers::{BroadcastStream, errors::BroadcastStreamRecvError};
+
+use super::{JobRecord, StateManager};
+use crate::sdk::basic::service::v1::BackgroundResponseEvent;
+
+// Number of transitions of one job buffered for its watchers before the
+// slowest ones lag.
+pub const WATCH_CAPACITY: usize = 64;
+
+// Number of transitions buffered for `subscribe_all` before the slowest
+// subscribers lag.
+pub const EVENTS_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobChange {
+    Started,
+    Result,
+    Error,
+    Finished,
+    // Current record handed to a watcher that just subscribed or fell behind.
+    Snapshot,
+}
+
+// A job transition together with the record as it looked right after it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobEvent {
+    pub hash: String,
+    pub change: JobChange,
+    pub record: JobRecord,
+}
+
+impl From<&JobRecord> for BackgroundResponseEvent {
+    fn from(record: &JobRecord) -> Self {
+        BackgroundResponseEvent {
+            started_at: record.started_at,
+            state: record.state as i32,
+            completed_at: record.completed_at,
+            responses: record.results.clone(),
+        }
+    }
+}
+
+// Every transition of every job. A lagging subscriber sees a `Lagged` error
+// with the number of events it missed and then continues with newer ones.
+pub type JobEvents = BroadcastStream<JobEvent>;
+
+// Transitions of a single job, starting with a snapshot if the job already
+// exists. Ends after the job finishes. Missed events are replaced by a fresh
+// snapshot, so a slow watcher always converges on the latest record. Every
+// watched job has its own channel, so watchers never sift through the
+// transitions of other jobs.
+pub struct JobWatch {
+    hash: String,
+    state: StateManager,
+    pending: Option<JobEvent>,
+    events: Option<JobEvents>,
+    done: bool,
+}
+
+impl JobWatch {
+    pub(super) fn new(
+        hash: &str,
+        state: StateManager,
+        snapshot: Option<JobRecord>,
+        events: Option<JobEvents>,
+    ) -> Self {
+        Self {
+            hash: hash.to_string(),
+            state,
+            pending: snapshot.map(|record| JobEvent {
+                hash: hash.to_string(),
+                change: JobChange::Snapshot,
+                record,
+            }),
+            events,
+            done: false,
+        }
+    }
+
+    fn emit(&mut self, event: JobEvent) -> Poll<Option<JobEvent>> {
+        self.done = event.record.is_finished();
+        Poll::Ready(Some(event))
+    }
+}
+
+impl Stream for JobWatch {
+    type Item = JobEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+        if let Some(event) = self.pending.take() {
+            return self.emit(event);
+        }
+
+        loop {
+            let Some(events) = self.events.as_mut() else {
+                return Poll::Ready(None);
+            };
+            match Pin::new(events).poll_next(cx) {
+                Poll::Ready(Some(Ok(event))) => return self.emit(event),
+                Poll::Ready(Some(Err(BroadcastStreamRecvError::Lagged(_)))) => {
+                    let Some(record) = self.state.job(&self.hash) else {
+                        continue;
+                    };
+                    let event = JobEvent {
+                        hash: self.hash.clone(),
+                        change: JobChange::Snapshot,
+                        record,
+                    };
+                    return self.emit(event);
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl Drop for JobWatch {
+    fn drop(&mut self) {
+        if self.events.take().is_some() {
+            self.state.unwatch(&self.hash);
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use std::{
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{self, SyncSender, TrySendError},
+    },
+    thread::{self, JoinHandle},
+};
+
+use super::{JobRecord, SharedJobStore};
+use crate::warning;
+
+// Most writes waiting for the store; further writes are dropped until the
+// store catches up.
+pub const STORE_QUEUE_CAPACITY: usize = 10_000;
+
+enum Write {
+    Save(String, Box<JobRecord>),
+    Delete(String),
+    Flush(mpsc::Sender<()>),
+}
+
+// Applies store writes on a dedicated thread in the order they were queued,
+// so no shard lock is held while a store does I/O. `shutdown` waits for the
+// queued writes; dropping the writer leaves the thread to finish them alone.
+#[derive(Debug)]
+pub(super) struct StoreWriter {
+    queue: Mutex<Option<SyncSender<Write>>>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+    dropped: AtomicU64,
+    dropping: AtomicBool,
+}
+
+impl StoreWriter {
+    pub fn spawn(store: SharedJobStore) -> Self {
+        let (queue, writes) = mpsc::sync_channel(STORE_QUEUE_CAPACITY);
+        let thread = thread::Builder::new()
+            .name("job-store".to_string())
+            .spawn(move || {
+                for write in writes {
+                    match write {
+                        Write::Save(hash, record) => {
+                            if let Err(e) = store.save(&hash, &record) {
+                                warning!("Failed to persist job {}: {}", hash, e);
+                            }
+                        }
+                        Write::Delete(hash) => {
+                            if let Err(e) = store.delete(&hash) {
+                                warning!("Failed to delete job {}: {}", hash, e);
+                            }
+                        }
+                        Write::Flush(done) => {
+                            let _ = done.send(());
+                        }
+                    }
+                }
+            })
+            .expect("spawn job store thread");
+        Self {
+            queue: Mutex::new(Some(queue)),
+            thread: Mutex::new(Some(thread)),
+            dropped: AtomicU64::new(0),
+            dropping: AtomicBool::new(false),
+        }
+    }
+
+    fn queue(&self) -> Option<SyncSender<Write>> {
+        self.queue.lock().unwrap().clone()
+    }
+
+    // Never blocks: a full queue drops the write. Only the first drop after
+    // the queue had room is logged, so a store that stays behind does not
+    // flood the log.
+    fn send(&self, write: Write) {
+        let Some(queue) = self.queue() else {
+            return;
+        };
+        match queue.try_send(write) {
+            Ok(()) => self.dropping.store(false, Ordering::Relaxed),
+            Err(TrySendError::Full(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                if !self.dropping.swap(true, Ordering::Relaxed) {
+                    warning!("Job store is falling behind; dropping writes");
+                }
+            }
+            Err(TrySendError::Disconnected(_)) => {}
+        }
+    }
+
+    pub fn save(&self, hash: &str, record: &JobRecord) {
+        self.send(Write::Save(hash.to_string(), Box::new(record.clone())));
+    }
+
+    pub fn delete(&self, hash: &str) {
+        self.send(Write::Delete(hash.to_string()));
+    }
+
+    // Writes dropped because the queue was full.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    // Waits for room in the queue, then for every write queued before it.
+    pub fn flush(&self) {
+        let Some(queue) = self.queue() else {
+            return;
+        };
+        let (done, flushed) = mpsc::channel();
+        if queue.send(Write::Flush(done)).is_ok() {
+            let _ = flushed.recv();
+        }
+    }
+
+    // Stops taking writes and blocks until the queued ones reached the store.
+    pub fn shutdown(&self) {
+        self.queue.lock().unwrap().take();
+        if let Some(thread) = self.thread.lock().unwrap().take() {
+            let _ = thread.join();
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use std::fmt::Debug;
+
+use rand::SeedableRng;
+
+use crate::talk::talk::{Answer, SessionRng, answer};
+
+// Produces the answers for a Talk stream. Each stream gets its own session.
+pub trait ConversationBackend: Debug + Send + Sync + 'static {
+    fn start(&self, seed: u64) -> Box<dyn ConversationSession>;
+}
+
+pub trait ConversationSession: Send {
+    fn reply(&mut self, input: &str) -> Answer;
+}
+
+// The built-in rule based therapist from `talk::globals`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Eliza;
+
+struct ElizaSession {
+    rng: SessionRng,
+}
+
+impl ConversationBackend for Eliza {
+    fn start(&self, seed: u64) -> Box<dyn ConversationSession> {
+        Box::new(ElizaSession {
+            rng: SessionRng::seed_from_u64(seed),
+        })
+    }
+}
+
+impl ConversationSession for ElizaSession {
+    fn reply(&mut self, input: &str) -> Answer {
+        answer(input, &mut self.rng)
+    }
+}
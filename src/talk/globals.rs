@@ -48,8 +48,11 @@ lazy_static! {
         m.insert("me", "you");
         m
     };
-    pub static ref REGEX_RESPONSES: Vec<(Regex, Vec<&'static str>)> = vec![
+    // Each rule starts with the id reported as `TalkResponse.rule`. Keep ids
+    // stable when rewording a pattern; clients and transcripts rely on them.
+    pub static ref REGEX_RESPONSES: Vec<(&'static str, Regex, Vec<&'static str>)> = vec![
         (
+            "need",
             Regex::new(r"i need (.*)").unwrap(),
             vec![
                 "Why do you need %s?",
@@ -58,6 +61,7 @@ lazy_static! {
             ]
         ),
         (
+            "why-dont-you",
             Regex::new(r"why don'?t you ([^\?]*)\??").unwrap(),
             vec![
                 "Do you really think I don't %s?",
@@ -66,6 +70,7 @@ lazy_static! {
             ]
         ),
         (
+            "why-cant-i",
             Regex::new(r"why can'?t I ([^\?]*)\??").unwrap(),
             vec![
                 "Do you think you should be able to %s?",
@@ -75,6 +80,7 @@ lazy_static! {
             ]
         ),
         (
+            "i-cant",
             Regex::new(r"i can'?t (.*)").unwrap(),
             vec![
                 "How do you know you can't %s?",
@@ -83,6 +89,7 @@ lazy_static! {
             ]
         ),
         (
+            "i-am",
             Regex::new(r"i am (.*)").unwrap(),
             vec![
                 "Did you come to me because you are %s?",
@@ -91,6 +98,7 @@ lazy_static! {
             ]
         ),
         (
+            "im",
             Regex::new(r"i'?m (.*)").unwrap(),
             vec![
                 "How does being %s make you feel?",
@@ -100,6 +108,7 @@ lazy_static! {
             ]
         ),
         (
+            "are-you",
             Regex::new(r"are you ([^\?]*)\??").unwrap(),
             vec![
                 "Why does it matter whether I am %s?",
@@ -109,6 +118,7 @@ lazy_static! {
             ]
         ),
         (
+            "what",
             Regex::new(r"what (.*)").unwrap(),
             vec![
                 "Why do you ask?",
@@ -117,6 +127,7 @@ lazy_static! {
             ]
         ),
         (
+            "how",
             Regex::new(r"how (.*)").unwrap(),
             vec![
                 "How do you suppose?",
@@ -125,6 +136,7 @@ lazy_static! {
             ]
         ),
         (
+            "because",
             Regex::new(r"because (.*)").unwrap(),
             vec![
                 "Is that the real reason?",
@@ -134,6 +146,7 @@ lazy_static! {
             ]
         ),
         (
+            "sorry",
             Regex::new(r"(.*) sorry (.*)").unwrap(),
             vec![
                 "There are many times when no apology is needed.",
@@ -141,6 +154,7 @@ lazy_static! {
             ]
         ),
         (
+            "hello",
             Regex::new(r"^hello(.*)").unwrap(),
             vec![
                 "Hello...I'm glad you could drop by today.",
@@ -149,6 +163,7 @@ lazy_static! {
             ]
         ),
         (
+            "hi",
             Regex::new(r"^hi(.*)").unwrap(),
             vec![
                 "Hello...I'm glad you could drop by today.",
@@ -157,14 +172,17 @@ lazy_static! {
             ]
         ),
         (
+            "thanks",
             Regex::new(r"^thanks(.*)").unwrap(),
             vec!["You're welcome!", "Anytime!"]
         ),
         (
+            "thank-you",
             Regex::new(r"^thank you(.*)").unwrap(),
             vec!["You're welcome!", "Anytime!"]
         ),
         (
+            "good-morning",
             Regex::new(r"^good morning(.*)").unwrap(),
             vec![
                 "Good morning...I'm glad you could drop by today.",
@@ -173,6 +191,7 @@ lazy_static! {
             ]
         ),
         (
+            "good-afternoon",
             Regex::new(r"^good afternoon(.*)").unwrap(),
             vec![
                 "Good afternoon...I'm glad you could drop by today.",
@@ -181,6 +200,7 @@ lazy_static! {
             ]
         ),
         (
+            "i-think",
             Regex::new(r"I think (.*)").unwrap(),
             vec![
                 "Do you doubt %s?",
@@ -189,6 +209,7 @@ lazy_static! {
             ]
         ),
         (
+            "friend",
             Regex::new(r"(.*) friend (.*)").unwrap(),
             vec![
                 "Tell me more about your friends.",
@@ -197,10 +218,12 @@ lazy_static! {
             ]
         ),
         (
+            "yes",
             Regex::new(r"yes").unwrap(),
             vec!["You seem quite sure.", "OK, but can you elaborate a bit?"]
         ),
         (
+            "computer",
             Regex::new(r"(.*) computer(.*)").unwrap(),
             vec![
                 "Are you really talking about me?",
@@ -210,6 +233,7 @@ lazy_static! {
             ]
         ),
         (
+            "is-it",
             Regex::new(r"is it (.*)").unwrap(),
             vec![
                 "Do you think it is %s?",
@@ -219,6 +243,7 @@ lazy_static! {
             ]
         ),
         (
+            "it-is",
             Regex::new(r"it is (.*)").unwrap(),
             vec![
                 "You seem very certain.",
@@ -226,6 +251,7 @@ lazy_static! {
             ]
         ),
         (
+            "can-you",
             Regex::new(r"can you ([^\?]*)\??").unwrap(),
             vec![
                 "What makes you think I can't %s?",
@@ -234,10 +260,12 @@ lazy_static! {
             ]
         ),
         (
+            "dream",
             Regex::new(r"(.*)dream(.*)").unwrap(),
             vec!["Tell me more about your dream."]
         ),
         (
+            "can-i",
             Regex::new(r"can I ([^\?]*)\??").unwrap(),
             vec![
                 "Perhaps you don't want to %s.",
@@ -246,6 +274,7 @@ lazy_static! {
             ]
         ),
         (
+            "you-are",
             Regex::new(r"you are (.*)").unwrap(),
             vec![
                 "Why do you think I am %s?",
@@ -255,6 +284,7 @@ lazy_static! {
             ]
         ),
         (
+            "youre",
             Regex::new(r"you'?re (.*)").unwrap(),
             vec![
                 "Why do you say I am %s?",
@@ -263,6 +293,7 @@ lazy_static! {
             ]
         ),
         (
+            "i-dont",
             Regex::new(r"i don'?t (.*)").unwrap(),
             vec![
                 "Don't you really %s?",
@@ -271,6 +302,7 @@ lazy_static! {
             ]
         ),
         (
+            "i-feel",
             Regex::new(r"i feel (.*)").unwrap(),
             vec![
                 "Good, tell me more about these feelings.",
@@ -281,6 +313,7 @@ lazy_static! {
             ]
         ),
         (
+            "i-have",
             Regex::new(r"i have (.*)").unwrap(),
             vec![
                 "Why do you tell me that you've %s?",
@@ -289,6 +322,7 @@ lazy_static! {
             ]
         ),
         (
+            "i-would",
             Regex::new(r"i would (.*)").unwrap(),
             vec![
                 "Could you explain why you would %s?",
@@ -297,6 +331,7 @@ lazy_static! {
             ]
         ),
         (
+            "is-there",
             Regex::new(r"is there (.*)").unwrap(),
             vec![
                 "Do you think there is %s?",
@@ -305,6 +340,7 @@ lazy_static! {
             ]
         ),
         (
+            "my",
             Regex::new(r"my (.*)").unwrap(),
             vec![
                 "I see, your %s.",
@@ -313,6 +349,7 @@ lazy_static! {
             ]
         ),
         (
+            "you",
             Regex::new(r"you (.*)").unwrap(),
             vec![
                 "We should be discussing you, not me.",
@@ -321,6 +358,7 @@ lazy_static! {
             ]
         ),
         (
+            "why",
             Regex::new(r"why (.*)").unwrap(),
             vec![
                 "Why don't you tell me the reason why %s?",
@@ -328,6 +366,7 @@ lazy_static! {
             ]
         ),
         (
+            "i-want",
             Regex::new(r"i want (.*)").unwrap(),
             vec![
                 "What would it mean to you if you got %s?",
@@ -337,6 +376,7 @@ lazy_static! {
             ]
         ),
         (
+            "mother",
             Regex::new(r"(.*) mother(.*)").unwrap(),
             vec![
                 "Tell me more about your mother.",
@@ -347,6 +387,7 @@ lazy_static! {
             ]
         ),
         (
+            "father",
             Regex::new(r"(.*) father(.*)").unwrap(),
             vec![
                 "Tell me more about your father.",
@@ -357,6 +398,7 @@ lazy_static! {
             ]
         ),
         (
+            "child",
             Regex::new(r"(.*) child(.*)").unwrap(),
             vec![
                 "Did you have close friends as a child?",
@@ -367,6 +409,7 @@ lazy_static! {
             ]
         ),
         (
+            "question",
             Regex::new(r"(.*)\?").unwrap(),
             vec![
                 "Why do you ask that?",
@@ -1,6 +1,11 @@
+pub mod backend;
 pub mod globals;
+#[allow(clippy::module_inception)]
 pub mod talk;
+pub mod transcript;
 pub mod types;
 
-pub use talk::{get_intro_responses, reply};
+pub use backend::{ConversationBackend, ConversationSession, Eliza};
+pub use talk::{Answer, SessionRng, answer, get_intro_responses, reply};
+pub use transcript::{Transcript, TranscriptFormat, TranscriptRecorder};
 pub use types::Talk;
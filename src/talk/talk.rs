@@ -1,18 +1,35 @@
-use rand::prelude::IndexedRandom;
+use rand::{Rng, prelude::IndexedRandom};
+use rand_chacha::ChaCha12Rng;
 
 use crate::talk::globals::{
     DEFAULT_RESPONSES, FACTS, GOODBYE_INPUTS, GOODBYE_RESPONSES, INTRO_RESPONSES, REFLECTED_WORDS,
     REGEX_RESPONSES,
 };
 
+// RNG behind seeded Talk sessions. The algorithm is fixed so a recorded seed
+// replays the same answers after a rand upgrade, which `StdRng` does not
+// promise.
+pub type SessionRng = ChaCha12Rng;
+
+pub const GOODBYE_RULE: &str = "goodbye";
+pub const DEFAULT_RULE: &str = "default";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Answer {
+    pub text: String,
+    pub ended: bool,
+    pub rule: String,
+    pub fragment: Option<String>,
+}
+
 pub fn preprocess(input: &str) -> String {
     input
         .trim_matches(|c: char| c.is_ascii_punctuation() || c.is_whitespace())
         .to_lowercase()
 }
 
-fn random_element_from(list: &[&'static str]) -> &'static str {
-    list.choose(&mut rand::rng()).unwrap_or(&"")
+fn random_element_from<R: Rng + ?Sized>(list: &[&'static str], rng: &mut R) -> &'static str {
+    list.choose(rng).unwrap_or(&"")
 }
 
 fn reflect(fragment: &str) -> String {
@@ -23,39 +40,67 @@ fn reflect(fragment: &str) -> String {
         .join(" ")
 }
 
-fn lookup_response(input: &str) -> String {
-    for (re, responses) in REGEX_RESPONSES.iter() {
+fn lookup_response<R: Rng + ?Sized>(input: &str, rng: &mut R) -> Answer {
+    for (id, re, responses) in REGEX_RESPONSES.iter() {
         if let Some(captures) = re.captures(input) {
-            let response = random_element_from(responses);
+            let response = random_element_from(responses, rng);
+            let fragment = captures.get(1).map(|m| m.as_str().to_string());
             if response.contains("%s") {
-                if let Some(fragment) = captures.get(1) {
-                    let reflected = reflect(fragment.as_str());
-                    return response.replace("%s", &reflected);
+                if let Some(fragment) = fragment {
+                    let reflected = reflect(&fragment);
+                    return Answer {
+                        text: response.replace("%s", &reflected),
+                        ended: false,
+                        rule: id.to_string(),
+                        fragment: Some(fragment),
+                    };
                 }
             } else {
-                return response.to_string();
+                return Answer {
+                    text: response.to_string(),
+                    ended: false,
+                    rule: id.to_string(),
+                    fragment,
+                };
             }
         }
     }
 
-    random_element_from(&DEFAULT_RESPONSES).to_string()
+    Answer {
+        text: random_element_from(&DEFAULT_RESPONSES, rng).to_string(),
+        ended: false,
+        rule: DEFAULT_RULE.to_string(),
+        fragment: None,
+    }
 }
 
-pub fn reply(input: &str) -> (String, bool) {
+// Same as `reply`, but draws from the given RNG and reports which rule
+// produced the answer, so a seeded session can be reproduced exactly.
+pub fn answer<R: Rng + ?Sized>(input: &str, rng: &mut R) -> Answer {
     let input = preprocess(input);
     if GOODBYE_INPUTS.contains(input.as_str()) {
-        (random_element_from(&GOODBYE_RESPONSES).to_string(), true)
+        Answer {
+            text: random_element_from(&GOODBYE_RESPONSES, rng).to_string(),
+            ended: true,
+            rule: GOODBYE_RULE.to_string(),
+            fragment: None,
+        }
     } else {
-        (lookup_response(&input), false)
+        lookup_response(&input, rng)
     }
 }
 
+pub fn reply(input: &str) -> (String, bool) {
+    let answer = answer(input, &mut rand::rng());
+    (answer.text, answer.ended)
+}
+
 pub fn get_intro_responses(name: &str) -> Vec<String> {
     let mut intros: Vec<String> = INTRO_RESPONSES
         .iter()
         .map(|r| r.replace("{}", name))
         .collect();
-    intros.push(random_element_from(&FACTS).to_string());
+    intros.push(random_element_from(&FACTS, &mut rand::rng()).to_string());
     intros.push("How are you feeling today?".to_string());
     intros
 }
@@ -0,0 +1,360 @@
+use std::{
+    collections::HashMap,
+    fmt, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use prost::Message;
+use prost_types::Timestamp;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::sdk::io::cloudevents::v1::{
+    CloudEvent, CloudEventBatch,
+    cloud_event::{CloudEventAttributeValue, cloud_event_attribute_value::Attr},
+};
+use crate::talk::talk::{Answer, SessionRng, answer};
+use crate::utils::{EventKind, SUBJECT_ATTRIBUTE, TIME_ATTRIBUTE};
+
+pub const TRANSCRIPT_DIR_ENV: &str = "TALK_TRANSCRIPT_DIR";
+pub const TRANSCRIPT_FORMAT_ENV: &str = "TALK_TRANSCRIPT_FORMAT";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TranscriptFormat {
+    #[default]
+    JsonLines,
+    CloudEventBatch,
+}
+
+impl TranscriptFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TranscriptFormat::JsonLines => "jsonl",
+            TranscriptFormat::CloudEventBatch => "pb",
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("pb") | Some("bin") => TranscriptFormat::CloudEventBatch,
+            _ => TranscriptFormat::JsonLines,
+        }
+    }
+}
+
+impl FromStr for TranscriptFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "jsonl" | "json-lines" | "jsonlines" => Ok(TranscriptFormat::JsonLines),
+            "cloudevents" | "cloudeventbatch" | "batch" | "pb" => {
+                Ok(TranscriptFormat::CloudEventBatch)
+            }
+            other => Err(format!("unknown transcript format: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TranscriptEntry {
+    pub session_id: String,
+    pub seed: u64,
+    pub seq: u64,
+    #[serde(with = "rfc3339")]
+    pub timestamp: Timestamp,
+    pub input: String,
+    pub rule: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fragment: Option<String>,
+    pub answer: String,
+    pub ended: bool,
+}
+
+// The turns of a single Talk session. Its answers are drawn from an RNG
+// seeded with `seed`, so the conversation can be replayed later.
+pub struct Transcript {
+    session_id: String,
+    seed: u64,
+    entries: Vec<TranscriptEntry>,
+}
+
+impl Transcript {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            session_id: Uuid::new_v4().to_string(),
+            seed,
+            entries: Vec::new(),
+        }
+    }
+
+    // Records under the id the session's Talk events use as their subject.
+    pub fn with_session_id(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = session_id.into();
+        self
+    }
+
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn entries(&self) -> &[TranscriptEntry] {
+        &self.entries
+    }
+
+    // Records a turn answered by a `ConversationBackend` session started
+    // with the same seed, at `time` on the service clock.
+    pub fn record(&mut self, input: &str, answer: &Answer, time: Timestamp) {
+        self.entries.push(TranscriptEntry {
+            session_id: self.session_id.clone(),
+            seed: self.seed,
+            seq: self.entries.len() as u64 + 1,
+            timestamp: time,
+            input: input.to_string(),
+            rule: answer.rule.clone(),
+            fragment: answer.fragment.clone(),
+            answer: answer.text.clone(),
+            ended: answer.ended,
+        });
+    }
+
+    pub fn encode(&self, format: TranscriptFormat) -> io::Result<Vec<u8>> {
+        encode_entries(&self.entries, format)
+    }
+}
+
+pub fn encode_entries(
+    entries: &[TranscriptEntry],
+    format: TranscriptFormat,
+) -> io::Result<Vec<u8>> {
+    match format {
+        TranscriptFormat::JsonLines => {
+            let mut out = Vec::new();
+            for entry in entries {
+                serde_json::to_writer(&mut out, entry)?;
+                out.push(b'\n');
+            }
+            Ok(out)
+        }
+        TranscriptFormat::CloudEventBatch => {
+            let batch = CloudEventBatch {
+                events: entries.iter().map(entry_to_cloud_event).collect(),
+            };
+            Ok(batch.encode_to_vec())
+        }
+    }
+}
+
+pub fn decode_entries(bytes: &[u8], format: TranscriptFormat) -> io::Result<Vec<TranscriptEntry>> {
+    match format {
+        TranscriptFormat::JsonLines => std::str::from_utf8(bytes)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).map_err(io::Error::from))
+            .collect(),
+        TranscriptFormat::CloudEventBatch => CloudEventBatch::decode(bytes)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+            .events
+            .iter()
+            .map(cloud_event_to_entry)
+            .collect(),
+    }
+}
+
+pub fn load(path: &Path) -> io::Result<Vec<TranscriptEntry>> {
+    let bytes = std::fs::read(path)?;
+    decode_entries(&bytes, TranscriptFormat::from_path(path))
+}
+
+fn entry_to_cloud_event(entry: &TranscriptEntry) -> CloudEvent {
+    let mut attributes = HashMap::new();
+    let mut put = |key: &str, attr: Attr| {
+        attributes.insert(
+            key.to_string(),
+            CloudEventAttributeValue { attr: Some(attr) },
+        );
+    };
+
+    put(TIME_ATTRIBUTE, Attr::CeTimestamp(entry.timestamp));
+    put(SUBJECT_ATTRIBUTE, Attr::CeString(entry.session_id.clone()));
+    put("sessionid", Attr::CeString(entry.session_id.clone()));
+    put("seed", Attr::CeString(entry.seed.to_string()));
+    put("seq", Attr::CeString(entry.seq.to_string()));
+    put("input", Attr::CeString(entry.input.clone()));
+    put("rule", Attr::CeString(entry.rule.clone()));
+    if let Some(fragment) = &entry.fragment {
+        put("fragment", Attr::CeString(fragment.clone()));
+    }
+    put("answer", Attr::CeString(entry.answer.clone()));
+    put("ended", Attr::CeBoolean(entry.ended));
+
+    CloudEvent {
+        id: Uuid::new_v4().to_string(),
+        source: EventKind::TALK_TURN.source.to_string(),
+        spec_version: "1.0".to_string(),
+        r#type: EventKind::TALK_TURN.r#type.to_string(),
+        attributes,
+        data: None,
+    }
+}
+
+fn cloud_event_to_entry(event: &CloudEvent) -> io::Result<TranscriptEntry> {
+    let attr = |key: &str| event.attributes.get(key).and_then(|v| v.attr.as_ref());
+    let string = |key: &str| match attr(key) {
+        Some(Attr::CeString(s)) => Some(s.clone()),
+        _ => None,
+    };
+    let missing = |key: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("missing attribute: {}", key),
+        )
+    };
+    let number = |key: &str| -> io::Result<u64> {
+        string(key)
+            .ok_or_else(|| missing(key))?
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    };
+
+    Ok(TranscriptEntry {
+        session_id: string("sessionid").ok_or_else(|| missing("sessionid"))?,
+        seed: number("seed")?,
+        seq: number("seq")?,
+        timestamp: match attr(TIME_ATTRIBUTE) {
+            Some(Attr::CeTimestamp(ts)) => *ts,
+            _ => return Err(missing(TIME_ATTRIBUTE)),
+        },
+        input: string("input").ok_or_else(|| missing("input"))?,
+        rule: string("rule").ok_or_else(|| missing("rule"))?,
+        fragment: string("fragment"),
+        answer: string("answer").ok_or_else(|| missing("answer"))?,
+        ended: matches!(attr("ended"), Some(Attr::CeBoolean(true))),
+    })
+}
+
+// JSON Lines keep timestamps in RFC 3339, as the CloudEvents `time` attribute.
+mod rfc3339 {
+    use prost_types::Timestamp;
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub fn serialize<S: Serializer>(
+        timestamp: &Timestamp,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(timestamp)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Timestamp, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TranscriptRecorder {
+    dir: PathBuf,
+    format: TranscriptFormat,
+}
+
+impl TranscriptRecorder {
+    pub fn new(dir: impl Into<PathBuf>, format: TranscriptFormat) -> Self {
+        Self {
+            dir: dir.into(),
+            format,
+        }
+    }
+
+    // Recording is enabled by pointing TALK_TRANSCRIPT_DIR at a directory.
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let Ok(dir) = std::env::var(TRANSCRIPT_DIR_ENV) else {
+            return Ok(None);
+        };
+        let format = match std::env::var(TRANSCRIPT_FORMAT_ENV) {
+            Ok(format) => format.parse()?,
+            Err(_) => TranscriptFormat::default(),
+        };
+        Ok(Some(Self::new(dir, format)))
+    }
+
+    pub async fn save(&self, transcript: &Transcript) -> io::Result<PathBuf> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let path = self.dir.join(format!(
+            "{}.{}",
+            transcript.session_id(),
+            self.format.extension()
+        ));
+        tokio::fs::write(&path, transcript.encode(self.format)?).await?;
+        Ok(path)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    pub seq: u64,
+    pub input: String,
+    pub expected_rule: String,
+    pub actual_rule: String,
+    pub expected_answer: String,
+    pub actual_answer: String,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "#{} {:?}: expected [{}] {:?}, got [{}] {:?}",
+            self.seq,
+            self.input,
+            self.expected_rule,
+            self.expected_answer,
+            self.actual_rule,
+            self.actual_answer
+        )
+    }
+}
+
+// Pushes the recorded inputs back through the reply rules and reports every
+// turn whose rule or answer no longer matches. Entries may span several
+// sessions; each session is replayed in `seq` order from its own seed.
+pub fn replay(entries: &[TranscriptEntry]) -> Vec<Divergence> {
+    let mut entries = entries.to_vec();
+    entries.sort_by(|a, b| (&a.session_id, a.seed, a.seq).cmp(&(&b.session_id, b.seed, b.seq)));
+
+    let mut session: Option<(String, u64, SessionRng)> = None;
+    entries
+        .into_iter()
+        .filter_map(|entry| {
+            let rng = match &mut session {
+                Some((id, seed, rng)) if *id == entry.session_id && *seed == entry.seed => rng,
+                _ => {
+                    let rng = SessionRng::seed_from_u64(entry.seed);
+                    &mut session
+                        .insert((entry.session_id.clone(), entry.seed, rng))
+                        .2
+                }
+            };
+            let actual = answer(&entry.input, rng);
+            if actual.rule == entry.rule && actual.text == entry.answer {
+                None
+            } else {
+                Some(Divergence {
+                    seq: entry.seq,
+                    input: entry.input,
+                    expected_rule: entry.rule,
+                    actual_rule: actual.rule,
+                    expected_answer: entry.answer,
+                    actual_answer: actual.text,
+                })
+            }
+        })
+        .collect()
+}
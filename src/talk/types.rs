@@ -1,5 +1,7 @@
+pub type ReplyFn = Box<dyn Fn(&str) -> (String, bool) + Send + Sync>;
+
 pub struct Talk {
-    pub reply_fn: Box<dyn Fn(&str) -> (String, bool) + Send + Sync>,
+    pub reply_fn: ReplyFn,
 }
 
 impl Talk {
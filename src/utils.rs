@@ -1,115 +1,232 @@
-use crate::sdk::basic::service::v1::{BackgroundResponse, BackgroundResponseEvent, State};
-use crate::sdk::io::cloudevents::v1::{CloudEvent, cloud_event::Data::ProtoData};
-use prost::Message;
+use crate::greeting::{Greeting, LOCALE_ATTRIBUTE};
+use crate::random::RandomSource;
+use crate::sdk::basic::service::v1::{
+    BackgroundResponse, BackgroundResponseEvent, HelloResponseEvent, TalkResponse,
+    TalkResponseEvent,
+};
+use crate::sdk::io::cloudevents::v1::{
+    CloudEvent,
+    cloud_event::{CloudEventAttributeValue, Data::ProtoData, cloud_event_attribute_value::Attr},
+};
+use crate::talk::Answer;
+use prost::{DecodeError, Message, Name};
 use prost_types::{Any, Timestamp};
-use rand::Rng;
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::{collections::HashMap, fmt};
 use uuid::Uuid;
 
-#[derive(Default, Clone)]
-pub struct StateManager {
-    state: Arc<Mutex<HashMap<String, State>>>,
-    start: Arc<Mutex<HashMap<String, Timestamp>>>,
-    complete: Arc<Mutex<HashMap<String, Timestamp>>>,
-    errors: Arc<Mutex<HashMap<String, Vec<String>>>>, // Replace String with actual error type if needed
+pub use crate::state::{JobState, StateManager};
+
+pub const PROTOCOLS: [&str; 4] = ["rest", "rpc", "grpc", "ws"];
+
+pub fn random_protocol(rng: &RandomSource) -> String {
+    rng.choose(&PROTOCOLS).unwrap_or(&"grpc").to_string()
 }
 
-impl StateManager {
-    pub fn new() -> Self {
-        Self::default()
-    }
+// `type` and `source` of every event the service emits. Types are
+// reverse-DNS names under `io.basic`, one segment per RPC plus one for
+// secondary events (`io.basic.subscribe.lagged`); sources are `/basic/<rpc>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventKind {
+    pub r#type: &'static str,
+    pub source: &'static str,
+}
 
-    pub fn start(&self, hash: &str, state: State) {
-        let mut state_map = self.state.lock().unwrap();
-        let mut start_map = self.start.lock().unwrap();
+impl EventKind {
+    pub const HELLO: Self = Self {
+        r#type: "io.basic.hello",
+        source: "/basic/hello",
+    };
+    pub const TALK: Self = Self {
+        r#type: "io.basic.talk",
+        source: "/basic/talk",
+    };
+    pub const TALK_TURN: Self = Self {
+        r#type: "io.basic.talk.turn",
+        source: "/basic/talk",
+    };
+    pub const BACKGROUND: Self = Self {
+        r#type: "io.basic.background",
+        source: "/basic/background",
+    };
+    pub const SUBSCRIBE_LAGGED: Self = Self {
+        r#type: "io.basic.subscribe.lagged",
+        source: "/basic/subscribe",
+    };
+}
 
-        state_map.insert(hash.to_string(), state);
-        start_map.insert(hash.to_string(), Self::current_timestamp());
-    }
+// Optional CloudEvents context attributes set on every emitted event.
+pub const TIME_ATTRIBUTE: &str = "time";
+pub const SUBJECT_ATTRIBUTE: &str = "subject";
+pub const DATACONTENTTYPE_ATTRIBUTE: &str = "datacontenttype";
+pub const DATASCHEMA_ATTRIBUTE: &str = "dataschema";
 
-    pub fn finish(&self, hash: &str, state: State) {
-        let mut state_map = self.state.lock().unwrap();
-        let mut complete_map = self.complete.lock().unwrap();
+pub const PROTOBUF_CONTENT_TYPE: &str = "application/protobuf";
 
-        state_map.insert(hash.to_string(), state);
-        complete_map.insert(hash.to_string(), Self::current_timestamp());
-    }
+// `dataschema` values are `urn:proto:` followed by the full message name.
+// The service publishes no schema documents, so the URI names the message
+// rather than pointing at something to fetch.
+const DATASCHEMA_PREFIX: &str = "urn:proto:";
 
-    pub fn get_state(
-        &self,
-        hash: &str,
-    ) -> (
-        Option<State>,
-        Option<Timestamp>,
-        Option<Timestamp>,
-        Option<Vec<String>>,
-    ) {
-        let state = self.state.lock().unwrap().get(hash).cloned();
-        let start = self.start.lock().unwrap().get(hash).cloned();
-        let complete = self.complete.lock().unwrap().get(hash).cloned();
-        let errors = self.errors.lock().unwrap().get(hash).cloned();
-        (state, start, complete, errors)
-    }
+// `dataschema` for a payload with the given `Any` type URL. Only the part
+// after the last `/` names the message.
+pub fn dataschema(type_url: &str) -> String {
+    let full_name = type_url.rsplit('/').next().unwrap_or_default();
+    format!("{}{}", DATASCHEMA_PREFIX, full_name)
+}
 
-    pub fn set_error(&self, hash: &str, err: Option<String>) {
-        if let Some(error) = err {
-            let mut errors_map = self.errors.lock().unwrap();
-            errors_map.entry(hash.to_string()).or_default().push(error);
-        }
+// Event carrying `message` as proto data, with the type URL and the
+// `dataschema` derived from its `prost::Name` impl.
+// `subject` names what the event is about: the greeted name, the job id or
+// the Talk session id.
+pub fn proto_event<T: Message + Name>(
+    kind: EventKind,
+    subject: &str,
+    time: Timestamp,
+    message: &T,
+    attributes: &HashMap<String, CloudEventAttributeValue>,
+) -> CloudEvent {
+    let type_url = T::type_url();
+
+    let mut attributes = attributes.clone();
+    let mut put = |key: &str, attr: Attr| {
+        attributes.insert(
+            key.to_string(),
+            CloudEventAttributeValue { attr: Some(attr) },
+        );
+    };
+    put(TIME_ATTRIBUTE, Attr::CeTimestamp(time));
+    put(SUBJECT_ATTRIBUTE, Attr::CeString(subject.to_string()));
+    put(
+        DATACONTENTTYPE_ATTRIBUTE,
+        Attr::CeString(PROTOBUF_CONTENT_TYPE.to_string()),
+    );
+    put(
+        DATASCHEMA_ATTRIBUTE,
+        Attr::CeUri(format!("{}{}", DATASCHEMA_PREFIX, T::full_name())),
+    );
+
+    CloudEvent {
+        id: Uuid::new_v4().to_string(),
+        source: kind.source.to_string(),
+        spec_version: "1.0".to_string(),
+        r#type: kind.r#type.to_string(),
+        attributes,
+        data: Some(ProtoData(Any {
+            type_url,
+            value: message.encode_to_vec(),
+        })),
     }
+}
+
+// Hello event for a greeting; the greeting's locale is added to the
+// attributes and the greeted name is the subject.
+pub fn create_hello_event(
+    greeting: Greeting,
+    time: Timestamp,
+    attributes: &HashMap<String, CloudEventAttributeValue>,
+) -> CloudEvent {
+    let event = HelloResponseEvent {
+        greeting: greeting.text,
+    };
 
-    pub fn has_errors(&self, hash: &str) -> bool {
-        self.errors
-            .lock()
-            .unwrap()
-            .get(hash)
-            .map_or(false, |errs| !errs.is_empty())
+    let mut attributes = attributes.clone();
+    attributes.insert(
+        LOCALE_ATTRIBUTE.to_string(),
+        CloudEventAttributeValue {
+            attr: Some(Attr::CeString(greeting.locale)),
+        },
+    );
+
+    proto_event(EventKind::HELLO, &greeting.name, time, &event, &attributes)
+}
+
+pub fn create_background_response(
+    job: &str,
+    event: &BackgroundResponseEvent,
+    time: Timestamp,
+    attributes: &HashMap<String, CloudEventAttributeValue>,
+) -> BackgroundResponse {
+    let cloudevent = proto_event(EventKind::BACKGROUND, job, time, event, attributes);
+
+    BackgroundResponse {
+        cloud_event: Some(cloudevent),
     }
+}
+
+pub fn create_talk_response(
+    session_id: &str,
+    sequence: u64,
+    answer: &Answer,
+    time: Timestamp,
+    attributes: &HashMap<String, CloudEventAttributeValue>,
+) -> TalkResponse {
+    let event = TalkResponseEvent {
+        answer: answer.text.clone(),
+        sequence,
+        rule: answer.rule.clone(),
+        fragment: answer.fragment.clone().unwrap_or_default(),
+        ended: answer.ended,
+    };
 
-    pub fn get_errors(&self, hash: &str) -> Vec<String> {
-        self.errors
-            .lock()
-            .unwrap()
-            .get(hash)
-            .cloned()
-            .unwrap_or_default()
+    let cloudevent = proto_event(EventKind::TALK, session_id, time, &event, attributes);
+
+    TalkResponse {
+        answer: event.answer,
+        sequence: event.sequence,
+        rule: event.rule,
+        fragment: event.fragment,
+        ended: event.ended,
+        cloud_event: Some(cloudevent),
     }
+}
 
-    fn current_timestamp() -> Timestamp {
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap();
-        Timestamp {
-            seconds: now.as_secs() as i64,
-            nanos: now.subsec_nanos() as i32,
+#[derive(Debug)]
+pub enum EventDecodeError {
+    // The response carried no CloudEvent at all.
+    MissingEvent,
+    MissingData,
+    TypeMismatch { expected: String, found: String },
+    Invalid(DecodeError),
+}
+
+impl fmt::Display for EventDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EventDecodeError::MissingEvent => write!(f, "missing cloud_event"),
+            EventDecodeError::MissingData => write!(f, "cloud event carries no proto data"),
+            EventDecodeError::TypeMismatch { expected, found } => {
+                write!(f, "expected a {} payload, got {}", expected, found)
+            }
+            EventDecodeError::Invalid(e) => write!(f, "{}", e),
         }
     }
 }
 
-pub fn random_protocol() -> String {
-    let protocols = vec!["rest", "rpc", "grpc", "ws"];
-    let mut rng = rand::rng();
-    protocols[rng.random_range(0..protocols.len())].to_string()
+impl std::error::Error for EventDecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EventDecodeError::Invalid(e) => Some(e),
+            _ => None,
+        }
+    }
 }
 
-pub fn create_background_response(event: &BackgroundResponseEvent) -> BackgroundResponse {
-    let any = Any {
-        type_url: "type.googleapis.com/basic.service.v1.BackgroundResponseEvent".to_string(),
-        value: Message::encode_to_vec(event),
+// Decodes the proto payload of an event, refusing an `Any` that holds a
+// different message: prost would happily decode it into `T` anyway.
+pub fn decode_cloud_event<T: Message + Name + Default>(
+    cloudevent: &CloudEvent,
+) -> Result<T, EventDecodeError> {
+    let Some(ProtoData(any)) = &cloudevent.data else {
+        return Err(EventDecodeError::MissingData);
     };
-
-    let cloudevent = CloudEvent {
-        id: Uuid::new_v4().to_string(),
-        source: "basic.v1/Background".to_string(),
-        spec_version: "1.0".to_string(),
-        // You might want this to be the *Event* type, not *Response*
-        r#type: "type.googleapis.com/basic.service.v1.BackgroundResponse".to_string(),
-        attributes: Default::default(),
-        data: Some(ProtoData(any)),
-    };
-
-    BackgroundResponse {
-        cloud_event: Some(cloudevent),
+    // Only the part after the last `/` names the type (the Any spec leaves
+    // the domain to the sender).
+    let name = any.type_url.rsplit('/').next().unwrap_or_default();
+    if name != T::full_name() {
+        return Err(EventDecodeError::TypeMismatch {
+            expected: T::full_name(),
+            found: any.type_url.clone(),
+        });
     }
+    T::decode(any.value.as_slice()).map_err(EventDecodeError::Invalid)
 }
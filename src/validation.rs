@@ -0,0 +1,218 @@
+use tonic::{
+    Status,
+    codec::{BufferSettings, Codec, DecodeBuf, Decoder},
+};
+use tonic_prost::{ProstCodec, ProstDecoder, ProstEncoder};
+use tonic_types::FieldViolation;
+
+use crate::{
+    error::ServiceError,
+    sdk::{
+        basic::service::v1::{
+            BackgroundRequest, BackgroundResponse, HelloBatchRequest, HelloRequest, HelloResponse,
+            SubscribeRequest, SubscribeResponse, TalkRequest, TalkResponse,
+        },
+        io::cloudevents::v1::CloudEventBatch,
+    },
+};
+
+// Most names one HelloBatch request may carry; larger inputs are split across
+// HelloBatchStream requests.
+pub const MAX_BATCH_NAMES: usize = 1000;
+
+// Most processes one Background request may start; each one is a task on the
+// server.
+pub const MAX_PROCESSES: i64 = 100;
+
+// A check one field of a message must pass.
+pub struct Rule<T> {
+    pub field: &'static str,
+    pub description: &'static str,
+    pub check: fn(&T) -> bool,
+}
+
+// Messages decoded by `ValidatingCodec`. Every failed rule becomes one
+// `BadRequest` field violation.
+pub trait Validate: Sized + 'static {
+    const RULES: &'static [Rule<Self>] = &[];
+
+    fn validate(&self) -> Result<(), ServiceError> {
+        let violations: Vec<_> = Self::RULES
+            .iter()
+            .filter(|rule| !(rule.check)(self))
+            .map(|rule| FieldViolation::new(rule.field, rule.description))
+            .collect();
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(ServiceError::InvalidArgument(violations))
+        }
+    }
+}
+
+// The rules for every request message of `service.proto`; keep the field
+// comments there in sync.
+impl Validate for HelloRequest {
+    const RULES: &'static [Rule<Self>] = &[
+        Rule {
+            field: "message",
+            description: "must not be blank",
+            check: |request| !request.message.trim().is_empty() || request.name.is_some(),
+        },
+        Rule {
+            field: "name",
+            description: "must not be blank when set",
+            check: |request| {
+                request
+                    .name
+                    .as_ref()
+                    .is_none_or(|name| !name.trim().is_empty())
+            },
+        },
+        Rule {
+            field: "locale",
+            description: "must be a language tag such as en or de-CH",
+            check: |request| request.locale.as_deref().is_none_or(is_language_tag),
+        },
+    ];
+}
+
+impl Validate for HelloBatchRequest {
+    const RULES: &'static [Rule<Self>] = &[
+        Rule {
+            field: "names",
+            description: "must have between 1 and 1000 entries",
+            check: |request| (1..=MAX_BATCH_NAMES).contains(&request.names.len()),
+        },
+        Rule {
+            field: "names",
+            description: "must not contain blank entries",
+            check: |request| request.names.iter().all(|name| !name.trim().is_empty()),
+        },
+        Rule {
+            field: "locale",
+            description: "must be a language tag such as en or de-CH",
+            check: |request| request.locale.as_deref().is_none_or(is_language_tag),
+        },
+    ];
+}
+
+impl Validate for TalkRequest {
+    const RULES: &'static [Rule<Self>] = &[Rule {
+        field: "message",
+        description: "must not be blank",
+        check: |request| !request.message.trim().is_empty(),
+    }];
+}
+
+impl Validate for BackgroundRequest {
+    const RULES: &'static [Rule<Self>] = &[
+        Rule {
+            field: "processes",
+            description: "must not be negative",
+            check: |request| request.processes >= 0,
+        },
+        Rule {
+            field: "processes",
+            description: "must be at most 100",
+            check: |request| request.processes <= MAX_PROCESSES,
+        },
+    ];
+}
+
+impl Validate for SubscribeRequest {
+    const RULES: &'static [Rule<Self>] = &[
+        Rule {
+            field: "types",
+            description: "must not contain empty entries",
+            check: |request| request.types.iter().all(|t| !t.is_empty()),
+        },
+        Rule {
+            field: "sources",
+            description: "must not contain empty entries",
+            check: |request| request.sources.iter().all(|s| !s.is_empty()),
+        },
+        Rule {
+            field: "attributes",
+            description: "must not contain empty keys",
+            check: |request| request.attributes.keys().all(|k| !k.is_empty()),
+        },
+    ];
+}
+
+// Letters, digits and hyphens in subtags of at most 8 characters, starting
+// with a letter.
+fn is_language_tag(tag: &str) -> bool {
+    tag.len() <= 35
+        && tag.starts_with(|c: char| c.is_ascii_alphabetic())
+        && tag.split('-').all(|subtag| {
+            (1..=8).contains(&subtag.len()) && subtag.chars().all(|c| c.is_ascii_alphanumeric())
+        })
+}
+
+// Responses are decoded by the generated client and are taken as they come.
+impl Validate for HelloResponse {}
+impl Validate for TalkResponse {}
+impl Validate for BackgroundResponse {}
+impl Validate for SubscribeResponse {}
+impl Validate for CloudEventBatch {}
+
+// `ProstCodec` that validates every decoded message, so invalid requests are
+// rejected before the handler runs and invalid Talk messages end the stream.
+// `build.rs` points the generated service code at it.
+#[derive(Debug, Clone)]
+pub struct ValidatingCodec<T, U> {
+    inner: ProstCodec<T, U>,
+}
+
+impl<T, U> Default for ValidatingCodec<T, U> {
+    fn default() -> Self {
+        Self {
+            inner: ProstCodec::default(),
+        }
+    }
+}
+
+impl<T, U> Codec for ValidatingCodec<T, U>
+where
+    T: prost::Message + Send + 'static,
+    U: prost::Message + Default + Validate + Send + 'static,
+{
+    type Encode = T;
+    type Decode = U;
+
+    type Encoder = ProstEncoder<T>;
+    type Decoder = ValidatingDecoder<U>;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        self.inner.encoder()
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        ValidatingDecoder {
+            inner: self.inner.decoder(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ValidatingDecoder<U> {
+    inner: ProstDecoder<U>,
+}
+
+impl<U: prost::Message + Default + Validate> Decoder for ValidatingDecoder<U> {
+    type Item = U;
+    type Error = Status;
+
+    fn decode(&mut self, buf: &mut DecodeBuf<'_>) -> Result<Option<U>, Status> {
+        let message = self.inner.decode(buf)?;
+        if let Some(message) = &message {
+            message.validate()?;
+        }
+        Ok(message)
+    }
+
+    fn buffer_settings(&self) -> BufferSettings {
+        self.inner.buffer_settings()
+    }
+}
@@ -0,0 +1,232 @@
+use std::{
+    collections::HashSet,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use http::{HeaderName, HeaderValue, Method, header};
+use prost::Message;
+use prost_types::FileDescriptorSet;
+use tonic::{Status, body::Body};
+use tonic_web::GrpcWebLayer;
+use tower::{
+    Layer, Service,
+    layer::util::{Identity, Stack},
+};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::{FILE_DESCRIPTOR_SET, connect, idempotency::IDEMPOTENCY_KEY_HEADER, rate_limit};
+
+pub const GRPC_WEB_ENV: &str = "GRPC_WEB";
+pub const GRPC_WEB_ORIGINS_ENV: &str = "GRPC_WEB_ALLOWED_ORIGINS";
+pub const GRPC_WEB_MAX_AGE_ENV: &str = "GRPC_WEB_MAX_AGE_SECS";
+
+// CORS, the client-streaming guard and the gRPC-Web translation, outermost
+// first.
+pub type WebLayer = Stack<GrpcWebLayer, Stack<StreamingGuardLayer, Stack<CorsLayer, Identity>>>;
+
+const REQUEST_HEADERS: [&str; 9] = [
+    "content-type",
+    "x-grpc-web",
+    "x-user-agent",
+    "grpc-timeout",
+    "authorization",
+    IDEMPOTENCY_KEY_HEADER,
+    rate_limit::IDENTITY_METADATA_KEY,
+    connect::PROTOCOL_VERSION_HEADER,
+    connect::TIMEOUT_HEADER,
+];
+
+const RESPONSE_HEADERS: [&str; 3] = ["grpc-status", "grpc-message", "grpc-status-details-bin"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AllowedOrigins {
+    Any,
+    // Exact origins such as `https://dashboard.example.com`. Empty means
+    // same-origin only.
+    List(Vec<String>),
+}
+
+// Cross-origin access for browser clients speaking gRPC-Web.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorsConfig {
+    pub allowed_origins: AllowedOrigins,
+    // How long browsers may cache a preflight response.
+    pub max_age: Duration,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: AllowedOrigins::List(Vec::new()),
+            max_age: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+impl CorsConfig {
+    // gRPC-Web, and with it HTTP/1.1, is off unless GRPC_WEB=true.
+    // GRPC_WEB_ALLOWED_ORIGINS takes a comma-separated list of origins or `*`.
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let enabled = match std::env::var(GRPC_WEB_ENV) {
+            Ok(enabled) => enabled
+                .parse::<bool>()
+                .map_err(|_| format!("invalid value for {}: {}", GRPC_WEB_ENV, enabled))?,
+            Err(_) => false,
+        };
+        if !enabled {
+            return Ok(None);
+        }
+
+        let mut config = Self::default();
+        if let Ok(origins) = std::env::var(GRPC_WEB_ORIGINS_ENV) {
+            config.allowed_origins = match origins.trim() {
+                "*" => AllowedOrigins::Any,
+                origins => AllowedOrigins::List(
+                    origins
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|origin| !origin.is_empty())
+                        .map(str::to_string)
+                        .collect(),
+                ),
+            };
+        }
+        if let Ok(secs) = std::env::var(GRPC_WEB_MAX_AGE_ENV) {
+            config.max_age =
+                Duration::from_secs(secs.parse().map_err(|_| {
+                    format!("invalid value for {}: {}", GRPC_WEB_MAX_AGE_ENV, secs)
+                })?);
+        }
+        Ok(Some(config))
+    }
+
+    pub fn layer(&self) -> Result<CorsLayer, http::header::InvalidHeaderValue> {
+        let origins = match &self.allowed_origins {
+            AllowedOrigins::Any => AllowOrigin::any(),
+            AllowedOrigins::List(origins) => AllowOrigin::list(
+                origins
+                    .iter()
+                    .map(|origin| HeaderValue::from_str(origin))
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+        };
+
+        Ok(CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods([Method::POST, Method::OPTIONS])
+            .allow_headers(REQUEST_HEADERS.map(HeaderName::from_static))
+            .expose_headers(RESPONSE_HEADERS.map(HeaderName::from_static))
+            .max_age(self.max_age))
+    }
+}
+
+pub fn web_layer(cors: &CorsConfig) -> Result<WebLayer, http::header::InvalidHeaderValue> {
+    Ok(tower::ServiceBuilder::new()
+        .layer(cors.layer()?)
+        .layer(StreamingGuardLayer::new())
+        .layer(GrpcWebLayer::new())
+        .into_inner())
+}
+
+// gRPC-Web has no client streaming. Rejects such calls up front with
+// UNIMPLEMENTED instead of letting them fail halfway through.
+#[derive(Debug, Clone)]
+pub struct StreamingGuardLayer {
+    methods: Arc<HashSet<String>>,
+}
+
+impl Default for StreamingGuardLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreamingGuardLayer {
+    pub fn new() -> Self {
+        Self {
+            methods: Arc::new(client_streaming_methods(FILE_DESCRIPTOR_SET)),
+        }
+    }
+}
+
+impl<S> Layer<S> for StreamingGuardLayer {
+    type Service = StreamingGuard<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        StreamingGuard {
+            inner,
+            methods: self.methods.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StreamingGuard<S> {
+    inner: S,
+    methods: Arc<HashSet<String>>,
+}
+
+impl<S, B> Service<http::Request<B>> for StreamingGuard<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<Body>> + Send,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let content_type = req.headers().get(header::CONTENT_TYPE).cloned();
+        let is_grpc_web = content_type
+            .as_ref()
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("application/grpc-web"));
+        if !is_grpc_web || !self.methods.contains(req.uri().path()) {
+            return Box::pin(self.inner.call(req));
+        }
+
+        let status = Status::unimplemented(format!(
+            "{} streams requests from the client, which gRPC-Web does not support; \
+             call it over HTTP/2 gRPC instead",
+            req.uri().path()
+        ));
+        // Trailers-only response in the caller's gRPC-Web flavor.
+        let mut response: http::Response<Body> = status.into_http();
+        if let Some(content_type) = content_type {
+            response
+                .headers_mut()
+                .insert(header::CONTENT_TYPE, content_type);
+        }
+        Box::pin(async move { Ok(response) })
+    }
+}
+
+// Paths (`/package.Service/Method`) of every client-streaming method.
+fn client_streaming_methods(descriptors: &[u8]) -> HashSet<String> {
+    let Ok(descriptors) = FileDescriptorSet::decode(descriptors) else {
+        return HashSet::new();
+    };
+    descriptors
+        .file
+        .iter()
+        .flat_map(|file| {
+            file.service.iter().flat_map(move |service| {
+                service
+                    .method
+                    .iter()
+                    .filter(|method| method.client_streaming())
+                    .map(move |method| {
+                        format!("/{}.{}/{}", file.package(), service.name(), method.name())
+                    })
+            })
+        })
+        .collect()
+}
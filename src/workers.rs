@@ -0,0 +1,104 @@
+use std::{fmt, sync::Arc, time::Duration};
+
+use uuid::Uuid;
+
+use crate::{
+    clock::{SharedClock, SystemClock},
+    random::RandomSource,
+    sdk::basic::service::v1::{SomeServiceData, SomeServiceResponse},
+    utils::random_protocol,
+};
+
+// A downstream process the Background RPC fans out to.
+#[tonic::async_trait]
+pub trait Worker: fmt::Debug + Send + Sync + 'static {
+    async fn call(&self, index: usize) -> SomeServiceResponse;
+}
+
+// Pretends to call a process: waits 1-3 seconds and reports a random protocol.
+#[derive(Debug, Clone)]
+pub struct SimulatedWorker {
+    rng: RandomSource,
+    clock: SharedClock,
+}
+
+impl Default for SimulatedWorker {
+    fn default() -> Self {
+        Self::new(RandomSource::default(), Arc::new(SystemClock))
+    }
+}
+
+impl SimulatedWorker {
+    pub fn new(rng: RandomSource, clock: SharedClock) -> Self {
+        Self { rng, clock }
+    }
+}
+
+#[tonic::async_trait]
+impl Worker for SimulatedWorker {
+    async fn call(&self, index: usize) -> SomeServiceResponse {
+        let delay = self.rng.random_range(1..=3);
+        self.clock.sleep(Duration::from_secs(delay)).await;
+
+        SomeServiceResponse {
+            id: Uuid::new_v4().to_string(),
+            name: format!("service-{}", index),
+            version: "1.1.2".to_string(),
+            data: Some(SomeServiceData {
+                r#type: "protocol".to_string(),
+                value: random_protocol(&self.rng),
+            }),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct WorkerRegistry {
+    workers: Vec<Arc<dyn Worker>>,
+}
+
+impl Default for WorkerRegistry {
+    fn default() -> Self {
+        Self::simulated(RandomSource::default(), Arc::new(SystemClock))
+    }
+}
+
+impl fmt::Debug for WorkerRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(&self.workers).finish()
+    }
+}
+
+impl WorkerRegistry {
+    pub fn new() -> Self {
+        Self {
+            workers: Vec::new(),
+        }
+    }
+
+    pub fn simulated(rng: RandomSource, clock: SharedClock) -> Self {
+        Self::new().register(SimulatedWorker::new(rng, clock))
+    }
+
+    pub fn register(mut self, worker: impl Worker) -> Self {
+        self.workers.push(Arc::new(worker));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.workers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.workers.is_empty()
+    }
+
+    // Processes are numbered from 1 and assigned to the registered workers
+    // round-robin.
+    pub fn worker(&self, index: usize) -> Option<Arc<dyn Worker>> {
+        if self.workers.is_empty() {
+            return None;
+        }
+        Some(self.workers[index.saturating_sub(1) % self.workers.len()].clone())
+    }
+}
@@ -0,0 +1,192 @@
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use basic_grpc_service_rust::auth::{AuthConfig, AuthError, AuthMethod, Authenticator};
+use http::HeaderMap;
+use jsonwebtoken::{Algorithm, EncodingKey, Header, encode};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+const FIRST_SECRET: &[u8] = b"first-signing-secret-for-tests!!";
+const SECOND_SECRET: &[u8] = b"second-signing-secret-for-tests!";
+
+fn key(kid: &str, secret: &str, alg: Option<&str>) -> Value {
+    let mut key = json!({ "kty": "oct", "kid": kid, "k": secret });
+    if let Some(alg) = alg {
+        key["alg"] = json!(alg);
+    }
+    key
+}
+
+fn first_key() -> Value {
+    key(
+        "first",
+        "Zmlyc3Qtc2lnbmluZy1zZWNyZXQtZm9yLXRlc3RzISE",
+        Some("HS256"),
+    )
+}
+
+fn second_key() -> Value {
+    key(
+        "second",
+        "c2Vjb25kLXNpZ25pbmctc2VjcmV0LWZvci10ZXN0cyE",
+        Some("HS256"),
+    )
+}
+
+fn authenticator(keys: Vec<Value>, algorithms: Vec<Algorithm>) -> Authenticator {
+    let path = std::env::temp_dir().join(format!("basic-jwks-{}.json", Uuid::new_v4()));
+    std::fs::write(&path, json!({ "keys": keys }).to_string()).unwrap();
+    let authenticator = Authenticator::new(AuthConfig {
+        api_keys: Vec::new(),
+        jwks_file: Some(path.to_string_lossy().into_owned()),
+        issuer: Some("https://auth.example.com".to_string()),
+        audience: Some("basic-service".to_string()),
+        algorithms,
+        methods: HashMap::new(),
+        public: Vec::new(),
+    })
+    .unwrap();
+    std::fs::remove_file(path).unwrap();
+    authenticator
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+fn claims() -> Value {
+    json!({
+        "sub": "ada",
+        "scope": "hello background",
+        "iss": "https://auth.example.com",
+        "aud": "basic-service",
+        "exp": now() + 600,
+    })
+}
+
+fn token(alg: Algorithm, kid: Option<&str>, secret: &[u8], claims: &Value) -> String {
+    let mut header = Header::new(alg);
+    header.kid = kid.map(str::to_string);
+    encode(&header, claims, &EncodingKey::from_secret(secret)).unwrap()
+}
+
+fn headers(authorization: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert("authorization", authorization.parse().unwrap());
+    headers
+}
+
+fn reason(authenticator: &Authenticator, token: &str) -> String {
+    authenticator
+        .authenticate(&headers(&format!("Bearer {}", token)))
+        .unwrap_err()
+        .to_string()
+}
+
+#[test]
+fn valid_tokens_authenticate() {
+    let authenticator = authenticator(vec![first_key(), second_key()], Vec::new());
+
+    for scheme in ["Bearer", "bearer", "BEARER"] {
+        let token = token(Algorithm::HS256, Some("second"), SECOND_SECRET, &claims());
+        let principal = authenticator
+            .authenticate(&headers(&format!("{} {}", scheme, token)))
+            .unwrap();
+
+        assert_eq!(principal.subject, "ada");
+        assert_eq!(principal.method, AuthMethod::Jwt);
+        assert!(principal.has_scope("hello") && principal.has_scope("background"));
+    }
+}
+
+#[test]
+fn expired_tokens_are_rejected() {
+    let authenticator = authenticator(vec![first_key()], Vec::new());
+    let mut claims = claims();
+    claims["exp"] = json!(now() - 600);
+
+    let token = token(Algorithm::HS256, Some("first"), FIRST_SECRET, &claims);
+
+    assert!(reason(&authenticator, &token).contains("ExpiredSignature"));
+}
+
+#[test]
+fn foreign_issuers_and_audiences_are_rejected() {
+    let authenticator = authenticator(vec![first_key()], Vec::new());
+
+    for (claim, reason_text) in [("iss", "InvalidIssuer"), ("aud", "InvalidAudience")] {
+        let mut claims = claims();
+        claims[claim] = json!("someone-else");
+        let token = token(Algorithm::HS256, Some("first"), FIRST_SECRET, &claims);
+
+        assert!(reason(&authenticator, &token).contains(reason_text));
+    }
+}
+
+#[test]
+fn unknown_key_ids_are_rejected() {
+    let authenticator = authenticator(vec![first_key()], Vec::new());
+
+    let token = token(Algorithm::HS256, Some("third"), FIRST_SECRET, &claims());
+
+    assert!(reason(&authenticator, &token).contains("unknown signing key"));
+}
+
+#[test]
+fn tokens_need_a_key_id_when_several_keys_are_configured() {
+    let token = token(Algorithm::HS256, None, FIRST_SECRET, &claims());
+
+    let single = authenticator(vec![first_key()], Vec::new());
+    assert!(
+        single
+            .authenticate(&headers(&format!("Bearer {}", token)))
+            .is_ok()
+    );
+
+    let several = authenticator(vec![first_key(), second_key()], Vec::new());
+    assert!(reason(&several, &token).contains("token has no key id"));
+}
+
+#[test]
+fn the_header_cannot_pick_the_algorithm() {
+    let authenticator = authenticator(vec![first_key()], Vec::new());
+
+    let token = token(Algorithm::HS512, Some("first"), FIRST_SECRET, &claims());
+
+    assert!(reason(&authenticator, &token).contains("unexpected signing algorithm"));
+}
+
+#[test]
+fn keys_without_an_algorithm_follow_the_allow_list() {
+    let bare = key("first", "Zmlyc3Qtc2lnbmluZy1zZWNyZXQtZm9yLXRlc3RzISE", None);
+    let token = token(Algorithm::HS256, Some("first"), FIRST_SECRET, &claims());
+
+    let unlisted = authenticator(vec![bare.clone()], Vec::new());
+    assert!(reason(&unlisted, &token).contains("unexpected signing algorithm"));
+
+    let listed = authenticator(vec![bare], vec![Algorithm::HS256]);
+    assert!(
+        listed
+            .authenticate(&headers(&format!("Bearer {}", token)))
+            .is_ok()
+    );
+
+    let excluded = authenticator(vec![first_key()], vec![Algorithm::HS384]);
+    assert!(reason(&excluded, &token).contains("unexpected signing algorithm"));
+}
+
+#[test]
+fn unreadable_configs_name_the_file() {
+    let error = Authenticator::from_file("/nonexistent/auth.json")
+        .err()
+        .unwrap();
+
+    assert!(matches!(error, AuthError::Read { .. }));
+    assert!(error.to_string().contains("/nonexistent/auth.json"));
+}
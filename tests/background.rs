@@ -0,0 +1,120 @@
+mod support;
+
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use basic_grpc_service_rust::{
+    clock::ManualClock, random::RandomSource, sdk::basic::service::v1::State,
+    service::BasicServiceV1, utils,
+};
+use prost_types::Timestamp;
+use support::{TestServer, Transport};
+use tokio_stream::StreamExt;
+
+#[tokio::test]
+async fn background_streams_state_transitions() {
+    let mut server = TestServer::start(Transport::Duplex).await;
+
+    let updates: Vec<_> = server
+        .client
+        .background(3)
+        .await
+        .unwrap()
+        .map(|update| update.unwrap().event)
+        .collect()
+        .await;
+
+    // initial snapshot, one per worker, final snapshot
+    assert_eq!(updates.len(), 5);
+
+    let first = updates.first().unwrap();
+    assert_eq!(first.state, State::Process as i32);
+    assert!(first.started_at.is_some());
+    assert!(first.completed_at.is_none());
+    assert!(first.responses.is_empty());
+
+    for (i, update) in updates[1..4].iter().enumerate() {
+        assert_eq!(update.state, State::Process as i32);
+        assert_eq!(update.responses.len(), i + 1);
+        assert_eq!(update.started_at, first.started_at);
+    }
+
+    let last = updates.last().unwrap();
+    assert_eq!(last.state, State::Complete as i32);
+    assert_eq!(last.responses.len(), 3);
+    let started = last.started_at.unwrap();
+    let completed = last.completed_at.unwrap();
+    assert!((completed.seconds, completed.nanos) >= (started.seconds, started.nanos));
+
+    let mut names: Vec<_> = last.responses.iter().map(|r| r.name.clone()).collect();
+    names.sort();
+    assert_eq!(names, ["service-1", "service-2", "service-3"]);
+}
+
+#[tokio::test]
+async fn background_without_processes_completes_immediately() {
+    let mut server = TestServer::start(Transport::Tcp).await;
+
+    let updates: Vec<_> = server
+        .client
+        .background(0)
+        .await
+        .unwrap()
+        .map(|update| update.unwrap().event)
+        .collect()
+        .await;
+
+    assert_eq!(updates.len(), 2);
+    assert_eq!(updates[1].state, State::Complete as i32);
+}
+
+#[tokio::test]
+async fn background_timestamps_follow_the_injected_clock() {
+    let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let clock = ManualClock::new(start);
+    let service = BasicServiceV1::new()
+        .with_rng(RandomSource::seeded(7))
+        .with_clock(Arc::new(clock.clone()));
+    let mut server = TestServer::start_with(Transport::Duplex, service).await;
+
+    let mut updates = server.client.background(3).await.unwrap();
+    let first = updates.next().await.unwrap().unwrap().event;
+    assert_eq!(first.started_at, Some(Timestamp::from(start)));
+
+    // Simulated workers take 1-3 virtual seconds; nothing finishes until the
+    // clock moves.
+    clock.wait_for_sleepers(3).await;
+    clock.advance(Duration::from_secs(3));
+
+    let last = updates
+        .map(|update| update.unwrap().event)
+        .fold(None, |_, event| Some(event))
+        .await
+        .unwrap();
+    assert_eq!(last.state, State::Complete as i32);
+    assert_eq!(last.responses.len(), 3);
+    assert_eq!(last.started_at, Some(Timestamp::from(start)));
+    assert_eq!(
+        last.completed_at,
+        Some(Timestamp::from(start + Duration::from_secs(3)))
+    );
+}
+
+#[test]
+fn protocols_follow_the_injected_rng() {
+    let picks = |seed| {
+        let rng = RandomSource::seeded(seed);
+        (0..8)
+            .map(|_| utils::random_protocol(&rng))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(picks(3), picks(3));
+    assert!(
+        picks(3)
+            .iter()
+            .all(|p| utils::PROTOCOLS.contains(&p.as_str()))
+    );
+}
@@ -0,0 +1,156 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use basic_grpc_service_rust::{
+    client::{BasicClient, ClientConfig, ClientError},
+    sdk::{
+        basic::{
+            service::v1::{BackgroundResponseEvent, HelloResponseEvent},
+            v1::basic_service_server::BasicServiceServer,
+        },
+        io::cloudevents::v1::{CloudEvent, cloud_event::Data},
+    },
+    service::BasicServiceV1,
+    utils::{self, EventDecodeError},
+};
+use prost::{Message, Name};
+use prost_types::Any;
+use tonic::{Code, Status, body::Body, transport::Endpoint};
+use tower::Service;
+
+// Serves the real service behind a front that answers the first `failures`
+// calls with `code` and records the idempotency key of every call.
+async fn flaky_client(
+    failures: usize,
+    code: Code,
+    retries: u32,
+) -> (BasicClient, Arc<Mutex<Vec<String>>>) {
+    let keys = Arc::new(Mutex::new(Vec::new()));
+    let seen = keys.clone();
+    let inner = BasicServiceServer::new(BasicServiceV1::new());
+    let front = tower::service_fn(move |request: http::Request<axum::body::Body>| {
+        let mut inner = inner.clone();
+        let attempt = {
+            let mut keys = seen.lock().unwrap();
+            let key = request.headers()["idempotency-key"].to_str().unwrap();
+            keys.push(key.to_string());
+            keys.len()
+        };
+        async move {
+            if attempt <= failures {
+                Ok::<_, std::convert::Infallible>(
+                    Status::new(code, "try again").into_http::<Body>(),
+                )
+            } else {
+                inner.call(request).await
+            }
+        }
+    });
+    let router = axum::Router::new().fallback_service(front);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, router).await });
+
+    let channel = Endpoint::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap();
+    let config = ClientConfig {
+        hello_retries: retries,
+        retry_backoff: Duration::from_millis(1),
+        ..Default::default()
+    };
+    (BasicClient::from_channel(channel, config), keys)
+}
+
+fn event_with(type_url: &str, value: Vec<u8>) -> CloudEvent {
+    CloudEvent {
+        data: Some(Data::ProtoData(Any {
+            type_url: type_url.to_string(),
+            value,
+        })),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn transient_failures_are_retried_with_one_key() {
+    let (mut client, keys) = flaky_client(2, Code::Unavailable, 3).await;
+
+    let hello = client.hello("Ada").await.unwrap();
+
+    assert_eq!(hello.event.greeting, "Hello, Ada!");
+    let keys = keys.lock().unwrap();
+    assert_eq!(keys.len(), 3);
+    assert!(keys.iter().all(|key| key == &keys[0]));
+}
+
+#[tokio::test]
+async fn retries_give_up_after_the_configured_attempts() {
+    let (mut client, keys) = flaky_client(usize::MAX, Code::Unavailable, 2).await;
+
+    let error = client.hello("Ada").await.unwrap_err();
+
+    assert!(matches!(error, ClientError::Status(ref s) if s.code() == Code::Unavailable));
+    assert_eq!(keys.lock().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn permanent_failures_are_not_retried() {
+    let (mut client, keys) = flaky_client(1, Code::InvalidArgument, 3).await;
+
+    let error = client.hello("Ada").await.unwrap_err();
+
+    assert!(matches!(error, ClientError::Status(ref s) if s.code() == Code::InvalidArgument));
+    assert_eq!(keys.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn unsendable_tokens_are_metadata_errors() {
+    let channel = Endpoint::from_static("http://127.0.0.1:1").connect_lazy();
+    let config = ClientConfig {
+        token: Some("line\nbreak".to_string()),
+        ..Default::default()
+    };
+    let mut client = BasicClient::from_channel(channel, config);
+
+    let error = client.hello("Ada").await.unwrap_err();
+
+    assert!(matches!(error, ClientError::Metadata(_)), "{}", error);
+}
+
+#[test]
+fn payloads_decode_by_type_url() {
+    let hello = HelloResponseEvent {
+        greeting: "Hello, Ada!".to_string(),
+    };
+    let event = event_with(&HelloResponseEvent::type_url(), hello.encode_to_vec());
+
+    let decoded: HelloResponseEvent = utils::decode_cloud_event(&event).unwrap();
+    assert_eq!(decoded, hello);
+
+    let error = utils::decode_cloud_event::<BackgroundResponseEvent>(&event).unwrap_err();
+    assert!(
+        matches!(error, EventDecodeError::TypeMismatch { ref expected, .. } if expected == "basic.service.v1.BackgroundResponseEvent"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn undecodable_payloads_are_reported() {
+    let missing = CloudEvent::default();
+    assert!(matches!(
+        utils::decode_cloud_event::<HelloResponseEvent>(&missing),
+        Err(EventDecodeError::MissingData)
+    ));
+
+    let garbage = event_with(&HelloResponseEvent::type_url(), vec![0xff, 0xff, 0xff]);
+    assert!(matches!(
+        utils::decode_cloud_event::<HelloResponseEvent>(&garbage),
+        Err(EventDecodeError::Invalid(_))
+    ));
+}
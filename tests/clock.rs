@@ -0,0 +1,132 @@
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+mod support;
+
+use basic_grpc_service_rust::{
+    client::ClientError,
+    clock::{Clock, ManualClock},
+    rate_limit::{RateLimiter, TalkLimits},
+    sdk::basic::service::v1::State,
+    service::BasicServiceV1,
+    utils::StateManager,
+    workers::WorkerRegistry,
+};
+use prost_types::Timestamp;
+use support::{TestServer, Transport};
+use tonic::Code;
+
+#[tokio::test]
+async fn manual_clock_wakes_sleepers_only_when_advanced_past_their_deadline() {
+    let clock = ManualClock::default();
+    let short = tokio::spawn({
+        let clock = clock.clone();
+        async move { clock.sleep(Duration::from_secs(1)).await }
+    });
+    let long = tokio::spawn({
+        let clock = clock.clone();
+        async move { clock.sleep(Duration::from_secs(5)).await }
+    });
+
+    clock.wait_for_sleepers(2).await;
+    clock.advance(Duration::from_secs(2));
+    short.await.unwrap();
+    assert!(!long.is_finished());
+    assert_eq!(clock.sleepers(), 1);
+
+    clock.advance(Duration::from_secs(3));
+    long.await.unwrap();
+    assert_eq!(clock.now(), SystemTime::UNIX_EPOCH + Duration::from_secs(5));
+}
+
+#[test]
+fn state_manager_stamps_jobs_with_its_clock() {
+    let clock = ManualClock::new(SystemTime::UNIX_EPOCH + Duration::from_secs(100));
+    let state = StateManager::new().with_clock(Arc::new(clock.clone()));
+
+    state.start("job", State::Process);
+    clock.advance(Duration::from_millis(1500));
+    state.finish("job", State::Complete);
+
+    let (job_state, started, completed, _) = state.get_state("job");
+    assert_eq!(job_state, Some(State::Complete));
+    assert_eq!(
+        started,
+        Some(Timestamp {
+            seconds: 100,
+            nanos: 0
+        })
+    );
+    assert_eq!(
+        completed,
+        Some(Timestamp {
+            seconds: 101,
+            nanos: 500_000_000
+        })
+    );
+}
+
+#[tokio::test]
+async fn cancelled_sleeps_are_unregistered() {
+    let clock = ManualClock::default();
+
+    tokio::select! {
+        biased;
+        _ = clock.sleep(Duration::from_secs(10)) => unreachable!(),
+        _ = std::future::ready(()) => {}
+    }
+
+    assert_eq!(clock.sleepers(), 0);
+}
+
+#[tokio::test]
+async fn with_clock_keeps_custom_workers() {
+    let clock = ManualClock::default();
+    let service = BasicServiceV1::new()
+        .with_workers(WorkerRegistry::new())
+        .with_clock(Arc::new(clock.clone()));
+    let mut server = TestServer::start_with(Transport::Duplex, service).await;
+
+    let Err(ClientError::Status(status)) = server.client.background(2).await else {
+        panic!("expected the empty registry to be kept");
+    };
+    assert_eq!(status.code(), Code::FailedPrecondition);
+}
+
+#[test]
+fn with_state_manager_keeps_the_managers_clock() {
+    let own = ManualClock::new(SystemTime::UNIX_EPOCH + Duration::from_secs(100));
+    let service = BasicServiceV1::new()
+        .with_state_manager(StateManager::new().with_clock(Arc::new(own)))
+        .with_clock(Arc::new(ManualClock::default()));
+
+    service.state_manager().start("job", State::Process);
+
+    let (_, started, _, _) = service.state_manager().get_state("job");
+    assert_eq!(started.unwrap().seconds, 100);
+}
+
+#[tokio::test]
+async fn talk_sessions_expire_on_the_service_clock() {
+    let clock = ManualClock::default();
+    let limits = TalkLimits {
+        max_session: Duration::from_secs(60),
+        ..TalkLimits::default()
+    };
+    let service = BasicServiceV1::new()
+        .with_clock(Arc::new(clock.clone()))
+        .with_limiter(RateLimiter::new(limits));
+    let mut server = TestServer::start_with(Transport::Duplex, service).await;
+    let mut talk = server.client.talk().await.unwrap();
+
+    talk.ask("Hello").await.unwrap();
+    clock.wait_for_sleepers(1).await;
+    clock.advance(Duration::from_secs(61));
+
+    let Err(ClientError::Status(status)) = talk.recv().await else {
+        panic!("expected the session to expire");
+    };
+    assert_eq!(status.code(), Code::ResourceExhausted);
+}
@@ -0,0 +1,414 @@
+mod support;
+
+use std::io::Write;
+
+use basic_grpc_service_rust::{
+    payload::PayloadConfig,
+    sdk::basic::service::v1::{
+        BackgroundRequest, BackgroundResponse, HelloRequest, HelloResponse, HelloResponseEvent,
+    },
+    service::BasicServiceV1,
+    utils,
+    workers::WorkerRegistry,
+};
+use flate2::{Compression, write::GzEncoder};
+use prost::Message;
+use reqwest::Response;
+use serde_json::{Value, json};
+use support::{TestServer, Transport};
+
+async fn connect_server(service: BasicServiceV1) -> TestServer {
+    TestServer::start_configured(Transport::Tcp, service, |builder| builder.connect(true)).await
+}
+
+async fn call(
+    server: &TestServer,
+    method: &str,
+    content_type: &str,
+    headers: &[(&str, &str)],
+    body: Vec<u8>,
+) -> Response {
+    let mut request = reqwest::Client::new()
+        .post(format!(
+            "http://{}/basic.v1.BasicService/{}",
+            server.addr.unwrap(),
+            method
+        ))
+        .header("content-type", content_type)
+        .header("connect-protocol-version", "1");
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    request.body(body).send().await.unwrap()
+}
+
+fn gzip(message: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(message).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn envelope(flags: u8, message: &[u8]) -> Vec<u8> {
+    let mut envelope = vec![flags];
+    envelope.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    envelope.extend_from_slice(message);
+    envelope
+}
+
+// Splits a streaming response into its message envelopes and the
+// end-of-stream JSON.
+fn envelopes(mut body: &[u8]) -> (Vec<Vec<u8>>, Value) {
+    let mut messages = Vec::new();
+    let mut end = Value::Null;
+    while !body.is_empty() {
+        let flags = body[0];
+        let len = u32::from_be_bytes(body[1..5].try_into().unwrap()) as usize;
+        let payload = &body[5..5 + len];
+        if flags & 0x02 != 0 {
+            end = serde_json::from_slice(payload).unwrap();
+        } else {
+            messages.push(payload.to_vec());
+        }
+        body = &body[5 + len..];
+    }
+    (messages, end)
+}
+
+#[tokio::test]
+async fn unary_json() {
+    let server = connect_server(BasicServiceV1::new()).await;
+
+    let body = json!({ "message": "Connect" }).to_string();
+    let response = call(&server, "Hello", "application/json", &[], body.into()).await;
+
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["content-type"], "application/json");
+    let body: Value = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+    assert_eq!(
+        body["cloudEvent"]["protoData"]["greeting"],
+        "Hello, Connect!"
+    );
+}
+
+#[tokio::test]
+async fn unary_proto() {
+    let server = connect_server(BasicServiceV1::new()).await;
+
+    let request = HelloRequest {
+        message: "Proto".to_string(),
+        ..Default::default()
+    };
+    let response = call(
+        &server,
+        "Hello",
+        "application/proto",
+        &[],
+        request.encode_to_vec(),
+    )
+    .await;
+
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["content-type"], "application/proto");
+    let hello = HelloResponse::decode(response.bytes().await.unwrap()).unwrap();
+    let event: HelloResponseEvent = utils::decode_cloud_event(&hello.cloud_event.unwrap()).unwrap();
+    assert_eq!(event.greeting, "Hello, Proto!");
+}
+
+#[tokio::test]
+async fn unary_errors_use_connect_codes() {
+    let server = connect_server(BasicServiceV1::new()).await;
+
+    let response = call(
+        &server,
+        "Hello",
+        "application/json",
+        &[],
+        b"{\"message\": 42}".to_vec(),
+    )
+    .await;
+
+    assert_eq!(response.status(), 400);
+    assert_eq!(response.headers()["content-type"], "application/json");
+    let error: Value = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+    assert_eq!(error["code"], "invalid_argument");
+    assert!(error["message"].as_str().unwrap().contains("HelloRequest"));
+}
+
+#[tokio::test]
+async fn unary_bodies_may_be_compressed() {
+    let server = connect_server(BasicServiceV1::new()).await;
+    let json = json!({ "message": "Gzip" }).to_string();
+    let proto = HelloRequest {
+        message: "Zstd".to_string(),
+        ..Default::default()
+    };
+
+    let gzipped = call(
+        &server,
+        "Hello",
+        "application/json",
+        &[("content-encoding", "gzip")],
+        gzip(json.as_bytes()),
+    )
+    .await;
+    let zstded = call(
+        &server,
+        "Hello",
+        "application/proto",
+        &[("content-encoding", "zstd")],
+        zstd::bulk::compress(&proto.encode_to_vec(), 0).unwrap(),
+    )
+    .await;
+
+    assert_eq!(gzipped.status(), 200);
+    let body: Value = serde_json::from_str(&gzipped.text().await.unwrap()).unwrap();
+    assert_eq!(body["cloudEvent"]["protoData"]["greeting"], "Hello, Gzip!");
+    assert_eq!(zstded.status(), 200);
+    let hello = HelloResponse::decode(zstded.bytes().await.unwrap()).unwrap();
+    let event: HelloResponseEvent = utils::decode_cloud_event(&hello.cloud_event.unwrap()).unwrap();
+    assert_eq!(event.greeting, "Hello, Zstd!");
+}
+
+#[tokio::test]
+async fn oversized_unary_bodies_are_resource_exhausted() {
+    let payload = PayloadConfig {
+        max_recv_message_bytes: 64,
+        ..Default::default()
+    };
+    let server = TestServer::start_configured(Transport::Tcp, BasicServiceV1::new(), |builder| {
+        builder.connect(true).payload(payload)
+    })
+    .await;
+    let body = json!({ "message": "x".repeat(100) }).to_string();
+
+    for (headers, body) in [
+        (&[][..], body.clone().into_bytes()),
+        (&[("content-encoding", "gzip")][..], gzip(body.as_bytes())),
+    ] {
+        let response = call(&server, "Hello", "application/json", headers, body).await;
+        assert_eq!(response.status(), 429);
+        let error: Value = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+        assert_eq!(error["code"], "resource_exhausted");
+    }
+}
+
+#[tokio::test]
+async fn server_streaming_json() {
+    let server = connect_server(BasicServiceV1::new()).await;
+
+    let request = envelope(0, json!({ "processes": "0" }).to_string().as_bytes());
+    let response = call(
+        &server,
+        "Background",
+        "application/connect+json",
+        &[],
+        request,
+    )
+    .await;
+
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        response.headers()["content-type"],
+        "application/connect+json"
+    );
+    let (messages, end) = envelopes(&response.bytes().await.unwrap());
+    let states: Vec<Value> = messages
+        .iter()
+        .map(|message| {
+            let snapshot: Value = serde_json::from_slice(message).unwrap();
+            snapshot["cloudEvent"]["protoData"]["state"].clone()
+        })
+        .collect();
+    assert_eq!(states, ["STATE_PROCESS", "STATE_COMPLETE"]);
+    assert!(end.get("error").is_none(), "{}", end);
+}
+
+#[tokio::test]
+async fn server_streaming_proto() {
+    let server = connect_server(BasicServiceV1::new()).await;
+
+    let request = envelope(0, &BackgroundRequest { processes: 0 }.encode_to_vec());
+    let response = call(
+        &server,
+        "Background",
+        "application/connect+proto",
+        &[],
+        request,
+    )
+    .await;
+
+    assert_eq!(
+        response.headers()["content-type"],
+        "application/connect+proto"
+    );
+    let (messages, end) = envelopes(&response.bytes().await.unwrap());
+    assert_eq!(messages.len(), 2);
+    for message in messages {
+        BackgroundResponse::decode(message.as_slice()).unwrap();
+    }
+    assert_eq!(end, json!({}));
+}
+
+#[tokio::test]
+async fn streaming_errors_end_the_stream() {
+    let service = BasicServiceV1::new().with_workers(WorkerRegistry::new());
+    let server = connect_server(service).await;
+
+    let request = envelope(0, json!({ "processes": 2 }).to_string().as_bytes());
+    let response = call(
+        &server,
+        "Background",
+        "application/connect+json",
+        &[],
+        request,
+    )
+    .await;
+
+    // Streaming errors travel in the end-of-stream envelope, not the status.
+    assert_eq!(response.status(), 200);
+    let (messages, end) = envelopes(&response.bytes().await.unwrap());
+    assert!(messages.is_empty());
+    assert_eq!(end["error"]["code"], "failed_precondition");
+    assert_eq!(end["error"]["message"], "no background workers registered");
+}
+
+#[tokio::test]
+async fn streaming_envelopes_may_be_compressed() {
+    let server = connect_server(BasicServiceV1::new()).await;
+    let headers = [("connect-content-encoding", "gzip")];
+
+    let json = envelope(1, &gzip(json!({ "processes": "0" }).to_string().as_bytes()));
+    let response = call(
+        &server,
+        "Background",
+        "application/connect+json",
+        &headers,
+        json,
+    )
+    .await;
+    let (messages, end) = envelopes(&response.bytes().await.unwrap());
+    assert_eq!(messages.len(), 2);
+    assert!(end.get("error").is_none(), "{}", end);
+
+    let proto = envelope(
+        1,
+        &gzip(&BackgroundRequest { processes: 0 }.encode_to_vec()),
+    );
+    let response = call(
+        &server,
+        "Background",
+        "application/connect+proto",
+        &headers,
+        proto,
+    )
+    .await;
+    let (messages, end) = envelopes(&response.bytes().await.unwrap());
+    assert_eq!(messages.len(), 2);
+    assert_eq!(end, json!({}));
+}
+
+#[tokio::test]
+async fn streaming_responses_stay_uncompressed() {
+    // Every gRPC response message would be compressed for a caller that
+    // accepts gzip.
+    let payload = PayloadConfig {
+        compression_min_bytes: 0,
+        ..Default::default()
+    };
+    let server = TestServer::start_configured(Transport::Tcp, BasicServiceV1::new(), |builder| {
+        builder.connect(true).payload(payload)
+    })
+    .await;
+    let headers = [
+        ("grpc-accept-encoding", "gzip"),
+        ("connect-accept-encoding", "gzip"),
+    ];
+
+    let request = envelope(0, &BackgroundRequest { processes: 0 }.encode_to_vec());
+    let response = call(
+        &server,
+        "Background",
+        "application/connect+proto",
+        &headers,
+        request,
+    )
+    .await;
+
+    let body = response.bytes().await.unwrap();
+    let mut rest = &body[..];
+    while !rest.is_empty() {
+        assert_eq!(rest[0] & 0x01, 0, "compressed envelope");
+        let len = u32::from_be_bytes(rest[1..5].try_into().unwrap()) as usize;
+        rest = &rest[5 + len..];
+    }
+    let (messages, end) = envelopes(&body);
+    assert_eq!(messages.len(), 2);
+    for message in messages {
+        BackgroundResponse::decode(message.as_slice()).unwrap();
+    }
+    assert_eq!(end, json!({}));
+}
+
+#[tokio::test]
+async fn bidi_streaming_json() {
+    let server = connect_server(BasicServiceV1::new()).await;
+
+    let mut request = Vec::new();
+    for message in ["Hello", "I feel tired"] {
+        request.extend(envelope(
+            0,
+            json!({ "message": message, "includeCloudEvent": true })
+                .to_string()
+                .as_bytes(),
+        ));
+    }
+    let response = call(&server, "Talk", "application/connect+json", &[], request).await;
+
+    let (messages, end) = envelopes(&response.bytes().await.unwrap());
+    assert_eq!(messages.len(), 2);
+    for (sequence, message) in messages.iter().enumerate() {
+        let talk: Value = serde_json::from_slice(message).unwrap();
+        assert_eq!(
+            talk["cloudEvent"]["protoData"]["sequence"],
+            (sequence + 1).to_string()
+        );
+    }
+    assert!(end.get("error").is_none(), "{}", end);
+}
+
+#[tokio::test]
+async fn unsupported_headers_are_rejected() {
+    let server = connect_server(BasicServiceV1::new()).await;
+    let body = json!({ "message": "x" }).to_string();
+
+    let compressed = call(
+        &server,
+        "Hello",
+        "application/json",
+        &[("content-encoding", "br")],
+        body.clone().into(),
+    )
+    .await;
+    assert_eq!(compressed.status(), 501);
+    let error: Value = serde_json::from_str(&compressed.text().await.unwrap()).unwrap();
+    assert_eq!(error["code"], "unimplemented");
+
+    let timeout = call(
+        &server,
+        "Hello",
+        "application/json",
+        &[("connect-timeout-ms", "soon")],
+        body.into(),
+    )
+    .await;
+    assert_eq!(timeout.status(), 400);
+}
+
+#[tokio::test]
+async fn grpc_keeps_working_alongside_connect() {
+    let mut server = connect_server(BasicServiceV1::new()).await;
+
+    let hello = server.client.hello("gRPC").await.unwrap();
+
+    assert_eq!(hello.event.greeting, "Hello, gRPC!");
+}
@@ -0,0 +1,299 @@
+mod support;
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use basic_grpc_service_rust::{
+    client::{BasicClient, ClientConfig, ClientError},
+    clock::ManualClock,
+    deadline::{Deadlines, MethodDeadline},
+    sdk::basic::service::v1::{SomeServiceResponse, State},
+    service::BasicServiceV1,
+    workers::{Worker, WorkerRegistry},
+};
+use support::{TestServer, Transport};
+use tokio_stream::StreamExt;
+use tonic::{Code, Request};
+
+// Answers right away.
+#[derive(Debug)]
+struct Quick;
+
+#[tonic::async_trait]
+impl Worker for Quick {
+    async fn call(&self, index: usize) -> SomeServiceResponse {
+        SomeServiceResponse {
+            name: format!("service-{}", index),
+            ..Default::default()
+        }
+    }
+}
+
+// Never answers; records when it is dropped.
+#[derive(Debug, Default)]
+struct Stuck {
+    stopped: Arc<AtomicBool>,
+}
+
+struct SetOnDrop(Arc<AtomicBool>);
+
+impl Drop for SetOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+#[tonic::async_trait]
+impl Worker for Stuck {
+    async fn call(&self, _index: usize) -> SomeServiceResponse {
+        let _guard = SetOnDrop(self.stopped.clone());
+        std::future::pending().await
+    }
+}
+
+async fn server(clock: &ManualClock, stuck: Stuck, deadlines: Deadlines) -> TestServer {
+    let service = BasicServiceV1::new()
+        .with_clock(Arc::new(clock.clone()))
+        .with_workers(WorkerRegistry::new().register(Quick).register(stuck))
+        .with_deadlines(deadlines);
+    TestServer::start_with(Transport::Tcp, service).await
+}
+
+async fn client(server: &TestServer, stream_timeout: Option<Duration>) -> BasicClient {
+    BasicClient::connect(ClientConfig {
+        endpoint: format!("http://{}", server.addr.unwrap()),
+        ca_cert: None,
+        stream_timeout,
+        ..Default::default()
+    })
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn background_ends_with_partial_results_at_the_deadline() {
+    let clock = ManualClock::default();
+    let stuck = Stuck::default();
+    let stopped = stuck.stopped.clone();
+    let server = server(&clock, stuck, Deadlines::new()).await;
+    let mut client = client(&server, Some(Duration::from_secs(30))).await;
+
+    let updates = client.background(2).await.unwrap();
+    // The coordinator waits for the deadline on the service clock.
+    clock.wait_for_sleepers(1).await;
+    clock.advance(Duration::from_secs(30));
+
+    let last = updates
+        .map(|update| update.unwrap().event)
+        .fold(None, |_, event| Some(event))
+        .await
+        .unwrap();
+    assert_eq!(last.state, State::CompleteWithError as i32);
+    assert_eq!(last.responses.len(), 1);
+    assert_eq!(last.responses[0].name, "service-1");
+
+    // The stuck worker was stopped instead of running on.
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while !stopped.load(Ordering::SeqCst) {
+            tokio::task::yield_now().await;
+        }
+    })
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn deadline_leaves_a_margin_for_the_final_snapshot() {
+    let clock = ManualClock::default();
+    let deadlines = Deadlines::new().with_margin(Duration::from_secs(5));
+    let server = server(&clock, Stuck::default(), deadlines).await;
+    let mut client = client(&server, Some(Duration::from_secs(30))).await;
+
+    let updates = client.background(2).await.unwrap();
+    clock.wait_for_sleepers(1).await;
+    clock.advance(Duration::from_secs(25));
+
+    let states: Vec<_> = updates
+        .map(|update| update.unwrap().event.state)
+        .collect()
+        .await;
+    assert_eq!(states.last(), Some(&(State::CompleteWithError as i32)));
+}
+
+#[tokio::test]
+async fn server_default_applies_without_a_client_deadline() {
+    let clock = ManualClock::default();
+    let deadlines = Deadlines::new().with_method(
+        "Background",
+        MethodDeadline {
+            default: Some(Duration::from_secs(10)),
+            max: None,
+        },
+    );
+    let server = server(&clock, Stuck::default(), deadlines).await;
+    let mut client = client(&server, None).await;
+
+    let updates = client.background(2).await.unwrap();
+    clock.wait_for_sleepers(1).await;
+    clock.advance(Duration::from_secs(10));
+
+    let states: Vec<_> = updates
+        .map(|update| update.unwrap().event.state)
+        .collect()
+        .await;
+    assert_eq!(states.last(), Some(&(State::CompleteWithError as i32)));
+}
+
+#[tokio::test]
+async fn keyed_background_jobs_outlive_the_first_deadline() {
+    let clock = ManualClock::default();
+    let stuck = Stuck::default();
+    let stopped = stuck.stopped.clone();
+    let server = server(&clock, stuck, Deadlines::new()).await;
+    let mut client = client(&server, Some(Duration::from_secs(30))).await;
+
+    let mut first = client.background_with_key(2, "job-1").await.unwrap();
+    first.next().await.unwrap().unwrap();
+    drop(first);
+    clock.advance(Duration::from_secs(60));
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let mut retry = client.background_with_key(2, "job-1").await.unwrap();
+    let attached = retry.next().await.unwrap().unwrap();
+    assert_eq!(attached.event.state, State::Process as i32);
+    assert!(!stopped.load(Ordering::SeqCst));
+}
+
+#[tokio::test]
+async fn keyed_background_jobs_stop_at_the_server_max() {
+    let clock = ManualClock::default();
+    let stuck = Stuck::default();
+    let stopped = stuck.stopped.clone();
+    let deadlines = Deadlines::new().with_method(
+        "Background",
+        MethodDeadline {
+            default: None,
+            max: Some(Duration::from_secs(60)),
+        },
+    );
+    let server = server(&clock, stuck, deadlines).await;
+    let mut client = client(&server, None).await;
+
+    let updates = client.background_with_key(2, "job-1").await.unwrap();
+    clock.wait_for_sleepers(1).await;
+    clock.advance(Duration::from_secs(60));
+
+    let states: Vec<_> = updates
+        .map(|update| update.unwrap().event.state)
+        .collect()
+        .await;
+    assert_eq!(states.last(), Some(&(State::CompleteWithError as i32)));
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while !stopped.load(Ordering::SeqCst) {
+            tokio::task::yield_now().await;
+        }
+    })
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn talk_ends_at_the_call_deadline() {
+    let clock = ManualClock::default();
+    let deadlines = Deadlines::new().with_method(
+        "Talk",
+        MethodDeadline {
+            default: None,
+            max: Some(Duration::from_secs(30)),
+        },
+    );
+    let service = BasicServiceV1::new()
+        .with_clock(Arc::new(clock.clone()))
+        .with_deadlines(deadlines);
+    let mut server = TestServer::start_with(Transport::Duplex, service).await;
+    let mut talk = server.client.talk().await.unwrap();
+
+    // The session waits for the deadline on the service clock.
+    talk.ask("hello").await.unwrap();
+    clock.wait_for_sleepers(1).await;
+    clock.advance(Duration::from_secs(29));
+    talk.ask("still there?").await.unwrap();
+
+    clock.advance(Duration::from_secs(1));
+    let error = talk.recv().await.unwrap_err();
+
+    let ClientError::Status(status) = error else {
+        panic!("expected a status, got {}", error);
+    };
+    assert_eq!(status.code(), Code::DeadlineExceeded);
+}
+
+fn request(timeout: Option<&str>) -> Request<()> {
+    let mut request = Request::new(());
+    if let Some(timeout) = timeout {
+        request
+            .metadata_mut()
+            .insert("grpc-timeout", timeout.parse().unwrap());
+    }
+    request
+}
+
+#[test]
+fn resolve_reads_grpc_timeout_units() {
+    let deadlines = Deadlines::new();
+
+    for (timeout, expected) in [
+        ("2H", Duration::from_secs(7200)),
+        ("3M", Duration::from_secs(180)),
+        ("5S", Duration::from_secs(5)),
+        ("250m", Duration::from_millis(250)),
+        ("10u", Duration::from_micros(10)),
+        ("99999999n", Duration::from_nanos(99_999_999)),
+    ] {
+        assert_eq!(
+            deadlines.resolve("Background", &request(Some(timeout))),
+            Some(expected),
+            "{}",
+            timeout
+        );
+    }
+    for malformed in ["", "5", "S", "5s", "-5S", "123456789S"] {
+        assert_eq!(
+            deadlines.resolve("Background", &request(Some(malformed))),
+            None,
+            "{}",
+            malformed
+        );
+    }
+}
+
+#[test]
+fn resolve_applies_method_default_and_max() {
+    let deadlines = Deadlines::new().with_method(
+        "Background",
+        MethodDeadline {
+            default: Some(Duration::from_secs(30)),
+            max: Some(Duration::from_secs(60)),
+        },
+    );
+
+    assert_eq!(
+        deadlines.resolve("Background", &request(None)),
+        Some(Duration::from_secs(30))
+    );
+    assert_eq!(
+        deadlines.resolve("Background", &request(Some("10S"))),
+        Some(Duration::from_secs(10))
+    );
+    assert_eq!(
+        deadlines.resolve("Background", &request(Some("1H"))),
+        Some(Duration::from_secs(60))
+    );
+    // Other methods are unaffected.
+    assert_eq!(deadlines.resolve("Hello", &request(None)), None);
+}
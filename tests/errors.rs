@@ -0,0 +1,197 @@
+mod support;
+
+use std::{collections::HashMap, time::Duration};
+
+use basic_grpc_service_rust::{
+    auth::{ApiKey, AuthConfig, Authenticator},
+    client::ClientError,
+    error::{ERROR_DOMAIN, JOB_RESOURCE_TYPE, ServiceError},
+    idempotency::IDEMPOTENCY_KEY_HEADER,
+    rate_limit::{RateLimiter, TalkLimits},
+    sdk::basic::{
+        service::v1::{BackgroundRequest, HelloRequest},
+        v1::basic_service_server::BasicService,
+    },
+    service::BasicServiceV1,
+    workers::WorkerRegistry,
+};
+use http::HeaderMap;
+use serde_json::{Value, json};
+use support::{TestServer, Transport};
+use tonic::{Code, Request, Status};
+use tonic_types::StatusExt;
+
+fn assert_reason(status: &Status, reason: &str) {
+    let info = status.get_details_error_info().expect("ErrorInfo");
+    assert_eq!(info.domain, ERROR_DOMAIN);
+    assert_eq!(info.reason, reason);
+}
+
+fn talk_status(error: ClientError) -> Status {
+    match error {
+        ClientError::Status(status) => status,
+        error => panic!("expected a status, got {}", error),
+    }
+}
+
+async fn talk_server(limits: TalkLimits) -> TestServer {
+    let service = BasicServiceV1::new().with_limiter(RateLimiter::new(limits));
+    TestServer::start_with(Transport::Duplex, service).await
+}
+
+#[tokio::test]
+async fn missing_workers_is_a_failed_precondition() {
+    let service = BasicServiceV1::new().with_workers(WorkerRegistry::new());
+
+    let Err(status) = service
+        .background(Request::new(BackgroundRequest { processes: 2 }))
+        .await
+    else {
+        panic!("expected an error");
+    };
+
+    assert_eq!(status.code(), Code::FailedPrecondition);
+    assert_eq!(status.message(), "no background workers registered");
+    assert_reason(&status, "NO_WORKERS");
+}
+
+#[tokio::test]
+async fn malformed_idempotency_keys_are_field_violations() {
+    let service = BasicServiceV1::new();
+    let mut request = Request::new(HelloRequest {
+        message: "Ada".to_string(),
+        ..Default::default()
+    });
+    request
+        .metadata_mut()
+        .insert(IDEMPOTENCY_KEY_HEADER, "".parse().unwrap());
+
+    let status = service.hello(request).await.unwrap_err();
+
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert_reason(&status, "INVALID_ARGUMENT");
+    let violations = status.get_details_bad_request().unwrap().field_violations;
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].field, IDEMPOTENCY_KEY_HEADER);
+}
+
+#[tokio::test]
+async fn oversized_talk_messages_are_field_violations() {
+    let mut server = talk_server(TalkLimits {
+        max_message_len: 8,
+        ..Default::default()
+    })
+    .await;
+    let mut talk = server.client.talk().await.unwrap();
+
+    talk.send("far too long for the limit").await.unwrap();
+    let status = talk_status(talk.recv().await.unwrap_err());
+
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert_eq!(status.message(), "message: must be at most 8 bytes");
+    let violations = status.get_details_bad_request().unwrap().field_violations;
+    assert_eq!(violations[0].field, "message");
+}
+
+#[tokio::test]
+async fn rate_limited_talk_carries_retry_info() {
+    let mut server = talk_server(TalkLimits {
+        connection_rate: 0.001,
+        connection_burst: 1.0,
+        ..Default::default()
+    })
+    .await;
+    let mut talk = server.client.talk().await.unwrap();
+
+    talk.ask("Hello").await.unwrap();
+    talk.send("Hello again").await.unwrap();
+    let status = talk_status(talk.recv().await.unwrap_err());
+
+    assert_eq!(status.code(), Code::ResourceExhausted);
+    assert_reason(&status, "RATE_LIMITED");
+    let retry_delay = status.get_details_retry_info().unwrap().retry_delay;
+    assert!(retry_delay.unwrap() > Duration::from_secs(1));
+}
+
+#[test]
+fn aborted_jobs_name_the_job() {
+    let status = Status::from(ServiceError::JobAborted {
+        job: "idempotency:Hello::abc".to_string(),
+        description: "idempotency key expired, retry".to_string(),
+    });
+
+    assert_eq!(status.code(), Code::Aborted);
+    assert_reason(&status, "JOB_ABORTED");
+    let resource = status.get_details_resource_info().unwrap();
+    assert_eq!(resource.resource_type, JOB_RESOURCE_TYPE);
+    assert_eq!(resource.resource_name, "idempotency:Hello::abc");
+}
+
+#[test]
+fn missing_scopes_are_reported_in_error_info() {
+    let authenticator = Authenticator::new(AuthConfig {
+        api_keys: vec![ApiKey {
+            key: "secret".to_string(),
+            subject: "reader".to_string(),
+            scopes: Vec::new(),
+        }],
+        jwks_file: None,
+        issuer: None,
+        audience: None,
+        algorithms: Vec::new(),
+        methods: HashMap::from([(
+            "/basic.v1.BasicService/Hello".to_string(),
+            vec!["hello".to_string()],
+        )]),
+        public: Vec::new(),
+    })
+    .unwrap();
+    let mut headers = HeaderMap::new();
+    headers.insert("authorization", "ApiKey secret".parse().unwrap());
+
+    let error = authenticator
+        .authorize("/basic.v1.BasicService/Hello", &headers)
+        .unwrap_err();
+    let status = Status::from(error);
+
+    assert_eq!(status.code(), Code::PermissionDenied);
+    let info = status.get_details_error_info().unwrap();
+    assert_eq!(info.reason, "MISSING_SCOPE");
+    assert_eq!(info.metadata["scope"], "hello");
+
+    let status = Status::from(authenticator.authenticate(&HeaderMap::new()).unwrap_err());
+    assert_eq!(status.code(), Code::Unauthenticated);
+    assert_reason(&status, "UNAUTHENTICATED");
+}
+
+#[tokio::test]
+async fn connect_errors_include_details() {
+    let service = BasicServiceV1::new().with_workers(WorkerRegistry::new());
+    let server =
+        TestServer::start_configured(Transport::Tcp, service, |builder| builder.connect(true))
+            .await;
+
+    let mut request = vec![0];
+    let message = json!({ "processes": 2 }).to_string();
+    request.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    request.extend_from_slice(message.as_bytes());
+    let body = reqwest::Client::new()
+        .post(format!(
+            "http://{}/basic.v1.BasicService/Background",
+            server.addr.unwrap()
+        ))
+        .header("content-type", "application/connect+json")
+        .body(request)
+        .send()
+        .await
+        .unwrap()
+        .bytes()
+        .await
+        .unwrap();
+
+    let end: Value = serde_json::from_slice(&body[5..]).unwrap();
+    let details = end["error"]["details"].as_array().unwrap();
+    assert_eq!(details.len(), 1);
+    assert_eq!(details[0]["type"], "google.rpc.ErrorInfo");
+    assert!(!details[0]["value"].as_str().unwrap().is_empty());
+}
@@ -0,0 +1,153 @@
+mod support;
+
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use basic_grpc_service_rust::{
+    clock::ManualClock,
+    events::{EventBus, EventFilter, structured_json},
+    sdk::io::cloudevents::v1::{CloudEvent, cloud_event::cloud_event_attribute_value::Attr},
+    service::BasicServiceV1,
+    utils::{
+        DATACONTENTTYPE_ATTRIBUTE, DATASCHEMA_ATTRIBUTE, EventKind, PROTOBUF_CONTENT_TYPE,
+        SUBJECT_ATTRIBUTE, TIME_ATTRIBUTE,
+    },
+};
+use prost_types::Timestamp;
+use support::{TestServer, Transport};
+use tokio_stream::StreamExt;
+use uuid::Uuid;
+
+fn attr(event: &CloudEvent, key: &str) -> Attr {
+    event.attributes[key].attr.clone().unwrap()
+}
+
+fn subject(event: &CloudEvent) -> String {
+    match attr(event, SUBJECT_ATTRIBUTE) {
+        Attr::CeString(subject) => subject,
+        other => panic!("subject is not a string: {:?}", other),
+    }
+}
+
+fn start() -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+}
+
+async fn server_at(clock: &ManualClock) -> TestServer {
+    let service = BasicServiceV1::new().with_clock(Arc::new(clock.clone()));
+    TestServer::start_with(Transport::Duplex, service).await
+}
+
+#[tokio::test]
+async fn hello_events_carry_context_attributes() {
+    let clock = ManualClock::new(start());
+    let mut server = server_at(&clock).await;
+
+    let hello = server.client.hello("<Ada>").await.unwrap();
+
+    let event = &hello.cloud_event;
+    assert_eq!(
+        attr(event, TIME_ATTRIBUTE),
+        Attr::CeTimestamp(Timestamp::from(start()))
+    );
+    assert_eq!(
+        attr(event, DATACONTENTTYPE_ATTRIBUTE),
+        Attr::CeString(PROTOBUF_CONTENT_TYPE.to_string())
+    );
+    assert_eq!(
+        attr(event, DATASCHEMA_ATTRIBUTE),
+        Attr::CeUri("urn:proto:basic.service.v1.HelloResponseEvent".to_string())
+    );
+    assert_eq!(subject(event), "&lt;Ada&gt;");
+}
+
+#[tokio::test]
+async fn background_snapshots_name_their_job() {
+    let mut server = TestServer::start(Transport::Duplex).await;
+
+    let events: Vec<_> = server
+        .client
+        .background(2)
+        .await
+        .unwrap()
+        .map(|update| update.unwrap().cloud_event)
+        .collect()
+        .await;
+
+    let job = subject(&events[0]);
+    assert!(Uuid::parse_str(&job).is_ok());
+    for event in &events {
+        assert_eq!(event.r#type, EventKind::BACKGROUND.r#type);
+        assert_eq!(event.source, EventKind::BACKGROUND.source);
+        assert_eq!(subject(event), job);
+        assert_eq!(
+            attr(event, DATASCHEMA_ATTRIBUTE),
+            Attr::CeUri("urn:proto:basic.service.v1.BackgroundResponseEvent".to_string())
+        );
+    }
+}
+
+#[tokio::test]
+async fn talk_turns_share_the_session_subject() {
+    let mut server = TestServer::start(Transport::Duplex).await;
+    let mut first = server.client.talk().await.unwrap().with_cloud_events(true);
+    let mut second = server.client.talk().await.unwrap().with_cloud_events(true);
+
+    let a = first.ask("Hello").await.unwrap().cloud_event.unwrap();
+    let b = first
+        .ask("I feel tired")
+        .await
+        .unwrap()
+        .cloud_event
+        .unwrap();
+    let other = second.ask("Hello").await.unwrap().cloud_event.unwrap();
+
+    assert_eq!(subject(&a), subject(&b));
+    assert_ne!(subject(&a), subject(&other));
+    assert_eq!(
+        attr(&a, DATACONTENTTYPE_ATTRIBUTE),
+        Attr::CeString(PROTOBUF_CONTENT_TYPE.to_string())
+    );
+}
+
+#[tokio::test]
+async fn lag_notices_are_stamped_by_the_bus_clock() {
+    let clock = ManualClock::new(start());
+    let bus = EventBus::new(1).with_clock(Arc::new(clock.clone()));
+    let mut events = bus.subscribe(EventFilter::default());
+    let hello = CloudEvent {
+        r#type: EventKind::HELLO.r#type.to_string(),
+        ..Default::default()
+    };
+
+    clock.advance(Duration::from_secs(5));
+    bus.publish(&hello);
+    bus.publish(&hello);
+
+    let notice = events.next().await.unwrap().unwrap().cloud_event.unwrap();
+    assert_eq!(notice.r#type, EventKind::SUBSCRIBE_LAGGED.r#type);
+    assert_eq!(
+        attr(&notice, TIME_ATTRIBUTE),
+        Attr::CeTimestamp(Timestamp::from(start() + Duration::from_secs(5)))
+    );
+    assert!(!subject(&notice).is_empty());
+}
+
+#[tokio::test]
+async fn sinks_render_the_attributes() {
+    let clock = ManualClock::new(start());
+    let mut server = server_at(&clock).await;
+
+    let hello = server.client.hello("Ada").await.unwrap();
+
+    let json = structured_json(&hello.cloud_event);
+    assert_eq!(json["subject"], "Ada");
+    assert_eq!(json["time"], "2023-11-14T22:13:20Z");
+    assert_eq!(json["datacontenttype"], "application/protobuf");
+    assert_eq!(
+        json["dataschema"],
+        "urn:proto:basic.service.v1.HelloResponseEvent"
+    );
+}
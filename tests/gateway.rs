@@ -0,0 +1,250 @@
+use std::net::SocketAddr;
+
+use basic_grpc_service_rust::{
+    gateway::Gateway, payload::PayloadConfig, service::BasicServiceV1, workers::WorkerRegistry,
+};
+use futures_util::SinkExt;
+use serde_json::{Value, json};
+use tokio_stream::StreamExt;
+use tokio_tungstenite::tungstenite::Message;
+
+async fn gateway(service: BasicServiceV1) -> String {
+    gateway_with(service, PayloadConfig::default()).await
+}
+
+async fn gateway_with(service: BasicServiceV1, payload: PayloadConfig) -> String {
+    let router = Gateway::new(service, None, &payload).unwrap().router();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(
+            listener,
+            router.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await
+    });
+    addr.to_string()
+}
+
+async fn post(addr: &str, path: &str, body: Value) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("http://{}{}", addr, path))
+        .header("content-type", "application/json")
+        .body(body.to_string())
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn json_body(response: reqwest::Response) -> Value {
+    serde_json::from_str(&response.text().await.unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn hello_maps_to_json() {
+    let addr = gateway(BasicServiceV1::new()).await;
+
+    let response = post(&addr, "/v1/hello", json!({ "message": "Ada" })).await;
+
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["content-type"], "application/json");
+    let body = json_body(response).await;
+    let event = &body["cloudEvent"];
+    assert_eq!(event["type"], "io.basic.hello");
+    assert_eq!(event["specVersion"], "1.0");
+    assert_eq!(
+        event["protoData"]["@type"],
+        "type.googleapis.com/basic.service.v1.HelloResponseEvent"
+    );
+    assert_eq!(event["protoData"]["greeting"], "Hello, Ada!");
+}
+
+#[tokio::test]
+async fn malformed_json_is_a_bad_request() {
+    let addr = gateway(BasicServiceV1::new()).await;
+
+    for body in [json!({ "message": 42 }), json!({ "unknown": "field" })] {
+        let response = post(&addr, "/v1/hello", body).await;
+        assert_eq!(response.status(), 400);
+        let error = json_body(response).await;
+        assert_eq!(error["code"], 3);
+        assert!(error["message"].as_str().unwrap().contains("HelloRequest"));
+    }
+}
+
+#[tokio::test]
+async fn rpc_errors_map_to_http_statuses() {
+    let service = BasicServiceV1::new().with_workers(WorkerRegistry::new());
+    let addr = gateway(service).await;
+
+    let response = post(&addr, "/v1/background", json!({ "processes": 2 })).await;
+
+    assert_eq!(response.status(), 400);
+    let error = json_body(response).await;
+    assert_eq!(error["code"], 9);
+    assert_eq!(error["message"], "no background workers registered");
+    assert_eq!(error["details"][0]["type"], "google.rpc.ErrorInfo");
+    assert!(error["details"][0]["value"].is_string());
+}
+
+#[tokio::test]
+async fn payload_limits_apply_to_gateway_calls() {
+    let payload = PayloadConfig {
+        max_recv_message_bytes: 64,
+        ..Default::default()
+    };
+    let addr = gateway_with(BasicServiceV1::new(), payload).await;
+
+    let response = post(&addr, "/v1/hello", json!({ "message": "x".repeat(100) })).await;
+
+    assert_eq!(response.status(), 429);
+    let error = json_body(response).await;
+    assert_eq!(error["code"], 8);
+    assert!(error["message"].as_str().unwrap().contains("64 byte limit"));
+}
+
+#[tokio::test]
+async fn idempotency_keys_are_forwarded() {
+    let addr = gateway(BasicServiceV1::new()).await;
+    let client = reqwest::Client::new();
+
+    let mut ids = Vec::new();
+    for _ in 0..2 {
+        let response = client
+            .post(format!("http://{}/v1/hello", addr))
+            .header("idempotency-key", "retry-me")
+            .body(json!({ "message": "Ada" }).to_string())
+            .send()
+            .await
+            .unwrap();
+        let body = json_body(response).await;
+        ids.push(body["cloudEvent"]["id"].as_str().unwrap().to_string());
+    }
+
+    assert_eq!(ids[0], ids[1]);
+}
+
+#[tokio::test]
+async fn accept_language_is_forwarded() {
+    let addr = gateway(BasicServiceV1::new()).await;
+
+    let response = reqwest::Client::new()
+        .post(format!("http://{}/v1/hello", addr))
+        .header("accept-language", "fr-CA, de;q=0.5")
+        .body(json!({ "message": "Ada" }).to_string())
+        .send()
+        .await
+        .unwrap();
+
+    let body = json_body(response).await;
+    let event = &body["cloudEvent"];
+    assert_eq!(event["protoData"]["greeting"], "Bonjour, Ada !");
+    assert_eq!(event["attributes"]["locale"]["ceString"], "fr");
+}
+
+#[tokio::test]
+async fn hello_batch_maps_to_json() {
+    let addr = gateway(BasicServiceV1::new()).await;
+
+    let response = reqwest::Client::new()
+        .post(format!("http://{}/v1/hello:batch", addr))
+        .header("accept-language", "es")
+        .body(json!({ "names": ["Ada", "Grace"] }).to_string())
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+    let body = json_body(response).await;
+    let greetings: Vec<_> = body["events"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|event| event["protoData"]["greeting"].as_str().unwrap())
+        .collect();
+    assert_eq!(greetings, ["¡Hola, Ada!", "¡Hola, Grace!"]);
+
+    let response = post(&addr, "/v1/hello:batch", json!({ "names": [] })).await;
+    assert_eq!(response.status(), 400);
+}
+
+#[tokio::test]
+async fn background_streams_ndjson() {
+    let addr = gateway(BasicServiceV1::new()).await;
+
+    let response = post(&addr, "/v1/background", json!({ "processes": "0" })).await;
+
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["content-type"], "application/x-ndjson");
+    let text = response.text().await.unwrap();
+    let snapshots: Vec<Value> = text
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(snapshots.len(), 2);
+    assert_eq!(
+        snapshots[0]["cloudEvent"]["protoData"]["state"],
+        "STATE_PROCESS"
+    );
+    assert_eq!(
+        snapshots[1]["cloudEvent"]["protoData"]["state"],
+        "STATE_COMPLETE"
+    );
+}
+
+#[tokio::test]
+async fn background_streams_server_sent_events() {
+    let addr = gateway(BasicServiceV1::new()).await;
+
+    let response = reqwest::Client::new()
+        .post(format!("http://{}/v1/background", addr))
+        .header("accept", "text/event-stream")
+        .body("{}")
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+    let text = response.text().await.unwrap();
+    let events: Vec<&str> = text.split("\n\n").filter(|e| !e.is_empty()).collect();
+    assert_eq!(events.len(), 2);
+    for event in events {
+        let (name, data) = event.split_once('\n').unwrap();
+        assert_eq!(name, "event: snapshot");
+        let data: Value = serde_json::from_str(data.strip_prefix("data: ").unwrap()).unwrap();
+        assert!(data["cloudEvent"]["protoData"]["state"].is_string());
+    }
+}
+
+#[tokio::test]
+async fn talk_over_websocket() {
+    let addr = gateway(BasicServiceV1::new()).await;
+    let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/v1/talk", addr))
+        .await
+        .unwrap();
+
+    for (sequence, message) in ["Hello", "I feel tired"].into_iter().enumerate() {
+        socket
+            .send(Message::text(
+                json!({ "message": message, "includeCloudEvent": true }).to_string(),
+            ))
+            .await
+            .unwrap();
+        let Some(Ok(Message::Text(text))) = socket.next().await else {
+            panic!("expected a text frame");
+        };
+        let response: Value = serde_json::from_str(&text).unwrap();
+        let event = &response["cloudEvent"];
+        assert_eq!(event["type"], "io.basic.talk");
+        assert_eq!(event["protoData"]["sequence"], (sequence + 1).to_string());
+        assert!(!event["protoData"]["answer"].as_str().unwrap().is_empty());
+    }
+
+    socket.send(Message::text("not json")).await.unwrap();
+    let Some(Ok(Message::Text(text))) = socket.next().await else {
+        panic!("expected an error frame");
+    };
+    let error: Value = serde_json::from_str(&text).unwrap();
+    assert_eq!(error["code"], 3);
+    assert!(matches!(socket.next().await, Some(Ok(Message::Close(_)))));
+}
@@ -0,0 +1,209 @@
+mod support;
+
+use std::path::PathBuf;
+
+use basic_grpc_service_rust::{
+    client::ClientError,
+    greeting::{Greetings, LOCALE_ATTRIBUTE, sanitize_name},
+    sdk::{
+        basic::{
+            service::v1::{HelloRequest, HelloResponseEvent},
+            v1::basic_service_server::BasicService,
+        },
+        io::cloudevents::v1::{CloudEvent, cloud_event::cloud_event_attribute_value::Attr},
+    },
+    service::BasicServiceV1,
+    utils,
+};
+use support::{TestServer, Transport};
+use tonic::{Code, Request};
+use tonic_types::StatusExt;
+use uuid::Uuid;
+
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!("basic-greetings-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn hello(locale: Option<&str>, headers: &[(&'static str, &str)]) -> Request<HelloRequest> {
+    let mut request = Request::new(HelloRequest {
+        message: "Ada".to_string(),
+        locale: locale.map(str::to_string),
+        ..Default::default()
+    });
+    for (name, value) in headers {
+        request.metadata_mut().insert(*name, value.parse().unwrap());
+    }
+    request
+}
+
+// Greeting and `locale` attribute of the event Hello answers with.
+async fn greet(service: &BasicServiceV1, request: Request<HelloRequest>) -> (String, String) {
+    let event: CloudEvent = service
+        .hello(request)
+        .await
+        .unwrap()
+        .into_inner()
+        .cloud_event
+        .unwrap();
+    let greeting = utils::decode_cloud_event::<HelloResponseEvent>(&event)
+        .unwrap()
+        .greeting;
+    let Some(Attr::CeString(locale)) = event.attributes[LOCALE_ATTRIBUTE].attr.clone() else {
+        panic!("locale attribute is not a string");
+    };
+    (greeting, locale)
+}
+
+#[tokio::test]
+async fn requested_locale_wins() {
+    let service = BasicServiceV1::new();
+
+    let request = hello(Some("de"), &[("accept-language", "fr")]);
+
+    assert_eq!(
+        greet(&service, request).await,
+        ("Hallo, Ada!".to_string(), "de".to_string())
+    );
+}
+
+#[tokio::test]
+async fn regional_tags_fall_back_to_their_language() {
+    let service = BasicServiceV1::new();
+
+    let (greeting, locale) = greet(&service, hello(Some("fr-CA"), &[])).await;
+
+    assert_eq!(greeting, "Bonjour, Ada !");
+    assert_eq!(locale, "fr");
+}
+
+#[tokio::test]
+async fn metadata_is_consulted_by_quality() {
+    let service = BasicServiceV1::new();
+
+    // Unknown requested locale, then the best accepted language with a
+    // template.
+    let request = hello(
+        Some("tlh"),
+        &[("grpc-accept-language", "xx, de;q=0.5, es-MX;q=0.8, *")],
+    );
+    assert_eq!(greet(&service, request).await.1, "es");
+
+    // accept-language is used once grpc-accept-language has no match.
+    let request = hello(
+        None,
+        &[
+            ("grpc-accept-language", "xx"),
+            ("accept-language", "it;q=0.9, pt;q=0"),
+        ],
+    );
+    assert_eq!(greet(&service, request).await.1, "it");
+}
+
+#[tokio::test]
+async fn default_locale_is_the_last_resort() {
+    let greetings = Greetings::new().with_default_locale("nl").unwrap();
+    let service = BasicServiceV1::new().with_greetings(greetings);
+
+    let (greeting, locale) = greet(&service, hello(None, &[("accept-language", "xx")])).await;
+
+    assert_eq!(greeting, "Hallo, Ada!");
+    assert_eq!(locale, "nl");
+}
+
+#[test]
+fn default_locales_need_a_template() {
+    let error = Greetings::new().with_default_locale("xx").unwrap_err();
+    assert!(error.contains("no template for xx"), "{}", error);
+
+    let custom = Greetings::new()
+        .with_template("xx", "Hi {name}")
+        .with_default_locale("XX");
+    assert!(custom.is_ok());
+}
+
+#[tokio::test]
+async fn name_takes_precedence_over_message() {
+    let mut server = TestServer::start(Transport::Duplex).await;
+
+    let hello = server
+        .client
+        .hello(HelloRequest {
+            message: String::new(),
+            name: Some("Grace".to_string()),
+            locale: Some("pt-BR".to_string()),
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(hello.event.greeting, "Olá, Grace!");
+}
+
+#[tokio::test]
+async fn names_are_sanitized() {
+    let service = BasicServiceV1::new();
+    let mut request = hello(None, &[]);
+    request.get_mut().name = Some("<b>Ada</b>\u{7}\r\n\u{202e}'s & co".to_string());
+
+    let (greeting, _) = greet(&service, request).await;
+
+    assert_eq!(greeting, "Hello, &lt;b&gt;Ada&lt;/b&gt;&#39;s &amp; co!");
+    assert_eq!(sanitize_name("\u{0}\u{1b}[31mred\u{9f}"), "[31mred");
+    assert_eq!(sanitize_name("\"Ada\""), "&quot;Ada&quot;");
+}
+
+#[tokio::test]
+async fn invalid_locales_and_blank_names_are_rejected() {
+    let mut server = TestServer::start(Transport::Duplex).await;
+
+    let error = server
+        .client
+        .hello(HelloRequest {
+            message: "Ada".to_string(),
+            name: Some(" ".to_string()),
+            locale: Some("en_US!".to_string()),
+        })
+        .await
+        .unwrap_err();
+
+    let ClientError::Status(status) = error else {
+        panic!("expected a status, got {}", error);
+    };
+    assert_eq!(status.code(), Code::InvalidArgument);
+    let fields: Vec<_> = status
+        .get_details_bad_request()
+        .unwrap()
+        .field_violations
+        .into_iter()
+        .map(|violation| violation.field)
+        .collect();
+    assert_eq!(fields, ["name", "locale"]);
+}
+
+#[tokio::test]
+async fn templates_load_from_a_directory() {
+    let dir = TempDir::new();
+    std::fs::write(dir.0.join("de-ch.txt"), "Grüezi, {name}!\n").unwrap();
+    std::fs::write(dir.0.join("notes.md"), "ignored").unwrap();
+    let service = BasicServiceV1::new().with_greetings(Greetings::new().with_dir(&dir.0).unwrap());
+
+    assert_eq!(
+        greet(&service, hello(Some("de-CH"), &[])).await,
+        ("Grüezi, Ada!".to_string(), "de-ch".to_string())
+    );
+    assert_eq!(greet(&service, hello(Some("de-AT"), &[])).await.1, "de");
+
+    std::fs::write(dir.0.join("fr.txt"), "Salut !").unwrap();
+    assert!(Greetings::new().with_dir(&dir.0).is_err());
+}
@@ -0,0 +1,252 @@
+mod support;
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use basic_grpc_service_rust::{
+    sdk::basic::service::v1::{
+        BackgroundRequest, BackgroundResponse, HelloRequest, HelloResponse, HelloResponseEvent,
+        TalkRequest,
+    },
+    service::BasicServiceV1,
+    utils,
+    web::{AllowedOrigins, CorsConfig},
+};
+use prost::Message;
+use reqwest::{Response, Version};
+use support::{TestServer, Transport};
+
+const DASHBOARD: &str = "https://dashboard.example.com";
+
+async fn web_server() -> TestServer {
+    let cors = CorsConfig {
+        allowed_origins: AllowedOrigins::List(vec![DASHBOARD.to_string()]),
+        ..Default::default()
+    };
+    TestServer::start_configured(Transport::Tcp, BasicServiceV1::new(), |builder| {
+        builder.grpc_web(Some(cors))
+    })
+    .await
+}
+
+// Length-prefixed message frame as gRPC-Web sends it.
+fn frame(message: &impl Message) -> Vec<u8> {
+    let payload = message.encode_to_vec();
+    let mut frame = vec![0];
+    frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&payload);
+    frame
+}
+
+// Splits a gRPC-Web response body into its message frames and the trailers
+// carried in the final frame (flag 0x80).
+fn unframe(mut body: &[u8]) -> (Vec<Vec<u8>>, String) {
+    let mut messages = Vec::new();
+    let mut trailers = String::new();
+    while !body.is_empty() {
+        let flag = body[0];
+        let len = u32::from_be_bytes(body[1..5].try_into().unwrap()) as usize;
+        let payload = &body[5..5 + len];
+        if flag & 0x80 != 0 {
+            trailers = String::from_utf8(payload.to_vec()).unwrap();
+        } else {
+            messages.push(payload.to_vec());
+        }
+        body = &body[5 + len..];
+    }
+    (messages, trailers)
+}
+
+async fn call(server: &TestServer, method: &str, content_type: &str, body: Vec<u8>) -> Response {
+    reqwest::Client::builder()
+        .http1_only()
+        .build()
+        .unwrap()
+        .post(format!(
+            "http://{}/basic.v1.BasicService/{}",
+            server.addr.unwrap(),
+            method
+        ))
+        .header("content-type", content_type)
+        .header("accept", content_type)
+        .header("x-grpc-web", "1")
+        .header("origin", DASHBOARD)
+        .body(body)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn hello_over_grpc_web_on_http1() {
+    let server = web_server().await;
+
+    let response = call(
+        &server,
+        "Hello",
+        "application/grpc-web+proto",
+        frame(&HelloRequest {
+            message: "Browser".to_string(),
+            ..Default::default()
+        }),
+    )
+    .await;
+
+    assert_eq!(response.version(), Version::HTTP_11);
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        response.headers()["content-type"],
+        "application/grpc-web+proto"
+    );
+    assert_eq!(response.headers()["access-control-allow-origin"], DASHBOARD);
+    let (messages, trailers) = unframe(&response.bytes().await.unwrap());
+    assert!(trailers.contains("grpc-status:0"), "{}", trailers);
+    let hello = HelloResponse::decode(messages[0].as_slice()).unwrap();
+    let event: HelloResponseEvent = utils::decode_cloud_event(&hello.cloud_event.unwrap()).unwrap();
+    assert_eq!(event.greeting, "Hello, Browser!");
+}
+
+#[tokio::test]
+async fn hello_over_grpc_web_text() {
+    let server = web_server().await;
+
+    let body = STANDARD.encode(frame(&HelloRequest {
+        message: "Text".to_string(),
+        ..Default::default()
+    }));
+    let response = call(&server, "Hello", "application/grpc-web-text", body.into()).await;
+
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        response.headers()["content-type"],
+        "application/grpc-web-text+proto"
+    );
+    let text = response.text().await.unwrap();
+    // Chunks are encoded separately and may be padded, so decode them one
+    // 4-character quantum at a time.
+    let body: Vec<u8> = text
+        .as_bytes()
+        .chunks(4)
+        .flat_map(|quantum| STANDARD.decode(quantum).unwrap())
+        .collect();
+    let (messages, trailers) = unframe(&body);
+    assert!(trailers.contains("grpc-status:0"), "{}", trailers);
+    assert_eq!(messages.len(), 1);
+}
+
+#[tokio::test]
+async fn background_streams_frames_over_grpc_web() {
+    let server = web_server().await;
+
+    let response = call(
+        &server,
+        "Background",
+        "application/grpc-web+proto",
+        frame(&BackgroundRequest { processes: 0 }),
+    )
+    .await;
+
+    assert_eq!(response.status(), 200);
+    let (messages, trailers) = unframe(&response.bytes().await.unwrap());
+    assert!(trailers.contains("grpc-status:0"), "{}", trailers);
+    // initial and final snapshot
+    assert_eq!(messages.len(), 2);
+    for message in messages {
+        BackgroundResponse::decode(message.as_slice()).unwrap();
+    }
+}
+
+#[tokio::test]
+async fn talk_over_grpc_web_is_unimplemented() {
+    let server = web_server().await;
+
+    let response = call(
+        &server,
+        "Talk",
+        "application/grpc-web+proto",
+        frame(&TalkRequest {
+            message: "hi".to_string(),
+            ..Default::default()
+        }),
+    )
+    .await;
+
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        response.headers()["content-type"],
+        "application/grpc-web+proto"
+    );
+    assert_eq!(response.headers()["grpc-status"], "12");
+    let message = response.headers()["grpc-message"].to_str().unwrap();
+    assert!(message.contains("Talk"), "{}", message);
+}
+
+#[tokio::test]
+async fn preflight_allows_configured_origins_only() {
+    let server = web_server().await;
+    let client = reqwest::Client::new();
+    let url = format!(
+        "http://{}/basic.v1.BasicService/Hello",
+        server.addr.unwrap()
+    );
+
+    let allowed = client
+        .request(reqwest::Method::OPTIONS, &url)
+        .header("origin", DASHBOARD)
+        .header("access-control-request-method", "POST")
+        .header(
+            "access-control-request-headers",
+            "content-type,x-grpc-web,idempotency-key",
+        )
+        .send()
+        .await
+        .unwrap();
+    assert!(allowed.status().is_success());
+    assert_eq!(allowed.headers()["access-control-allow-origin"], DASHBOARD);
+    let headers = allowed.headers()["access-control-allow-headers"]
+        .to_str()
+        .unwrap();
+    assert!(headers.contains("x-grpc-web"), "{}", headers);
+    assert!(headers.contains("idempotency-key"), "{}", headers);
+
+    let denied = client
+        .request(reqwest::Method::OPTIONS, &url)
+        .header("origin", "https://evil.example.com")
+        .header("access-control-request-method", "POST")
+        .send()
+        .await
+        .unwrap();
+    assert!(
+        denied
+            .headers()
+            .get("access-control-allow-origin")
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn native_grpc_keeps_working_alongside_grpc_web() {
+    let mut server = web_server().await;
+
+    let hello = server.client.hello("HTTP/2").await.unwrap();
+
+    assert_eq!(hello.event.greeting, "Hello, HTTP/2!");
+}
+
+#[tokio::test]
+async fn http1_is_refused_without_grpc_web() {
+    let server = TestServer::start(Transport::Tcp).await;
+
+    let result = reqwest::Client::builder()
+        .http1_only()
+        .build()
+        .unwrap()
+        .post(format!(
+            "http://{}/basic.v1.BasicService/Hello",
+            server.addr.unwrap()
+        ))
+        .header("content-type", "application/grpc-web+proto")
+        .body(frame(&HelloRequest::default()))
+        .send()
+        .await;
+
+    assert!(result.is_err() || !result.unwrap().status().is_success());
+}
@@ -0,0 +1,47 @@
+mod support;
+
+use basic_grpc_service_rust::sdk::io::cloudevents::v1::cloud_event::Data;
+use support::{TestServer, Transport};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn hello_returns_greeting_cloud_event() {
+    let mut server = TestServer::start(Transport::Duplex).await;
+
+    let hello = server.client.hello("World").await.unwrap();
+
+    assert_eq!(hello.event.greeting, "Hello, World!");
+    let event = &hello.cloud_event;
+    assert!(Uuid::parse_str(&event.id).is_ok());
+    assert_eq!(event.spec_version, "1.0");
+    assert_eq!(event.r#type, "io.basic.hello");
+    assert_eq!(event.source, "/basic/hello");
+    match &event.data {
+        Some(Data::ProtoData(any)) => {
+            assert!(
+                any.type_url
+                    .ends_with("basic.service.v1.HelloResponseEvent")
+            )
+        }
+        other => panic!("unexpected data: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn hello_emits_unique_ids() {
+    let mut server = TestServer::start(Transport::Tcp).await;
+
+    let first = server.client.hello("a").await.unwrap();
+    let second = server.client.hello("a").await.unwrap();
+
+    assert_ne!(first.cloud_event.id, second.cloud_event.id);
+}
+
+#[tokio::test]
+async fn hello_over_tls() {
+    let mut server = TestServer::start(Transport::Tls).await;
+
+    let hello = server.client.hello("TLS").await.unwrap();
+
+    assert_eq!(hello.event.greeting, "Hello, TLS!");
+}
@@ -0,0 +1,143 @@
+mod support;
+
+use basic_grpc_service_rust::{
+    client::ClientError,
+    greeting::LOCALE_ATTRIBUTE,
+    sdk::{
+        basic::service::v1::{HelloBatchRequest, SubscribeRequest},
+        io::cloudevents::v1::cloud_event::cloud_event_attribute_value::Attr,
+    },
+    validation::MAX_BATCH_NAMES,
+};
+use support::{TestServer, Transport};
+use tokio_stream::StreamExt;
+use tonic::Code;
+use tonic_types::StatusExt;
+
+fn batch(names: &[&str], locale: Option<&str>) -> HelloBatchRequest {
+    HelloBatchRequest {
+        names: names.iter().map(|name| name.to_string()).collect(),
+        locale: locale.map(str::to_string),
+    }
+}
+
+fn violated_fields(error: ClientError) -> Vec<String> {
+    let ClientError::Status(status) = error else {
+        panic!("expected a status, got {}", error);
+    };
+    assert_eq!(status.code(), Code::InvalidArgument);
+    status
+        .get_details_bad_request()
+        .unwrap()
+        .field_violations
+        .into_iter()
+        .map(|violation| violation.field)
+        .collect()
+}
+
+#[tokio::test]
+async fn batches_greet_every_name_in_order() {
+    let mut server = TestServer::start(Transport::Duplex).await;
+
+    let events = server
+        .client
+        .hello_batch(batch(&["Ada", "Grace", "Linus"], Some("de-AT")))
+        .await
+        .unwrap();
+
+    let greetings: Vec<_> = events.iter().map(|e| e.event.greeting.as_str()).collect();
+    assert_eq!(greetings, ["Hallo, Ada!", "Hallo, Grace!", "Hallo, Linus!"]);
+    for event in &events {
+        assert_eq!(event.cloud_event.r#type, "io.basic.hello");
+        let attr = event.cloud_event.attributes[LOCALE_ATTRIBUTE].attr.clone();
+        assert_eq!(attr, Some(Attr::CeString("de".to_string())));
+    }
+    assert_ne!(events[0].cloud_event.id, events[1].cloud_event.id);
+}
+
+#[tokio::test]
+async fn streams_answer_each_batch() {
+    let mut server = TestServer::start(Transport::Duplex).await;
+    let batches = tokio_stream::iter([
+        batch(&["Ada"], None),
+        batch(&["Grace", "Linus"], Some("fr")),
+    ]);
+
+    let results: Vec<_> = server
+        .client
+        .hello_batch_stream(batches)
+        .await
+        .unwrap()
+        .collect()
+        .await;
+
+    let sizes: Vec<_> = results.iter().map(|r| r.as_ref().unwrap().len()).collect();
+    assert_eq!(sizes, [1, 2]);
+    assert_eq!(
+        results[1].as_ref().unwrap()[1].event.greeting,
+        "Bonjour, Linus !"
+    );
+}
+
+#[tokio::test]
+async fn invalid_batches_are_rejected() {
+    let mut server = TestServer::start(Transport::Duplex).await;
+
+    let error = server
+        .client
+        .hello_batch(batch(&[], None))
+        .await
+        .unwrap_err();
+    assert_eq!(violated_fields(error), ["names"]);
+
+    let names = vec!["Ada"; MAX_BATCH_NAMES + 1];
+    let error = server
+        .client
+        .hello_batch(batch(&names, None))
+        .await
+        .unwrap_err();
+    assert_eq!(violated_fields(error), ["names"]);
+
+    let error = server
+        .client
+        .hello_batch(batch(&["Ada", " "], Some("x_y")))
+        .await
+        .unwrap_err();
+    assert_eq!(violated_fields(error), ["names", "locale"]);
+}
+
+#[tokio::test]
+async fn invalid_batches_end_the_stream() {
+    let mut server = TestServer::start(Transport::Duplex).await;
+    let batches = tokio_stream::iter([batch(&["Ada"], None), batch(&[], None)]);
+
+    let mut results = server.client.hello_batch_stream(batches).await.unwrap();
+
+    assert_eq!(results.next().await.unwrap().unwrap().len(), 1);
+    assert_eq!(
+        violated_fields(results.next().await.unwrap().unwrap_err()),
+        ["names"]
+    );
+    assert!(results.next().await.is_none());
+}
+
+#[tokio::test]
+async fn batch_greetings_are_published() {
+    let mut server = TestServer::start(Transport::Duplex).await;
+    let mut subscriber = server.client.clone();
+    let mut events = subscriber
+        .subscribe(SubscribeRequest::default())
+        .await
+        .unwrap();
+
+    let greeted = server
+        .client
+        .hello_batch(batch(&["Ada", "Grace"], None))
+        .await
+        .unwrap();
+
+    for event in greeted {
+        let published = events.next().await.unwrap().unwrap();
+        assert_eq!(published.id, event.cloud_event.id);
+    }
+}
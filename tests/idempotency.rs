@@ -0,0 +1,214 @@
+mod support;
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use basic_grpc_service_rust::{
+    clock::ManualClock,
+    idempotency::IDEMPOTENCY_KEY_HEADER,
+    sdk::basic::{
+        service::v1::{HelloRequest, SomeServiceResponse, State},
+        v1::basic_service_server::BasicService,
+    },
+    service::BasicServiceV1,
+    state::StateManager,
+    workers::{Worker, WorkerRegistry},
+};
+use support::{TestServer, Transport};
+use tokio::sync::Semaphore;
+use tokio_stream::StreamExt;
+use tonic::{Code, Request, transport::server::TcpConnectInfo};
+use tonic_types::StatusExt;
+
+fn hello(message: &str, key: Option<&str>) -> Request<HelloRequest> {
+    let mut request = Request::new(HelloRequest {
+        message: message.to_string(),
+        ..Default::default()
+    });
+    if let Some(key) = key {
+        request
+            .metadata_mut()
+            .insert(IDEMPOTENCY_KEY_HEADER, key.parse().unwrap());
+    }
+    request
+}
+
+async fn hello_id(service: &BasicServiceV1, message: &str, key: Option<&str>) -> String {
+    service
+        .hello(hello(message, key))
+        .await
+        .unwrap()
+        .into_inner()
+        .cloud_event
+        .unwrap()
+        .id
+}
+
+// Counts its calls and blocks until the test hands out permits.
+#[derive(Debug, Clone)]
+struct GatedWorker {
+    calls: Arc<AtomicUsize>,
+    gate: Arc<Semaphore>,
+}
+
+#[tonic::async_trait]
+impl Worker for GatedWorker {
+    async fn call(&self, index: usize) -> SomeServiceResponse {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        self.gate.acquire().await.unwrap().forget();
+        SomeServiceResponse {
+            id: index.to_string(),
+            name: format!("gated-{}", index),
+            version: "test".to_string(),
+            data: None,
+        }
+    }
+}
+
+#[tokio::test]
+async fn hello_with_the_same_key_returns_the_cached_event() {
+    let service = BasicServiceV1::new();
+
+    let first = hello_id(&service, "Ada", Some("key-1")).await;
+    let retry = hello_id(&service, "Ada", Some("key-1")).await;
+    let other = hello_id(&service, "Ada", Some("key-2")).await;
+    let unkeyed = hello_id(&service, "Ada", None).await;
+
+    assert_eq!(first, retry);
+    assert_ne!(first, other);
+    assert_ne!(first, unkeyed);
+    assert_eq!(service.state_manager().len(), 2);
+}
+
+#[tokio::test]
+async fn hello_keys_expire_with_the_job_state_ttl() {
+    let clock = ManualClock::default();
+    let service = BasicServiceV1::new()
+        .with_clock(Arc::new(clock.clone()))
+        .with_state_manager(
+            StateManager::new()
+                .with_clock(Arc::new(clock.clone()))
+                .with_ttl(Duration::from_secs(60)),
+        );
+
+    let first = hello_id(&service, "Ada", Some("key")).await;
+    clock.advance(Duration::from_secs(30));
+    assert_eq!(hello_id(&service, "Ada", Some("key")).await, first);
+
+    clock.advance(Duration::from_secs(31));
+    assert_ne!(hello_id(&service, "Ada", Some("key")).await, first);
+}
+
+#[tokio::test]
+async fn keys_reused_for_another_request_are_rejected() {
+    let service = BasicServiceV1::new();
+
+    hello_id(&service, "Ada", Some("key")).await;
+    let status = service
+        .hello(hello("Grace", Some("key")))
+        .await
+        .unwrap_err();
+
+    assert_eq!(status.code(), Code::FailedPrecondition);
+    let info = status.get_details_error_info().unwrap();
+    assert_eq!(info.reason, "IDEMPOTENCY_KEY_REUSED");
+}
+
+#[tokio::test]
+async fn anonymous_keys_are_scoped_to_the_peer() {
+    let service = BasicServiceV1::new();
+    let from = |ip: &str| {
+        let mut request = hello("Ada", Some("key"));
+        request.extensions_mut().insert(TcpConnectInfo {
+            local_addr: None,
+            remote_addr: Some(format!("{}:4000", ip).parse().unwrap()),
+        });
+        request
+    };
+
+    let mut ids = Vec::new();
+    for ip in ["10.0.0.1", "10.0.0.2", "10.0.0.1"] {
+        let response = service.hello(from(ip)).await.unwrap();
+        ids.push(response.into_inner().cloud_event.unwrap().id);
+    }
+
+    assert_ne!(ids[0], ids[1]);
+    assert_eq!(ids[0], ids[2]);
+}
+
+#[tokio::test]
+async fn malformed_keys_are_rejected() {
+    let service = BasicServiceV1::new();
+    let too_long = "k".repeat(256);
+
+    for key in ["", too_long.as_str()] {
+        let status = service.hello(hello("Ada", Some(key))).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+    }
+}
+
+#[tokio::test]
+async fn background_retry_attaches_to_the_running_job() {
+    let worker = GatedWorker {
+        calls: Arc::new(AtomicUsize::new(0)),
+        gate: Arc::new(Semaphore::new(0)),
+    };
+    let workers = WorkerRegistry::new()
+        .register(worker.clone())
+        .register(worker.clone());
+    let service = BasicServiceV1::new().with_workers(workers);
+    let state = service.state_manager().clone();
+    let mut server = TestServer::start_with(Transport::Duplex, service).await;
+
+    // The first stream breaks after the initial snapshot.
+    let mut first = server.client.background_with_key(2, "job-1").await.unwrap();
+    let snapshot = first.next().await.unwrap().unwrap();
+    assert_eq!(snapshot.event.state, State::Process as i32);
+    drop(first);
+
+    let mut retry = server.client.background_with_key(2, "job-1").await.unwrap();
+    let attached = retry.next().await.unwrap().unwrap();
+    assert_eq!(attached.event.state, State::Process as i32);
+    assert_eq!(attached.event.started_at, snapshot.event.started_at);
+
+    worker.gate.add_permits(2);
+    let last = retry
+        .map(|update| update.unwrap().event)
+        .collect::<Vec<_>>()
+        .await;
+    let last = last.last().unwrap();
+    assert_eq!(last.state, State::Complete as i32);
+    assert_eq!(last.responses.len(), 2);
+    assert_eq!(worker.calls.load(Ordering::SeqCst), 2);
+    assert_eq!(state.len(), 1);
+}
+
+#[tokio::test]
+async fn background_retry_after_completion_replays_the_result() {
+    let mut server = TestServer::start(Transport::Duplex).await;
+
+    let first: Vec<_> = server
+        .client
+        .background_with_key(0, "job-1")
+        .await
+        .unwrap()
+        .map(|update| update.unwrap().event)
+        .collect()
+        .await;
+    let retry: Vec<_> = server
+        .client
+        .background_with_key(0, "job-1")
+        .await
+        .unwrap()
+        .map(|update| update.unwrap().event)
+        .collect()
+        .await;
+
+    assert_eq!(retry.len(), 1);
+    assert_eq!(retry[0], *first.last().unwrap());
+}
@@ -0,0 +1,197 @@
+mod support;
+
+use std::io::Read;
+
+use basic_grpc_service_rust::{
+    client::{BasicClient, ClientConfig, ClientError},
+    payload::PayloadConfig,
+    sdk::basic::service::v1::{HelloRequest, HelloResponse, HelloResponseEvent},
+    service::BasicServiceV1,
+    utils,
+    web::CorsConfig,
+};
+use flate2::read::GzDecoder;
+use prost::Message;
+use support::{TestServer, Transport};
+use tokio_stream::StreamExt;
+use tonic::{Code, codec::CompressionEncoding};
+use tonic_types::StatusExt;
+
+async fn server(payload: PayloadConfig) -> TestServer {
+    TestServer::start_configured(Transport::Tcp, BasicServiceV1::new(), |builder| {
+        builder
+            .payload(payload)
+            .grpc_web(Some(CorsConfig::default()))
+    })
+    .await
+}
+
+async fn client(server: &TestServer, compression: CompressionEncoding) -> BasicClient {
+    BasicClient::connect(ClientConfig {
+        endpoint: format!("http://{}", server.addr.unwrap()),
+        ca_cert: None,
+        compression: Some(compression),
+        ..Default::default()
+    })
+    .await
+    .unwrap()
+}
+
+// Calls Hello over gRPC-Web, which leaves the message frames as the server
+// wrote them, and returns the flags, payload and `grpc-encoding` header.
+async fn raw_hello(
+    server: &TestServer,
+    message: &str,
+    accept_encoding: &str,
+) -> (u8, Vec<u8>, Option<String>) {
+    let payload = HelloRequest {
+        message: message.to_string(),
+        ..Default::default()
+    }
+    .encode_to_vec();
+    let mut body = vec![0];
+    body.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    body.extend_from_slice(&payload);
+
+    let response = reqwest::Client::new()
+        .post(format!(
+            "http://{}/basic.v1.BasicService/Hello",
+            server.addr.unwrap()
+        ))
+        .header("content-type", "application/grpc-web+proto")
+        .header("grpc-accept-encoding", accept_encoding)
+        .body(body)
+        .send()
+        .await
+        .unwrap();
+    let encoding = response
+        .headers()
+        .get("grpc-encoding")
+        .map(|value| value.to_str().unwrap().to_string());
+    let body = response.bytes().await.unwrap();
+    let len = u32::from_be_bytes(body[1..5].try_into().unwrap()) as usize;
+    (body[0], body[5..5 + len].to_vec(), encoding)
+}
+
+fn greeting(message: &[u8]) -> String {
+    let hello = HelloResponse::decode(message).unwrap();
+    let event: HelloResponseEvent = utils::decode_cloud_event(&hello.cloud_event.unwrap()).unwrap();
+    event.greeting
+}
+
+#[tokio::test]
+async fn small_responses_stay_uncompressed() {
+    let server = server(PayloadConfig::default()).await;
+
+    let (flags, message, encoding) = raw_hello(&server, "Ada", "gzip").await;
+
+    assert_eq!(encoding.as_deref(), Some("gzip"));
+    assert_eq!(flags, 0);
+    assert_eq!(greeting(&message), "Hello, Ada!");
+}
+
+#[tokio::test]
+async fn large_responses_are_compressed() {
+    let server = server(PayloadConfig::default()).await;
+    let name = "Ada ".repeat(500);
+
+    let (flags, message, encoding) = raw_hello(&server, &name, "gzip").await;
+
+    assert_eq!(encoding.as_deref(), Some("gzip"));
+    assert_eq!(flags, 1);
+    let mut decompressed = Vec::new();
+    GzDecoder::new(message.as_slice())
+        .read_to_end(&mut decompressed)
+        .unwrap();
+    assert_eq!(greeting(&decompressed), format!("Hello, {}!", name));
+}
+
+#[tokio::test]
+async fn server_preference_picks_the_encoding() {
+    let server = server(PayloadConfig::default()).await;
+    let name = "Ada ".repeat(500);
+
+    let (flags, message, encoding) = raw_hello(&server, &name, "gzip, zstd").await;
+
+    assert_eq!(encoding.as_deref(), Some("zstd"));
+    assert_eq!(flags, 1);
+    let decompressed = zstd::decode_all(message.as_slice()).unwrap();
+    assert_eq!(greeting(&decompressed), format!("Hello, {}!", name));
+}
+
+#[tokio::test]
+async fn threshold_and_encodings_are_configurable() {
+    let server = server(PayloadConfig {
+        compression: vec![CompressionEncoding::Gzip],
+        compression_min_bytes: 1024 * 1024,
+        ..Default::default()
+    })
+    .await;
+    let name = "Ada ".repeat(500);
+
+    let (flags, _, _) = raw_hello(&server, &name, "gzip").await;
+    assert_eq!(flags, 0);
+
+    let (flags, _, encoding) = raw_hello(&server, &name, "zstd").await;
+    assert_eq!(flags, 0);
+    assert_eq!(encoding, None);
+}
+
+#[tokio::test]
+async fn clients_negotiate_compression_per_call() {
+    let server = server(PayloadConfig {
+        compression_min_bytes: 0,
+        ..Default::default()
+    })
+    .await;
+
+    for encoding in [CompressionEncoding::Gzip, CompressionEncoding::Zstd] {
+        let mut client = client(&server, encoding).await;
+
+        let hello = client.hello("Compressed").await.unwrap();
+        assert_eq!(hello.event.greeting, "Hello, Compressed!");
+
+        let snapshots: Vec<_> = client.background(2).await.unwrap().collect().await;
+        assert!(snapshots.iter().all(Result::is_ok));
+    }
+}
+
+#[tokio::test]
+async fn oversized_requests_are_rejected() {
+    let mut server = server(PayloadConfig {
+        max_recv_message_bytes: 64,
+        ..Default::default()
+    })
+    .await;
+
+    let error = server.client.hello("x".repeat(100)).await.unwrap_err();
+
+    let ClientError::Status(status) = error else {
+        panic!("expected a status, got {}", error);
+    };
+    assert_eq!(status.code(), Code::ResourceExhausted);
+    assert!(
+        status.message().contains("64 byte limit"),
+        "{}",
+        status.message()
+    );
+    let info = status.get_details_error_info().unwrap();
+    assert_eq!(info.reason, "MESSAGE_TOO_LARGE");
+    assert_eq!(info.metadata["limit"], "64");
+}
+
+#[tokio::test]
+async fn oversized_responses_fail() {
+    let mut server = server(PayloadConfig {
+        max_send_message_bytes: 64,
+        ..Default::default()
+    })
+    .await;
+
+    let error = server.client.hello("Ada").await.unwrap_err();
+
+    let ClientError::Status(status) = error else {
+        panic!("expected a status, got {}", error);
+    };
+    assert_eq!(status.code(), Code::OutOfRange);
+}
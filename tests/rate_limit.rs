@@ -0,0 +1,106 @@
+use std::{
+    collections::HashSet,
+    net::{IpAddr, SocketAddr},
+    time::Duration,
+};
+
+use basic_grpc_service_rust::{
+    auth::{AuthMethod, Principal},
+    error::ServiceError,
+    rate_limit::{RateLimiter, TalkLimits},
+};
+use tonic::transport::server::TcpConnectInfo;
+
+const PROXY: &str = "10.0.0.1";
+
+fn limiter() -> RateLimiter {
+    RateLimiter::new(TalkLimits {
+        connection_rate: 1.0,
+        connection_burst: 1.0,
+        identity_rate: 1.0,
+        identity_burst: 1.0,
+        trusted_proxies: vec![PROXY.parse().unwrap()],
+        ..TalkLimits::default()
+    })
+}
+
+fn request(peer: &str, port: u16, client_id: Option<&str>) -> tonic::Request<()> {
+    let mut request = tonic::Request::new(());
+    let ip: IpAddr = peer.parse().unwrap();
+    request.extensions_mut().insert(TcpConnectInfo {
+        local_addr: None,
+        remote_addr: Some(SocketAddr::new(ip, port)),
+    });
+    if let Some(client_id) = client_id {
+        request
+            .metadata_mut()
+            .insert("x-client-id", client_id.parse().unwrap());
+    }
+    request
+}
+
+#[test]
+fn untrusted_peers_cannot_pick_their_identity() {
+    let limiter = limiter();
+
+    let (_, a) = limiter.keys(&request("192.0.2.7", 1000, Some("a")));
+    let (_, b) = limiter.keys(&request("192.0.2.7", 1000, Some("b")));
+
+    assert_eq!(a, b);
+}
+
+#[test]
+fn trusted_proxies_name_the_identity() {
+    let limiter = limiter();
+
+    let (_, a) = limiter.keys(&request(PROXY, 1000, Some("a")));
+    let (_, b) = limiter.keys(&request(PROXY, 1000, Some("b")));
+    let (_, none) = limiter.keys(&request(PROXY, 1000, None));
+
+    assert_ne!(a, b);
+    assert_ne!(a, none);
+}
+
+#[test]
+fn principals_take_precedence_over_the_header() {
+    let limiter = limiter();
+    let mut request = request(PROXY, 1000, Some("client"));
+    request.extensions_mut().insert(Principal {
+        subject: "client".to_string(),
+        scopes: HashSet::new(),
+        method: AuthMethod::ApiKey,
+    });
+
+    let (_, identity) = limiter.keys(&request);
+    let (_, header) = limiter.keys(&self::request(PROXY, 1000, Some("client")));
+
+    assert_ne!(identity, header);
+}
+
+#[test]
+fn rejected_messages_do_not_charge_the_other_bucket() {
+    let limiter = limiter();
+
+    limiter.check_message("first", "shared", "hi").unwrap();
+    let error = limiter.check_message("second", "shared", "hi").unwrap_err();
+    assert!(matches!(error, ServiceError::RateLimited { .. }));
+
+    // The second connection still has its token for another identity.
+    limiter.check_message("second", "other", "hi").unwrap();
+}
+
+#[test]
+fn rate_limited_errors_report_the_longest_wait() {
+    let limiter = limiter();
+    limiter
+        .check_message("connection", "identity", "hi")
+        .unwrap();
+
+    let Err(ServiceError::RateLimited { retry_after }) =
+        limiter.check_message("connection", "identity", "hi")
+    else {
+        panic!("expected a rate limit");
+    };
+
+    assert!(retry_after > Duration::ZERO && retry_after <= Duration::from_secs(1));
+}
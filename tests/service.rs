@@ -0,0 +1,93 @@
+mod support;
+
+use std::sync::Arc;
+
+use basic_grpc_service_rust::{
+    sdk::basic::service::v1::{SomeServiceResponse, State},
+    service::BasicServiceV1,
+    talk::{Answer, ConversationBackend, ConversationSession},
+    workers::{Worker, WorkerRegistry},
+};
+use support::{TestServer, Transport};
+use tokio_stream::StreamExt;
+
+#[derive(Debug)]
+struct InstantWorker(&'static str);
+
+#[tonic::async_trait]
+impl Worker for InstantWorker {
+    async fn call(&self, index: usize) -> SomeServiceResponse {
+        SomeServiceResponse {
+            id: index.to_string(),
+            name: format!("{}-{}", self.0, index),
+            version: "test".to_string(),
+            data: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Echo;
+
+struct EchoSession;
+
+impl ConversationBackend for Echo {
+    fn start(&self, _seed: u64) -> Box<dyn ConversationSession> {
+        Box::new(EchoSession)
+    }
+}
+
+impl ConversationSession for EchoSession {
+    fn reply(&mut self, input: &str) -> Answer {
+        Answer {
+            text: input.to_string(),
+            ended: input == "stop",
+            rule: "echo".to_string(),
+            fragment: None,
+        }
+    }
+}
+
+#[tokio::test]
+async fn background_uses_injected_workers() {
+    let workers = WorkerRegistry::new()
+        .register(InstantWorker("alpha"))
+        .register(InstantWorker("beta"));
+    let service = BasicServiceV1::new().with_workers(workers);
+    let mut server = TestServer::start_with(Transport::Duplex, service).await;
+
+    let last = server
+        .client
+        .background(4)
+        .await
+        .unwrap()
+        .map(|update| update.unwrap().event)
+        .fold(None, |_, event| Some(event))
+        .await
+        .unwrap();
+
+    let mut names: Vec<_> = last.responses.iter().map(|r| r.name.clone()).collect();
+    names.sort();
+    assert_eq!(names, ["alpha-1", "alpha-3", "beta-2", "beta-4"]);
+    assert_eq!(last.state, State::Complete as i32);
+}
+
+#[tokio::test]
+async fn talk_uses_injected_conversation_backend() {
+    let service = BasicServiceV1::new().with_conversations(Arc::new(Echo));
+    let mut server = TestServer::start_with(Transport::Duplex, service).await;
+    let mut talk = server.client.talk().await.unwrap();
+
+    let answer = talk.ask("ping").await.unwrap();
+    assert_eq!(answer.answer, "ping");
+    assert_eq!(answer.rule, "echo");
+    assert!(talk.ask("stop").await.unwrap().ended);
+}
+
+#[tokio::test]
+async fn background_without_workers_is_rejected() {
+    let service = BasicServiceV1::new().with_workers(WorkerRegistry::new());
+    let mut server = TestServer::start_with(Transport::Duplex, service).await;
+
+    assert!(server.client.background(1).await.is_err());
+}
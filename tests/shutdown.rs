@@ -0,0 +1,41 @@
+mod support;
+
+use std::time::Duration;
+
+use support::{TestServer, Transport};
+
+#[tokio::test]
+async fn shutdown_stops_server_gracefully() {
+    let mut server = TestServer::start(Transport::Tcp).await;
+    server.client.hello("before").await.unwrap();
+
+    let mut client = server.client.clone();
+    tokio::time::timeout(Duration::from_secs(5), server.shutdown())
+        .await
+        .expect("server did not shut down in time")
+        .unwrap();
+
+    assert!(client.hello("after").await.is_err());
+}
+
+#[tokio::test]
+async fn shutdown_waits_for_in_flight_talk() {
+    let mut server = TestServer::start(Transport::Duplex).await;
+    let mut talk = server.client.talk().await.unwrap();
+    talk.ask("hello").await.unwrap();
+
+    let client = server.client.clone();
+    let shutdown = tokio::spawn(server.shutdown());
+
+    // the open stream still gets answers while the server drains
+    let answer = talk.ask("bye").await.unwrap();
+    assert!(answer.ended);
+    drop(talk.close());
+    drop(client);
+
+    tokio::time::timeout(Duration::from_secs(5), shutdown)
+        .await
+        .expect("server did not drain in time")
+        .unwrap()
+        .unwrap();
+}
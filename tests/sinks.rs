@@ -0,0 +1,310 @@
+mod support;
+
+use std::{
+    collections::VecDeque,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use axum::{
+    Router,
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    routing::post,
+};
+use basic_grpc_service_rust::{
+    events::{
+        BrokerSink, EventBus, EventSink, FileSink, InMemoryBroker, RetryPolicy, SharedSink,
+        SinkDispatcher, SinkError, SinkOptions, WebhookMode, WebhookSink,
+    },
+    sdk::{
+        basic::service::v1::HelloResponseEvent,
+        io::cloudevents::v1::{CloudEvent, cloud_event::Data},
+    },
+    service::BasicServiceV1,
+};
+use prost::Message;
+use support::{TestServer, Transport};
+use uuid::Uuid;
+
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new() -> Self {
+        Self(std::env::temp_dir().join(format!("basic-sinks-{}", Uuid::new_v4())))
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[derive(Clone, Default)]
+struct Webhook {
+    requests: Arc<Mutex<Vec<(HeaderMap, Bytes)>>>,
+    statuses: Arc<Mutex<VecDeque<StatusCode>>>,
+}
+
+async fn receive(State(hook): State<Webhook>, headers: HeaderMap, body: Bytes) -> StatusCode {
+    hook.requests.lock().unwrap().push((headers, body));
+    hook.statuses
+        .lock()
+        .unwrap()
+        .pop_front()
+        .unwrap_or(StatusCode::NO_CONTENT)
+}
+
+// Starts a webhook receiver answering with `statuses` in turn, then 204.
+async fn webhook(statuses: &[StatusCode]) -> (String, Webhook) {
+    let hook = Webhook {
+        statuses: Arc::new(Mutex::new(statuses.iter().copied().collect())),
+        ..Default::default()
+    };
+    let app = Router::new()
+        .route("/events", post(receive))
+        .with_state(hook.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/events", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    (url, hook)
+}
+
+fn hello_event() -> CloudEvent {
+    let greeting = HelloResponseEvent {
+        greeting: "Hello, Ada!".to_string(),
+    };
+    CloudEvent {
+        id: Uuid::new_v4().to_string(),
+        source: "/basic/hello".to_string(),
+        spec_version: "1.0".to_string(),
+        r#type: "io.basic.hello".to_string(),
+        attributes: Default::default(),
+        data: Some(Data::ProtoData(prost_types::Any {
+            type_url: "type.googleapis.com/basic.service.v1.HelloResponseEvent".to_string(),
+            value: greeting.encode_to_vec(),
+        })),
+    }
+}
+
+fn fast_retries(dead_letter: Option<SharedSink>) -> SinkOptions {
+    SinkOptions {
+        retry: RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(5),
+        },
+        dead_letter,
+        ..Default::default()
+    }
+}
+
+// Never finishes a delivery.
+#[derive(Debug)]
+struct StalledSink;
+
+#[tonic::async_trait]
+impl EventSink for StalledSink {
+    fn name(&self) -> &str {
+        "stalled"
+    }
+
+    async fn deliver(&self, _event: &CloudEvent) -> Result<(), SinkError> {
+        std::future::pending().await
+    }
+}
+
+async fn eventually(mut done: impl FnMut() -> bool) {
+    for _ in 0..200 {
+        if done() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("condition not met in time");
+}
+
+#[tokio::test]
+async fn file_sink_writes_json_lines_and_rotates() {
+    let dir = TempDir::new();
+    let path = dir.0.join("events.jsonl");
+    let sink = FileSink::new(&path).with_rotation(400, 2);
+
+    for _ in 0..6 {
+        sink.deliver(&hello_event()).await.unwrap();
+    }
+
+    let mut rotated = path.clone().into_os_string();
+    rotated.push(".1");
+    assert!(std::fs::metadata(&rotated).is_ok());
+    rotated.push("0");
+    assert!(std::fs::metadata(&rotated).is_err());
+
+    let current = std::fs::read_to_string(&path).unwrap();
+    let line: serde_json::Value = serde_json::from_str(current.lines().next().unwrap()).unwrap();
+    assert_eq!(line["specversion"], "1.0");
+    assert_eq!(line["type"], "io.basic.hello");
+    assert_eq!(line["datacontenttype"], "application/protobuf");
+    assert_eq!(
+        line["dataschema"],
+        "urn:proto:basic.service.v1.HelloResponseEvent"
+    );
+    assert!(line["data_base64"].is_string());
+}
+
+#[tokio::test]
+async fn webhook_binary_mode_sends_ce_headers_and_payload() {
+    let (url, hook) = webhook(&[]).await;
+    let event = hello_event();
+    let sink = WebhookSink::new(url, WebhookMode::Binary).unwrap();
+
+    sink.deliver(&event).await.unwrap();
+
+    let requests = hook.requests.lock().unwrap();
+    let (headers, body) = &requests[0];
+    assert_eq!(headers["ce-id"], event.id.as_str());
+    assert_eq!(headers["ce-type"], "io.basic.hello");
+    assert_eq!(headers["ce-source"], "/basic/hello");
+    assert_eq!(headers["ce-specversion"], "1.0");
+    assert_eq!(headers["content-type"], "application/protobuf");
+    let greeting = HelloResponseEvent::decode(body.as_ref()).unwrap();
+    assert_eq!(greeting.greeting, "Hello, Ada!");
+}
+
+#[tokio::test]
+async fn webhook_structured_mode_sends_cloudevents_json() {
+    let (url, hook) = webhook(&[]).await;
+    let event = hello_event();
+    let sink = WebhookSink::new(url, WebhookMode::Structured).unwrap();
+
+    sink.deliver(&event).await.unwrap();
+
+    let requests = hook.requests.lock().unwrap();
+    let (headers, body) = &requests[0];
+    assert_eq!(headers["content-type"], "application/cloudevents+json");
+    let json: serde_json::Value = serde_json::from_slice(body).unwrap();
+    assert_eq!(json["id"], event.id.as_str());
+    assert_eq!(json["type"], "io.basic.hello");
+}
+
+#[tokio::test]
+async fn dispatcher_retries_server_errors() {
+    let (url, hook) = webhook(&[
+        StatusCode::SERVICE_UNAVAILABLE,
+        StatusCode::INTERNAL_SERVER_ERROR,
+    ])
+    .await;
+    let dead_letters = InMemoryBroker::new();
+    let dispatcher = SinkDispatcher::spawn(
+        Arc::new(WebhookSink::new(url, WebhookMode::Binary).unwrap()),
+        fast_retries(Some(Arc::new(BrokerSink::new(dead_letters.clone(), "dlq")))),
+    );
+
+    dispatcher.enqueue(&hello_event());
+
+    eventually(|| hook.requests.lock().unwrap().len() == 3).await;
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(dead_letters.messages().is_empty());
+}
+
+#[tokio::test]
+async fn permanent_failures_are_dead_lettered_without_retrying() {
+    let (url, hook) = webhook(&[StatusCode::BAD_REQUEST]).await;
+    let dead_letters = InMemoryBroker::new();
+    let dispatcher = SinkDispatcher::spawn(
+        Arc::new(WebhookSink::new(url, WebhookMode::Binary).unwrap()),
+        fast_retries(Some(Arc::new(BrokerSink::new(dead_letters.clone(), "dlq")))),
+    );
+    let event = hello_event();
+
+    dispatcher.enqueue(&event);
+
+    eventually(|| dead_letters.messages().len() == 1).await;
+    assert_eq!(hook.requests.lock().unwrap().len(), 1);
+    let message = &dead_letters.messages()[0];
+    assert_eq!(message.subject, "dlq.io.basic.hello");
+    assert_eq!(message.header("ce_id"), Some(event.id.as_str()));
+    assert!(
+        message
+            .header("ce_deadlettersink")
+            .unwrap()
+            .starts_with("webhook:")
+    );
+    assert!(
+        message
+            .header("ce_deadletterreason")
+            .unwrap()
+            .contains("400")
+    );
+}
+
+#[tokio::test]
+async fn exhausted_retries_are_dead_lettered() {
+    let broker = InMemoryBroker::new();
+    broker.fail_next(3);
+    let dir = TempDir::new();
+    let dead_letter = Arc::new(FileSink::new(dir.0.join("dead.jsonl")));
+    let dispatcher = SinkDispatcher::spawn(
+        Arc::new(BrokerSink::new(broker.clone(), "basic")),
+        fast_retries(Some(dead_letter.clone())),
+    );
+
+    dispatcher.enqueue(&hello_event());
+    dispatcher.enqueue(&hello_event());
+
+    eventually(|| broker.messages().len() == 1).await;
+    let lines = std::fs::read_to_string(dead_letter.path()).unwrap();
+    let dead: serde_json::Value = serde_json::from_str(lines.lines().next().unwrap()).unwrap();
+    assert_eq!(dead["deadlettersink"], "broker:basic");
+    assert!(
+        dead["deadletterreason"]
+            .as_str()
+            .unwrap()
+            .contains("after 3 attempt(s)")
+    );
+}
+
+#[tokio::test]
+async fn emitted_events_reach_configured_sinks() {
+    let broker = InMemoryBroker::new();
+    let events = EventBus::new(16).with_sink(SinkDispatcher::spawn(
+        Arc::new(BrokerSink::new(broker.clone(), "basic.events")),
+        SinkOptions::default(),
+    ));
+    let service = BasicServiceV1::new().with_event_bus(events);
+    let mut server = TestServer::start_with(Transport::Duplex, service).await;
+
+    let hello = server.client.hello("Ada").await.unwrap();
+
+    eventually(|| broker.messages().len() == 1).await;
+    let message = &broker.messages()[0];
+    assert_eq!(message.subject, "basic.events.io.basic.hello");
+    assert_eq!(message.key, "/basic/hello");
+    assert_eq!(message.header("ce_id"), Some(hello.cloud_event.id.as_str()));
+    match &hello.cloud_event.data {
+        Some(Data::ProtoData(any)) => assert_eq!(message.payload, any.value),
+        other => panic!("unexpected data: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn overflow_beyond_the_dead_letter_queue_is_counted() {
+    let dispatcher = SinkDispatcher::spawn(
+        Arc::new(StalledSink),
+        SinkOptions {
+            dead_letter: Some(Arc::new(StalledSink)),
+            queue_capacity: 1,
+            ..Default::default()
+        },
+    );
+
+    for _ in 0..10 {
+        dispatcher.enqueue(&hello_event());
+    }
+
+    // At most one event is in delivery and one queued per queue.
+    assert!(dispatcher.dropped() >= 6, "{}", dispatcher.dropped());
+}
@@ -0,0 +1,211 @@
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use basic_grpc_service_rust::{
+    clock::ManualClock,
+    sdk::basic::service::v1::{SomeServiceResponse, State},
+    state::{JobChange, StateManager, WATCH_CAPACITY},
+};
+use tokio_stream::StreamExt;
+
+fn response(name: &str) -> SomeServiceResponse {
+    SomeServiceResponse {
+        id: name.to_string(),
+        name: name.to_string(),
+        version: "test".to_string(),
+        data: None,
+    }
+}
+
+#[test]
+fn job_records_hold_state_results_and_errors_together() {
+    let state = StateManager::new();
+    state.start("job", State::Process);
+    state.add_result("job", response("service-1"));
+    state.set_error("job", Some("boom".to_string()));
+    state.finish("job", State::Error);
+
+    let record = state.job("job").unwrap();
+    assert_eq!(record.state, State::Error);
+    assert!(record.is_finished());
+    assert!(record.started_at.is_some() && record.completed_at.is_some());
+    assert_eq!(record.results, [response("service-1")]);
+    assert_eq!(state.get_errors("job"), ["boom"]);
+    assert!(state.job("unknown").is_none());
+}
+
+#[test]
+fn finished_jobs_expire_after_the_ttl() {
+    let clock = ManualClock::new(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000));
+    let state = StateManager::new()
+        .with_clock(Arc::new(clock.clone()))
+        .with_ttl(Duration::from_secs(60));
+
+    state.start("done", State::Process);
+    state.finish("done", State::Complete);
+    state.start("running", State::Process);
+
+    clock.advance(Duration::from_secs(59));
+    assert!(state.job("done").is_some());
+
+    clock.advance(Duration::from_secs(1));
+    assert!(state.job("done").is_none());
+    assert_eq!(state.evict_expired(), 1);
+    assert_eq!(state.len(), 1);
+    assert_eq!(state.job("running").unwrap().state, State::Process);
+}
+
+#[test]
+fn oldest_finished_jobs_are_evicted_beyond_capacity() {
+    let clock = ManualClock::default();
+    let state = StateManager::new()
+        .with_clock(Arc::new(clock.clone()))
+        .with_capacity(16);
+
+    state.start("running", State::Process);
+    for i in 0..100 {
+        let hash = format!("job-{}", i);
+        state.start(&hash, State::Process);
+        clock.advance(Duration::from_secs(1));
+        state.finish(&hash, State::Complete);
+    }
+
+    // Every shard keeps at most one finished job, and running jobs survive.
+    assert!(state.len() <= 17);
+    assert!(state.job("job-99").is_some());
+    assert!(state.job("running").is_some());
+}
+
+#[test]
+fn running_jobs_do_not_count_against_the_capacity() {
+    let state = StateManager::new().with_capacity(16);
+
+    for i in 0..64 {
+        state.start(&format!("running-{}", i), State::Process);
+    }
+    state.start("done", State::Process);
+    state.finish("done", State::Complete);
+
+    assert_eq!(state.len(), 65);
+    assert!(state.job("done").is_some());
+}
+
+#[tokio::test]
+async fn the_sweeper_drops_expired_jobs() {
+    let clock = ManualClock::default();
+    let state = StateManager::new()
+        .with_clock(Arc::new(clock.clone()))
+        .with_ttl(Duration::from_secs(60));
+    state.spawn_sweeper(Duration::from_secs(30));
+
+    state.start("done", State::Process);
+    state.finish("done", State::Complete);
+    state.start("running", State::Process);
+
+    clock.wait_for_sleepers(1).await;
+    clock.advance(Duration::from_secs(61));
+    clock.wait_for_sleepers(1).await;
+
+    assert_eq!(state.len(), 1);
+    assert!(state.job("running").is_some());
+}
+
+#[test]
+fn concurrent_jobs_never_observe_torn_records() {
+    let state = StateManager::new();
+    std::thread::scope(|scope| {
+        for worker in 0..8 {
+            let state = &state;
+            scope.spawn(move || {
+                for job in 0..200 {
+                    let hash = format!("{}-{}", worker, job);
+                    state.start(&hash, State::Process);
+                    state.finish(&hash, State::Complete);
+                    let (job_state, started, completed, _) = state.get_state(&hash);
+                    assert_eq!(job_state, Some(State::Complete));
+                    assert!(started.is_some() && completed.is_some());
+                }
+            });
+        }
+    });
+    assert_eq!(state.len(), 1_600);
+}
+
+#[tokio::test]
+async fn job_watch_streams_transitions_until_the_job_finishes() {
+    let state = StateManager::new();
+    let mut watch = state.subscribe("job");
+
+    state.start("job", State::Process);
+    state.start("other", State::Process);
+    state.add_result("job", response("service-1"));
+    state.finish("job", State::Complete);
+
+    let changes: Vec<_> = (&mut watch).map(|event| event.change).collect().await;
+    assert_eq!(
+        changes,
+        [JobChange::Started, JobChange::Result, JobChange::Finished]
+    );
+
+    // Late subscribers get the final record and nothing else.
+    let late: Vec<_> = state.subscribe("job").collect().await;
+    assert_eq!(late.len(), 1);
+    assert_eq!(late[0].change, JobChange::Snapshot);
+    assert_eq!(late[0].record.results, [response("service-1")]);
+}
+
+#[tokio::test]
+async fn global_subscription_sees_every_job() {
+    let state = StateManager::new();
+    let mut events = state.subscribe_all();
+
+    state.start("a", State::Process);
+    state.start("b", State::Process);
+    state.finish("a", State::Complete);
+
+    let mut seen = Vec::new();
+    for _ in 0..3 {
+        let event = events.next().await.unwrap().unwrap();
+        seen.push((event.hash, event.change));
+    }
+    assert_eq!(
+        seen,
+        [
+            ("a".to_string(), JobChange::Started),
+            ("b".to_string(), JobChange::Started),
+            ("a".to_string(), JobChange::Finished),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn lagging_job_watch_catches_up_with_a_snapshot() {
+    let state = StateManager::new();
+    let mut watch = state.subscribe("job");
+
+    state.start("job", State::Process);
+    for i in 0..WATCH_CAPACITY {
+        state.add_result("job", response(&format!("service-{}", i)));
+    }
+
+    let event = watch.next().await.unwrap();
+    assert_eq!(event.change, JobChange::Snapshot);
+    assert_eq!(event.record.results.len(), WATCH_CAPACITY);
+}
+
+#[tokio::test]
+async fn other_jobs_never_make_a_watch_lag() {
+    let state = StateManager::new();
+    let mut watch = state.subscribe("job");
+
+    state.start("job", State::Process);
+    for i in 0..4 * WATCH_CAPACITY {
+        state.start(&format!("noise-{}", i), State::Process);
+    }
+    state.finish("job", State::Complete);
+
+    let changes: Vec<_> = (&mut watch).map(|event| event.change).collect().await;
+    assert_eq!(changes, [JobChange::Started, JobChange::Finished]);
+}
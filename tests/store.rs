@@ -0,0 +1,245 @@
+use std::{
+    io,
+    path::PathBuf,
+    sync::{Arc, Mutex, mpsc},
+};
+
+use basic_grpc_service_rust::{
+    sdk::{
+        basic::service::v1::{SomeServiceData, SomeServiceResponse, State},
+        io::cloudevents::v1::CloudEvent,
+    },
+    state::{
+        INTERRUPTED, JobRecord, JobStore, JsonlStore, MemoryStore, STORE_QUEUE_CAPACITY,
+        SharedJobStore, SqliteStore, StateManager,
+    },
+};
+use uuid::Uuid;
+
+struct TempPath(PathBuf);
+
+impl TempPath {
+    fn new(extension: &str) -> Self {
+        Self(std::env::temp_dir().join(format!("basic-jobs-{}.{}", Uuid::new_v4(), extension)))
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        for suffix in ["", "-wal", "-shm"] {
+            let mut path = self.0.clone().into_os_string();
+            path.push(suffix);
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+fn response(name: &str) -> SomeServiceResponse {
+    SomeServiceResponse {
+        id: Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        version: "1.1.2".to_string(),
+        data: Some(SomeServiceData {
+            r#type: "protocol".to_string(),
+            value: "grpc".to_string(),
+        }),
+    }
+}
+
+// Runs one process lifetime against `store`, then "restarts" with a fresh
+// manager on `reopened` and checks what survived.
+fn assert_survives_restart(store: SharedJobStore, reopen: impl Fn() -> SharedJobStore) {
+    let before = StateManager::new().with_store(store);
+    before.start("done", State::Process);
+    before.add_result("done", response("service-1"));
+    before.finish("done", State::Complete);
+    before.start("running", State::Process);
+    before.add_result("running", response("service-2"));
+    before.start_once("keyed", State::Process, Some("fingerprint".to_string()));
+    before.set_event(
+        "keyed",
+        CloudEvent {
+            id: "event-1".to_string(),
+            r#type: "io.basic.hello".to_string(),
+            ..Default::default()
+        },
+    );
+    before.finish("keyed", State::Complete);
+    let done = before.job("done").unwrap();
+    let keyed = before.job("keyed").unwrap();
+    before.shutdown();
+
+    let after = StateManager::new().with_store(reopen());
+    assert_eq!(after.recover().unwrap(), 1);
+    assert_eq!(after.len(), 3);
+    assert_eq!(after.job("done").unwrap(), done);
+    assert_eq!(after.job("keyed").unwrap(), keyed);
+
+    let running = after.job("running").unwrap();
+    assert_eq!(running.state, State::Error);
+    assert_eq!(running.errors, [INTERRUPTED]);
+    assert!(running.completed_at.is_some());
+    assert_eq!(running.results[0].name, "service-2");
+
+    // The interruption itself is persisted, so a second restart finds nothing new.
+    let again = StateManager::new().with_store(reopen());
+    assert_eq!(again.recover().unwrap(), 0);
+    assert_eq!(again.job("running").unwrap().state, State::Error);
+}
+
+#[test]
+fn memory_store_recovers_interrupted_jobs() {
+    let store = MemoryStore::new();
+    let reopened = store.clone();
+    assert_survives_restart(Arc::new(store), move || Arc::new(reopened.clone()));
+}
+
+#[test]
+fn sqlite_store_recovers_interrupted_jobs() {
+    let path = TempPath::new("db");
+    let store = SqliteStore::open(&path.0).unwrap();
+    assert_eq!(store.schema_version().unwrap(), SqliteStore::SCHEMA_VERSION);
+    assert_survives_restart(Arc::new(store), || {
+        Arc::new(SqliteStore::open(&path.0).unwrap())
+    });
+}
+
+#[test]
+fn jsonl_store_recovers_interrupted_jobs() {
+    let path = TempPath::new("jsonl");
+    let store = JsonlStore::open(&path.0).unwrap();
+    assert_survives_restart(Arc::new(store), || {
+        Arc::new(JsonlStore::open(&path.0).unwrap())
+    });
+}
+
+#[test]
+fn jsonl_store_replays_deletes_and_compacts() {
+    let path = TempPath::new("jsonl");
+    let store = JsonlStore::open(&path.0).unwrap();
+    let state = StateManager::new()
+        .with_store(Arc::new(store))
+        .with_capacity(0);
+    for i in 0..10 {
+        let hash = format!("job-{}", i);
+        state.start(&hash, State::Process);
+        state.finish(&hash, State::Complete);
+    }
+    state.start("running", State::Process);
+    state.flush();
+
+    let reopened = JsonlStore::open(&path.0).unwrap();
+    let jobs = reopened.load().unwrap();
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].0, "running");
+
+    // Header plus the single live job.
+    let log = std::fs::read_to_string(&path.0).unwrap();
+    assert_eq!(log.lines().count(), 2);
+    assert!(log.starts_with("{\"schema\":1}"));
+}
+
+#[test]
+fn jsonl_store_compacts_past_its_threshold() {
+    let path = TempPath::new("jsonl");
+    let store = JsonlStore::open(&path.0)
+        .unwrap()
+        .with_compact_threshold(4096);
+    let state = StateManager::new().with_store(Arc::new(store));
+
+    state.start("job", State::Process);
+    for i in 0..200 {
+        state.add_result("job", response(&format!("service-{}", i)));
+    }
+    state.flush();
+
+    // Without compaction the log would hold all 201 puts.
+    let lines = std::fs::read_to_string(&path.0).unwrap().lines().count();
+    assert!(lines < 10, "log has {} lines", lines);
+    state.shutdown();
+    let jobs = JsonlStore::open(&path.0).unwrap().load().unwrap();
+    assert_eq!(jobs[0].1.results.len(), 200);
+}
+
+// Blocks every save until the test lets it through.
+#[derive(Debug)]
+struct GatedStore {
+    gate: Mutex<mpsc::Receiver<()>>,
+    inner: MemoryStore,
+}
+
+impl JobStore for GatedStore {
+    fn save(&self, hash: &str, record: &JobRecord) -> io::Result<()> {
+        let _ = self.gate.lock().unwrap().recv();
+        self.inner.save(hash, record)
+    }
+
+    fn delete(&self, hash: &str) -> io::Result<()> {
+        self.inner.delete(hash)
+    }
+
+    fn load(&self) -> io::Result<Vec<(String, JobRecord)>> {
+        self.inner.load()
+    }
+}
+
+#[test]
+fn slow_stores_do_not_block_the_state_manager() {
+    let (open, gate) = mpsc::channel();
+    let inner = MemoryStore::new();
+    let state = StateManager::new().with_store(Arc::new(GatedStore {
+        gate: Mutex::new(gate),
+        inner: inner.clone(),
+    }));
+
+    state.start("job", State::Process);
+    state.finish("job", State::Complete);
+    assert!(state.job("job").unwrap().is_finished());
+    assert!(inner.load().unwrap().is_empty());
+
+    open.send(()).unwrap();
+    open.send(()).unwrap();
+    state.flush();
+    assert!(inner.load().unwrap()[0].1.is_finished());
+}
+
+#[test]
+fn writes_beyond_the_queue_are_dropped_and_counted() {
+    let (open, gate) = mpsc::channel();
+    let inner = MemoryStore::new();
+    let state = StateManager::new().with_store(Arc::new(GatedStore {
+        gate: Mutex::new(gate),
+        inner: inner.clone(),
+    }));
+
+    // One write may already be held by the stalled store; the rest fill the
+    // queue and then overflow.
+    let jobs = STORE_QUEUE_CAPACITY + 2;
+    for i in 0..jobs {
+        state.start(&format!("job-{}", i), State::Process);
+    }
+    let dropped = state.dropped_writes();
+    assert!((1..=2).contains(&dropped), "dropped {}", dropped);
+
+    drop(open);
+    state.shutdown();
+    assert_eq!(inner.load().unwrap().len() as u64, jobs as u64 - dropped);
+
+    // Changes after shutdown stay in memory only.
+    state.start("late", State::Process);
+    assert!(state.job("late").is_some());
+    assert_eq!(inner.load().unwrap().len() as u64, jobs as u64 - dropped);
+}
+
+#[test]
+fn sqlite_store_rejects_newer_schema() {
+    let path = TempPath::new("db");
+    drop(SqliteStore::open(&path.0).unwrap());
+
+    let conn = rusqlite::Connection::open(&path.0).unwrap();
+    conn.pragma_update(None, "user_version", SqliteStore::SCHEMA_VERSION + 1)
+        .unwrap();
+    drop(conn);
+
+    assert!(SqliteStore::open(&path.0).is_err());
+}
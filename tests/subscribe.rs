@@ -0,0 +1,231 @@
+mod support;
+
+use std::collections::HashMap;
+
+use basic_grpc_service_rust::{
+    auth::{ApiKey, AuthConfig, Authenticator, SUBSCRIBE_ALL_SCOPE, SUBSCRIBE_SCOPE},
+    client::{BasicClient, ClientConfig, ClientError},
+    events::{EventBus, EventFilter},
+    sdk::{
+        basic::service::v1::{
+            BackgroundResponseEvent, HelloResponseEvent, SubscribeLagEvent, SubscribeRequest,
+        },
+        io::cloudevents::v1::{
+            CloudEvent,
+            cloud_event::{CloudEventAttributeValue, cloud_event_attribute_value::Attr},
+        },
+    },
+    service::BasicServiceV1,
+    utils,
+};
+use support::{TestServer, Transport};
+use tokio_stream::StreamExt;
+use tonic::Code;
+
+fn event(r#type: &str, source: &str, attributes: &[(&str, Attr)]) -> CloudEvent {
+    CloudEvent {
+        id: "1".to_string(),
+        source: source.to_string(),
+        spec_version: "1.0".to_string(),
+        r#type: r#type.to_string(),
+        attributes: attributes
+            .iter()
+            .map(|(key, attr)| {
+                (
+                    key.to_string(),
+                    CloudEventAttributeValue {
+                        attr: Some(attr.clone()),
+                    },
+                )
+            })
+            .collect(),
+        data: None,
+    }
+}
+
+#[tokio::test]
+async fn subscribers_receive_events_emitted_for_other_callers() {
+    let mut server = TestServer::start(Transport::Duplex).await;
+    let mut all = server.client.clone();
+    let mut events = all.subscribe(SubscribeRequest::default()).await.unwrap();
+
+    server.client.hello("Ada").await.unwrap();
+    let hello = events.next().await.unwrap().unwrap();
+    assert_eq!(hello.r#type, "io.basic.hello");
+    let greeting: HelloResponseEvent = utils::decode_cloud_event(&hello).unwrap();
+    assert_eq!(greeting.greeting, "Hello, Ada!");
+
+    let updates: Vec<_> = server.client.background(0).await.unwrap().collect().await;
+    for update in updates {
+        let published = events.next().await.unwrap().unwrap();
+        assert_eq!(published.id, update.unwrap().cloud_event.id);
+        utils::decode_cloud_event::<BackgroundResponseEvent>(&published).unwrap();
+    }
+}
+
+#[tokio::test]
+async fn subscribers_only_receive_matching_types() {
+    let mut server = TestServer::start(Transport::Duplex).await;
+    let mut watcher = server.client.clone();
+    let mut hellos = watcher
+        .subscribe(SubscribeRequest {
+            types: vec!["io.basic.hello".to_string()],
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let _: Vec<_> = server.client.background(0).await.unwrap().collect().await;
+    server.client.hello("Grace").await.unwrap();
+
+    let first = hellos.next().await.unwrap().unwrap();
+    assert_eq!(first.r#type, "io.basic.hello");
+}
+
+#[tokio::test]
+async fn talk_turns_are_published_only_when_enabled() {
+    let service = BasicServiceV1::new().with_event_bus(EventBus::default().with_talk(true));
+    let mut server = TestServer::start_with(Transport::Duplex, service).await;
+    let mut watcher = server.client.clone();
+    let mut events = watcher
+        .subscribe(SubscribeRequest::default())
+        .await
+        .unwrap();
+
+    let mut talk = server.client.talk().await.unwrap().with_cloud_events(true);
+    let answer = talk.ask("hello").await.unwrap();
+    let published = events.next().await.unwrap().unwrap();
+    assert_eq!(published.r#type, "io.basic.talk");
+    assert_eq!(Some(published), answer.cloud_event);
+
+    let filter = EventFilter::default();
+    let bus = EventBus::default();
+    let mut quiet = bus.subscribe(filter);
+    bus.publish_talk(&event("io.basic.talk", "/basic/talk", &[]));
+    bus.publish(&event("io.basic.hello", "/basic/hello", &[]));
+    let next = quiet.next().await.unwrap().unwrap();
+    assert_eq!(next.cloud_event.unwrap().r#type, "io.basic.hello");
+}
+
+#[test]
+fn filters_match_type_source_and_attributes() {
+    let hello = event(
+        "io.basic.hello",
+        "/basic/hello",
+        &[
+            ("principal", Attr::CeString("ada".to_string())),
+            ("ended", Attr::CeBoolean(true)),
+        ],
+    );
+
+    let filter = |types: &[&str], sources: &[&str], attributes: &[(&str, &str)]| {
+        EventFilter::from(SubscribeRequest {
+            types: types.iter().map(|s| s.to_string()).collect(),
+            sources: sources.iter().map(|s| s.to_string()).collect(),
+            attributes: attributes
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>(),
+        })
+    };
+
+    assert!(filter(&[], &[], &[]).matches(&hello));
+    assert!(filter(&["io.basic.talk", "io.basic.hello"], &[], &[]).matches(&hello));
+    assert!(!filter(&["io.basic.talk"], &[], &[]).matches(&hello));
+    assert!(filter(&[], &["/basic/hello"], &[]).matches(&hello));
+    assert!(!filter(&[], &["/basic/talk"], &[]).matches(&hello));
+    assert!(filter(&[], &[], &[("principal", "ada"), ("ended", "true")]).matches(&hello));
+    assert!(!filter(&[], &[], &[("principal", "grace")]).matches(&hello));
+    assert!(!filter(&[], &[], &[("missing", "x")]).matches(&hello));
+}
+
+#[tokio::test]
+async fn lagging_subscribers_get_a_notice_instead_of_stalling_publishers() {
+    let bus = EventBus::new(2);
+    let mut events = bus.subscribe(EventFilter::default());
+
+    for i in 0..5 {
+        bus.publish(&event("io.basic.hello", &format!("/basic/{}", i), &[]));
+    }
+
+    let notice = events.next().await.unwrap().unwrap().cloud_event.unwrap();
+    assert_eq!(notice.r#type, "io.basic.subscribe.lagged");
+    let lag: SubscribeLagEvent = utils::decode_cloud_event(&notice).unwrap();
+    assert_eq!(lag.missed, 3);
+
+    let next = events.next().await.unwrap().unwrap().cloud_event.unwrap();
+    assert_eq!(next.source, "/basic/3");
+}
+
+fn api_key(key: &str, scopes: &[&str]) -> ApiKey {
+    ApiKey {
+        key: key.to_string(),
+        subject: key.to_string(),
+        scopes: scopes.iter().map(|scope| scope.to_string()).collect(),
+    }
+}
+
+async fn authenticated_server() -> TestServer {
+    let authenticator = Authenticator::new(AuthConfig {
+        api_keys: vec![
+            api_key("ada", &[SUBSCRIBE_SCOPE]),
+            api_key("grace", &[]),
+            api_key("ops", &[SUBSCRIBE_SCOPE, SUBSCRIBE_ALL_SCOPE]),
+        ],
+        jwks_file: None,
+        issuer: None,
+        audience: None,
+        algorithms: Vec::new(),
+        methods: HashMap::new(),
+        public: Vec::new(),
+    })
+    .unwrap();
+    TestServer::start_configured(Transport::Tcp, BasicServiceV1::new(), |builder| {
+        builder.authenticator(Some(authenticator))
+    })
+    .await
+}
+
+async fn client_for(server: &TestServer, key: &str) -> BasicClient {
+    BasicClient::connect(ClientConfig {
+        endpoint: format!("http://{}", server.addr.unwrap()),
+        ca_cert: None,
+        token: Some(key.to_string()),
+        ..Default::default()
+    })
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn subscribing_needs_the_subscribe_scope() {
+    let server = authenticated_server().await;
+    let mut grace = client_for(&server, "grace").await;
+
+    let Err(ClientError::Status(status)) = grace.subscribe(SubscribeRequest::default()).await
+    else {
+        panic!("expected a permission error");
+    };
+    assert_eq!(status.code(), Code::PermissionDenied);
+}
+
+#[tokio::test]
+async fn subscribers_only_see_their_own_events_without_the_all_scope() {
+    let server = authenticated_server().await;
+    let mut ada = client_for(&server, "ada").await;
+    let mut grace = client_for(&server, "grace").await;
+    let mut ops = client_for(&server, "ops").await;
+    let mut own = ada.subscribe(SubscribeRequest::default()).await.unwrap();
+    let mut all = ops.subscribe(SubscribeRequest::default()).await.unwrap();
+
+    grace.hello("Grace").await.unwrap();
+    let hello = ada.hello("Ada").await.unwrap();
+
+    let seen = own.next().await.unwrap().unwrap();
+    assert_eq!(seen.id, hello.cloud_event.id);
+
+    let first = all.next().await.unwrap().unwrap();
+    let second = all.next().await.unwrap().unwrap();
+    assert_ne!(first.id, hello.cloud_event.id);
+    assert_eq!(second.id, hello.cloud_event.id);
+}
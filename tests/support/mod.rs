@@ -0,0 +1,167 @@
+#![allow(dead_code)]
+
+use std::{net::SocketAddr, path::PathBuf};
+
+use basic_grpc_service_rust::{
+    client::{BasicClient, ClientConfig},
+    server::ServerBuilder,
+    service::BasicServiceV1,
+};
+use hyper_util::rt::TokioIo;
+use rcgen::{BasicConstraints, CertificateParams, IsCa, KeyPair};
+use tokio::{net::TcpListener, sync::oneshot, task::JoinHandle};
+use tonic::transport::{Endpoint, Identity, Uri, server::TcpIncoming};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    // Plaintext HTTP/2 on an ephemeral localhost port.
+    Tcp,
+    // TLS on an ephemeral localhost port with a throwaway CA.
+    Tls,
+    // In-memory duplex stream, no socket involved.
+    Duplex,
+}
+
+pub struct TestServer {
+    pub client: BasicClient,
+    pub addr: Option<SocketAddr>,
+    shutdown: Option<oneshot::Sender<()>>,
+    handle: JoinHandle<Result<(), tonic::transport::Error>>,
+    ca_file: Option<PathBuf>,
+}
+
+impl TestServer {
+    pub async fn start(transport: Transport) -> Self {
+        Self::start_with(transport, BasicServiceV1::default()).await
+    }
+
+    pub async fn start_with(transport: Transport, service: BasicServiceV1) -> Self {
+        Self::start_configured(transport, service, |builder| builder).await
+    }
+
+    // Like `start_with`, letting the test adjust the server before it starts.
+    pub async fn start_configured(
+        transport: Transport,
+        service: BasicServiceV1,
+        configure: impl FnOnce(ServerBuilder) -> ServerBuilder,
+    ) -> Self {
+        let (shutdown, signal) = oneshot::channel::<()>();
+        let signal = async {
+            let _ = signal.await;
+        };
+
+        match transport {
+            Transport::Duplex => {
+                let (client_io, server_io) = tokio::io::duplex(64 * 1024);
+                // keep the incoming stream open, the server shuts down once it ends
+                let incoming = tokio_stream::StreamExt::chain(
+                    tokio_stream::once(Ok::<_, std::io::Error>(server_io)),
+                    tokio_stream::pending(),
+                );
+                let router = configure(ServerBuilder::new(service)).build().unwrap();
+                let handle = tokio::spawn(router.serve_with_incoming_shutdown(incoming, signal));
+
+                let mut client_io = Some(client_io);
+                let channel = Endpoint::try_from("http://in-memory.test")
+                    .unwrap()
+                    .connect_with_connector(tower::service_fn(move |_: Uri| {
+                        let io = client_io.take();
+                        async move {
+                            io.map(TokioIo::new).ok_or_else(|| {
+                                std::io::Error::other("duplex transport already connected")
+                            })
+                        }
+                    }))
+                    .await
+                    .expect("connect over duplex transport");
+
+                Self {
+                    client: BasicClient::from_channel(channel, ClientConfig::default()),
+                    addr: None,
+                    shutdown: Some(shutdown),
+                    handle,
+                    ca_file: None,
+                }
+            }
+            Transport::Tcp | Transport::Tls => {
+                let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+                let addr = listener.local_addr().unwrap();
+                let incoming = TcpIncoming::from(listener);
+
+                let mut builder = configure(ServerBuilder::new(service));
+                let mut ca_file = None;
+                if transport == Transport::Tls {
+                    let (ca_pem, identity) = throwaway_certs();
+                    let path =
+                        std::env::temp_dir().join(format!("basic-test-ca-{}.pem", Uuid::new_v4()));
+                    std::fs::write(&path, ca_pem).unwrap();
+                    ca_file = Some(path);
+                    builder = builder.tls(identity);
+                }
+                let router = builder.build().unwrap();
+                let handle = tokio::spawn(router.serve_with_incoming_shutdown(incoming, signal));
+
+                let scheme = if transport == Transport::Tls {
+                    "https"
+                } else {
+                    "http"
+                };
+                let config = ClientConfig {
+                    endpoint: format!("{}://{}", scheme, addr),
+                    ca_cert: ca_file.clone(),
+                    domain: "localhost".to_string(),
+                    ..Default::default()
+                };
+                let client = BasicClient::connect(config)
+                    .await
+                    .expect("connect to test server");
+
+                Self {
+                    client,
+                    addr: Some(addr),
+                    shutdown: Some(shutdown),
+                    handle,
+                    ca_file,
+                }
+            }
+        }
+    }
+
+    // Signals a graceful shutdown and waits for the server task to finish.
+    pub async fn shutdown(mut self) -> Result<(), tonic::transport::Error> {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        (&mut self.handle).await.expect("server task panicked")
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(path) = self.ca_file.take() {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+// Generates a CA and a `localhost` leaf signed by it.
+fn throwaway_certs() -> (String, Identity) {
+    let ca_key = KeyPair::generate().unwrap();
+    let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+    ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    let ca = ca_params.self_signed(&ca_key).unwrap();
+
+    let leaf_key = KeyPair::generate().unwrap();
+    let leaf_params =
+        CertificateParams::new(vec!["localhost".to_string(), "127.0.0.1".to_string()]).unwrap();
+    let leaf = leaf_params.signed_by(&leaf_key, &ca, &ca_key).unwrap();
+
+    (
+        ca.pem(),
+        Identity::from_pem(leaf.pem(), leaf_key.serialize_pem()),
+    )
+}
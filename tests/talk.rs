@@ -0,0 +1,74 @@
+mod support;
+
+use basic_grpc_service_rust::{
+    sdk::basic::service::v1::TalkResponseEvent,
+    talk::{
+        globals::REGEX_RESPONSES,
+        talk::{DEFAULT_RULE, GOODBYE_RULE},
+    },
+    utils,
+};
+use support::{TestServer, Transport};
+
+#[tokio::test]
+async fn talk_numbers_turns_and_reports_rules() {
+    let mut server = TestServer::start(Transport::Duplex).await;
+    let mut talk = server.client.talk().await.unwrap();
+
+    let first = talk.ask("I need a holiday").await.unwrap();
+    assert_eq!(first.sequence, 1);
+    assert_eq!(first.rule, "need");
+    assert_eq!(first.fragment, "a holiday");
+    assert!(!first.ended);
+    assert!(first.cloud_event.is_none());
+
+    let second = talk.ask("zzz").await.unwrap();
+    assert_eq!(second.sequence, 2);
+    assert_eq!(second.rule, DEFAULT_RULE);
+}
+
+#[tokio::test]
+async fn talk_wraps_answers_in_cloud_events_on_request() {
+    let mut server = TestServer::start(Transport::Duplex).await;
+    let mut talk = server.client.talk().await.unwrap().with_cloud_events(true);
+
+    let response = talk.ask("I feel tired").await.unwrap();
+
+    let event: TalkResponseEvent =
+        utils::decode_cloud_event(response.cloud_event.as_ref().unwrap()).unwrap();
+    assert_eq!(event.sequence, response.sequence);
+    assert_eq!(event.rule, "i-feel");
+    assert_eq!(event.answer, response.answer);
+}
+
+#[test]
+fn rule_ids_are_unique_and_short() {
+    let mut ids: Vec<_> = REGEX_RESPONSES.iter().map(|(id, _, _)| *id).collect();
+    ids.extend([DEFAULT_RULE, GOODBYE_RULE]);
+    for id in &ids {
+        assert!(
+            !id.is_empty() && id.chars().all(|c| c.is_ascii_lowercase() || c == '-'),
+            "{}",
+            id
+        );
+    }
+    let count = ids.len();
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), count);
+}
+
+#[tokio::test]
+async fn talk_goodbye_ends_conversation() {
+    let mut server = TestServer::start(Transport::Tcp).await;
+    let mut talk = server.client.talk().await.unwrap();
+
+    talk.ask("hello").await.unwrap();
+    let goodbye = talk.ask("Goodbye!").await.unwrap();
+
+    assert!(goodbye.ended);
+    assert_eq!(goodbye.rule, GOODBYE_RULE);
+
+    let mut inbound = talk.close();
+    assert!(inbound.message().await.unwrap().is_none());
+}
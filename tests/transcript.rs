@@ -0,0 +1,127 @@
+mod support;
+
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use basic_grpc_service_rust::{
+    clock::ManualClock,
+    service::BasicServiceV1,
+    talk::{
+        SessionRng, Transcript, TranscriptFormat, TranscriptRecorder, answer,
+        transcript::{decode_entries, load, replay},
+    },
+};
+use prost_types::Timestamp;
+use rand::SeedableRng;
+use support::{TestServer, Transport};
+use uuid::Uuid;
+
+fn session(seed: u64, inputs: &[&str]) -> Transcript {
+    let mut transcript = Transcript::new(seed);
+    let mut rng = SessionRng::seed_from_u64(seed);
+    for (i, input) in inputs.iter().enumerate() {
+        let time = Timestamp {
+            seconds: 1_700_000_000 + i as i64,
+            nanos: 0,
+        };
+        transcript.record(input, &answer(input, &mut rng), time);
+    }
+    transcript
+}
+
+const INPUTS: [&str; 4] = ["Hello", "I feel tired", "I need a holiday", "Goodbye"];
+
+#[test]
+fn transcripts_round_trip_in_both_formats() {
+    let transcript = session(42, &INPUTS);
+
+    for format in [
+        TranscriptFormat::JsonLines,
+        TranscriptFormat::CloudEventBatch,
+    ] {
+        let bytes = transcript.encode(format).unwrap();
+        let decoded = decode_entries(&bytes, format).unwrap();
+        assert_eq!(decoded, transcript.entries(), "{:?}", format);
+    }
+}
+
+#[test]
+fn json_lines_keep_rfc3339_timestamps() {
+    let transcript = session(1, &["Hello"]);
+
+    let bytes = transcript.encode(TranscriptFormat::JsonLines).unwrap();
+    let line: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+
+    let timestamp = line["timestamp"].as_str().unwrap();
+    assert_eq!(timestamp, transcript.entries()[0].timestamp.to_string());
+    assert!(timestamp.ends_with('Z'), "{}", timestamp);
+}
+
+#[test]
+fn recorded_sessions_replay_without_divergence() {
+    let transcript = session(7, &INPUTS);
+
+    assert!(replay(transcript.entries()).is_empty());
+}
+
+#[test]
+fn each_session_replays_from_its_own_seed() {
+    let mut entries = session(1, &INPUTS).entries().to_vec();
+    entries.extend_from_slice(session(2, &INPUTS).entries());
+    entries.reverse();
+
+    assert!(replay(&entries).is_empty());
+}
+
+#[test]
+fn changed_answers_are_reported() {
+    let mut entries = session(3, &INPUTS).entries().to_vec();
+    entries[1].answer = "Something else entirely.".to_string();
+
+    let divergences = replay(&entries);
+
+    assert_eq!(divergences.len(), 1);
+    assert_eq!(divergences[0].seq, 2);
+    assert_eq!(divergences[0].expected_answer, "Something else entirely.");
+}
+
+#[tokio::test]
+async fn turns_are_timed_on_the_service_clock() {
+    let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let clock = ManualClock::new(start);
+    let dir = std::env::temp_dir().join(format!("basic-transcripts-{}", Uuid::new_v4()));
+    let service = BasicServiceV1::new()
+        .with_clock(Arc::new(clock.clone()))
+        .with_transcripts(Some(TranscriptRecorder::new(
+            &dir,
+            TranscriptFormat::JsonLines,
+        )));
+    let mut server = TestServer::start_with(Transport::Duplex, service).await;
+
+    let mut talk = server.client.talk().await.unwrap();
+    talk.ask("Hello").await.unwrap();
+    drop(talk.close());
+
+    let entries = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            // The file may still be in the middle of being written.
+            let saved = std::fs::read_dir(&dir)
+                .ok()
+                .and_then(|mut files| files.next()?.ok())
+                .and_then(|file| load(&file.path()).ok())
+                .filter(|entries| !entries.is_empty());
+            if let Some(entries) = saved {
+                break entries;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].timestamp, Timestamp::from(start));
+}
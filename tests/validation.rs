@@ -0,0 +1,141 @@
+mod support;
+
+use std::collections::HashMap;
+
+use basic_grpc_service_rust::{
+    client::ClientError,
+    sdk::basic::service::v1::{HelloRequest, SubscribeRequest},
+    service::BasicServiceV1,
+    validation::{MAX_PROCESSES, Validate},
+};
+use serde_json::{Value, json};
+use support::{TestServer, Transport};
+use tonic::{Code, Status};
+use tonic_types::StatusExt;
+
+fn status(error: ClientError) -> Status {
+    match error {
+        ClientError::Status(status) => status,
+        error => panic!("expected a status, got {}", error),
+    }
+}
+
+fn violated_fields(status: &Status) -> Vec<String> {
+    assert_eq!(status.code(), Code::InvalidArgument);
+    status
+        .get_details_bad_request()
+        .expect("BadRequest")
+        .field_violations
+        .into_iter()
+        .map(|violation| violation.field)
+        .collect()
+}
+
+#[tokio::test]
+async fn blank_hello_is_rejected() {
+    let mut server = TestServer::start(Transport::Duplex).await;
+
+    for message in ["", "  \n"] {
+        let error = server.client.hello(message).await.unwrap_err();
+        assert_eq!(violated_fields(&status(error)), ["message"]);
+    }
+}
+
+#[tokio::test]
+async fn negative_processes_are_rejected() {
+    let mut server = TestServer::start(Transport::Duplex).await;
+
+    let error = server.client.background(-3).await.err().unwrap();
+
+    let status = status(error);
+    assert_eq!(violated_fields(&status), ["processes"]);
+    assert_eq!(status.message(), "processes: must not be negative");
+}
+
+#[tokio::test]
+async fn processes_are_capped() {
+    let mut server = TestServer::start(Transport::Duplex).await;
+
+    let error = server
+        .client
+        .background(MAX_PROCESSES + 1)
+        .await
+        .err()
+        .unwrap();
+
+    let status = status(error);
+    assert_eq!(violated_fields(&status), ["processes"]);
+    assert_eq!(status.message(), "processes: must be at most 100");
+}
+
+#[tokio::test]
+async fn blank_talk_messages_end_the_stream() {
+    let mut server = TestServer::start(Transport::Duplex).await;
+    let mut talk = server.client.talk().await.unwrap();
+
+    talk.ask("Hello").await.unwrap();
+    talk.send("").await.unwrap();
+
+    let error = talk.recv().await.unwrap_err();
+    assert_eq!(violated_fields(&status(error)), ["message"]);
+}
+
+#[tokio::test]
+async fn subscribe_filters_are_checked() {
+    let mut server = TestServer::start(Transport::Duplex).await;
+
+    let error = server
+        .client
+        .subscribe(SubscribeRequest {
+            types: vec!["io.basic.hello".to_string(), String::new()],
+            sources: Vec::new(),
+            attributes: HashMap::from([(String::new(), "x".to_string())]),
+        })
+        .await
+        .err()
+        .unwrap();
+
+    assert_eq!(violated_fields(&status(error)), ["types", "attributes"]);
+}
+
+#[tokio::test]
+async fn connect_callers_get_field_violations() {
+    let server = TestServer::start_configured(Transport::Tcp, BasicServiceV1::new(), |builder| {
+        builder.connect(true)
+    })
+    .await;
+
+    let response = reqwest::Client::new()
+        .post(format!(
+            "http://{}/basic.v1.BasicService/Hello",
+            server.addr.unwrap()
+        ))
+        .header("content-type", "application/json")
+        .body(json!({ "message": "" }).to_string())
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 400);
+    let error: Value = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+    assert_eq!(error["code"], "invalid_argument");
+    assert_eq!(error["message"], "message: must not be blank");
+    let types: Vec<_> = error["details"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|detail| detail["type"].as_str().unwrap())
+        .collect();
+    assert!(types.contains(&"google.rpc.BadRequest"), "{:?}", types);
+}
+
+#[test]
+fn valid_messages_pass() {
+    let request = HelloRequest {
+        message: "Ada".to_string(),
+        ..Default::default()
+    };
+
+    assert!(request.validate().is_ok());
+    assert!(SubscribeRequest::default().validate().is_ok());
+}